@@ -0,0 +1,27 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Exposes the resolved versions of the GUI and audio crates for the About panel.
+fn main() {
+    let lock = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock.display());
+    let lock = fs::read_to_string(lock).unwrap_or_default();
+    for (krate, var) in [("iced", "ICED_VERSION"), ("rodio", "RODIO_VERSION")] {
+        println!(
+            "cargo:rustc-env={}={}",
+            var,
+            locked_version(&lock, krate).unwrap_or("unknown")
+        );
+    }
+}
+
+fn locked_version<'a>(lock: &'a str, krate: &str) -> Option<&'a str> {
+    let name = format!("name = \"{}\"", krate);
+    let mut lines = lock.lines();
+    lines.find(|line| *line == name)?;
+    lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+}
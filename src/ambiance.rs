@@ -0,0 +1,61 @@
+use iced::gradient::Linear;
+use iced::widget::container;
+use iced::{Color, Radians, Theme};
+
+/// A window background that drifts through the color wheel once per bar.
+///
+/// `bar_phase` is how far through the bar we are, from `0.0` to `1.0`, and `intensity` how far
+/// the theme's own background is tinted, from `0.0` (not at all) to `1.0`.
+pub fn appearance(theme: &Theme, bar_phase: f32, intensity: f32) -> container::Appearance {
+    let base = theme.palette().background;
+    // The two ends sit a sixth of the wheel apart so the gradient stays soft
+    let from = tint(base, hue(bar_phase), intensity);
+    let to = tint(base, hue(bar_phase + 1.0 / 6.0), intensity);
+    container::Appearance {
+        background: Some(
+            Linear::new(Radians(std::f32::consts::FRAC_PI_4))
+                .add_stop(0.0, from)
+                .add_stop(1.0, to)
+                .into(),
+        ),
+        ..Default::default()
+    }
+}
+
+/// A window background lit up by a beat, in the theme's primary color on the downbeat and
+/// half as bright on other beats.
+///
+/// `strength` fades from `1.0` right on the beat to `0.0`, the plain background.
+pub fn flash(theme: &Theme, downbeat: bool, strength: f32) -> container::Appearance {
+    let palette = theme.palette();
+    let amount = if downbeat { strength } else { strength * 0.5 };
+    container::Appearance {
+        background: Some(tint(palette.background, palette.primary, amount).into()),
+        ..Default::default()
+    }
+}
+
+/// A fully saturated color at `turns` around the color wheel; whole turns wrap, so a bar ends
+/// on the same color the next one starts on.
+fn hue(turns: f32) -> Color {
+    let h = turns.rem_euclid(1.0) * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    Color::from_rgb(r, g, b)
+}
+
+fn tint(base: Color, tint: Color, amount: f32) -> Color {
+    let mix = |a: f32, b: f32| a + (b - a) * amount;
+    Color::from_rgb(
+        mix(base.r, tint.r),
+        mix(base.g, tint.g),
+        mix(base.b, tint.b),
+    )
+}
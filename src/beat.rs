@@ -0,0 +1,493 @@
+/// A sound for the player to make.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Beat {
+    FirstBeat,
+    /// The lighter accent starting each group of the bar after the first.
+    MediumAccent,
+    OffBeat,
+    /// An off-beat played quieter, leading into the downbeat.
+    SoftOffBeat,
+    /// A subdivision of the beat other than the off-beat halfway through it.
+    Subdivision,
+    Beat,
+    /// A beat of the polyrhythm's second voice.
+    PolyBeat,
+    /// Not a beat: a short cue when the metronome starts or stops.
+    Cue(Cue),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cue {
+    Start,
+    Stop,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub enum MetroState {
+    Stopped,
+    /// Counting in, with `beat` of the bar last played and `bar_remaining` bars left
+    /// including this one.
+    CountIn {
+        bar_remaining: u32,
+        beat: u32,
+    },
+    FirstBeat,
+    Beat(u32),
+    /// Paused after playing `beat` of the bar, to carry on from the beat after it.
+    Paused(u32),
+}
+
+impl MetroState {
+    /// The state to start playback from so that the first beat played is `start_beat` of the
+    /// bar, counting from 1.
+    pub fn starting_on(start_beat: u32, bar: u32) -> MetroState {
+        match start_beat {
+            0 | 1 => MetroState::Beat(bar - 1),
+            2 => MetroState::FirstBeat,
+            beat => MetroState::Beat(beat - 2),
+        }
+    }
+
+    /// The state to start from to count in for `bars` bars. The take after the count-in
+    /// always starts on its downbeat.
+    pub fn counting_in(bars: u32, bar: u32) -> MetroState {
+        MetroState::CountIn {
+            bar_remaining: bars + 1,
+            beat: bar - 1,
+        }
+    }
+
+    /// The beat to play on the next main tick and the state after playing it, or `None` when
+    /// stopped. Starting from `Beat(bar - 1)` makes the first click the downbeat.
+    pub fn advance(&self, bar: u32) -> Option<(Beat, MetroState)> {
+        match *self {
+            MetroState::CountIn {
+                bar_remaining,
+                beat,
+            } if beat >= bar - 1 => Some((
+                Beat::FirstBeat,
+                if bar_remaining > 1 {
+                    MetroState::CountIn {
+                        bar_remaining: bar_remaining - 1,
+                        beat: 0,
+                    }
+                } else {
+                    MetroState::FirstBeat
+                },
+            )),
+            MetroState::CountIn {
+                bar_remaining,
+                beat,
+            } => Some((
+                Beat::Beat,
+                MetroState::CountIn {
+                    bar_remaining,
+                    beat: beat + 1,
+                },
+            )),
+            // A bar of one beat is nothing but downbeats
+            MetroState::FirstBeat if bar > 1 => Some((Beat::Beat, MetroState::Beat(1))),
+            MetroState::FirstBeat => Some((Beat::FirstBeat, MetroState::FirstBeat)),
+            MetroState::Beat(beat) if beat >= bar - 1 => {
+                Some((Beat::FirstBeat, MetroState::FirstBeat))
+            }
+            MetroState::Beat(beat) => Some((Beat::Beat, MetroState::Beat(beat + 1))),
+            MetroState::Stopped | MetroState::Paused(_) => None,
+        }
+    }
+
+    /// The off-beat to play after the beat last played, if any. The one leading into the
+    /// downbeat, which is the `last` subdivision of the last beat, follows `pickup`.
+    pub fn off_beat(&self, bar: u32, pickup: PickupOffBeat, last: bool) -> Option<Beat> {
+        match self.index()? {
+            beat if beat == bar - 1 && last => match pickup {
+                PickupOffBeat::Play => Some(Beat::OffBeat),
+                PickupOffBeat::Soften => Some(Beat::SoftOffBeat),
+                PickupOffBeat::Skip => None,
+            },
+            _ => Some(Beat::OffBeat),
+        }
+    }
+
+    /// The beat of the bar last played, counting from 0.
+    pub fn index(&self) -> Option<u32> {
+        match *self {
+            MetroState::FirstBeat => Some(0),
+            MetroState::Beat(beat)
+            | MetroState::CountIn { beat, .. }
+            | MetroState::Paused(beat) => Some(beat),
+            MetroState::Stopped => None,
+        }
+    }
+
+    /// How many beats of the count-in are left, counting the one last played, or `None`
+    /// when not counting in.
+    pub fn countdown(&self, bar: u32) -> Option<u32> {
+        match *self {
+            MetroState::CountIn {
+                bar_remaining,
+                beat,
+            } => Some((bar_remaining - 1) * bar + bar - beat),
+            _ => None,
+        }
+    }
+
+    /// Whether beats are being played, which they aren't while stopped or paused.
+    pub fn is_running(&self) -> bool {
+        !matches!(self, MetroState::Stopped | MetroState::Paused(_))
+    }
+
+    /// The state to resume a pause from, so the next beat follows the one paused on without
+    /// a count-in.
+    pub fn resumed(&self) -> MetroState {
+        match *self {
+            MetroState::Paused(0) => MetroState::FirstBeat,
+            MetroState::Paused(beat) => MetroState::Beat(beat),
+            ref state => state.clone(),
+        }
+    }
+
+    /// The position after the tempo changes. A tempo change only alters the interval, so
+    /// the count carries on unless `restart_bar` asks for the next beat to be a downbeat; a
+    /// bar that has only just started is left alone so its downbeat isn't repeated.
+    pub fn retimed(&self, bar: u32, restart_bar: bool) -> MetroState {
+        match *self {
+            MetroState::Beat(_) if restart_bar => MetroState::Beat(bar - 1),
+            ref state => state.clone(),
+        }
+    }
+
+    /// Keeps the position inside a bar that was just resized to `bar` beats. A position past
+    /// the new end lands on the last beat, so the next beat is a single, on-time downbeat.
+    pub fn resized(&self, bar: u32) -> MetroState {
+        match *self {
+            MetroState::Beat(beat) if beat >= bar => MetroState::Beat(bar - 1),
+            MetroState::CountIn {
+                bar_remaining,
+                beat,
+            } if beat >= bar => MetroState::CountIn {
+                bar_remaining,
+                beat: bar - 1,
+            },
+            ref state => state.clone(),
+        }
+    }
+}
+
+/// Which sound a beat of the bar plays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeatSound {
+    Accent,
+    Normal,
+    OffBeat,
+    Silent,
+}
+
+impl BeatSound {
+    /// The sound a click on the beat switches to.
+    pub fn next(self) -> Self {
+        match self {
+            BeatSound::Accent => BeatSound::Normal,
+            BeatSound::Normal => BeatSound::OffBeat,
+            BeatSound::OffBeat => BeatSound::Silent,
+            BeatSound::Silent => BeatSound::Accent,
+        }
+    }
+
+    /// What a beat with this sound plays, or `None` when it's silent. The whole count-in is
+    /// heard, silent beats and all, so the take can be found by ear.
+    pub fn beat(self, counting_in: bool) -> Option<Beat> {
+        match self {
+            BeatSound::Accent => Some(Beat::FirstBeat),
+            BeatSound::OffBeat => Some(Beat::OffBeat),
+            BeatSound::Silent if !counting_in => None,
+            BeatSound::Normal | BeatSound::Silent => Some(Beat::Beat),
+        }
+    }
+}
+
+/// An accented downbeat followed by plain beats.
+pub fn default_beat_sounds(bar: u32) -> Vec<BeatSound> {
+    let mut sounds = vec![BeatSound::Normal; bar as usize];
+    sounds[0] = BeatSound::Accent;
+    sounds
+}
+
+/// What happens to the off-beat just before the downbeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickupOffBeat {
+    Play,
+    Soften,
+    Skip,
+}
+
+/// The most beats a bar can have.
+pub const MAX_BAR: u32 = 32;
+
+/// The note values a beat can be, as the bottom of a time signature.
+pub const BEAT_UNITS: [u32; 4] = [2, 4, 8, 16];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays `ticks` timer ticks from a fresh start the way the sequencer does, with `None` for
+    /// a silent tick.
+    fn run_with(
+        bar: u32,
+        subdivision: u32,
+        pickup: PickupOffBeat,
+        ticks: usize,
+    ) -> Vec<Option<Beat>> {
+        let mut state = MetroState::Beat(bar - 1);
+        let mut sub_beat = 0;
+        let mut played = Vec::new();
+        // The first beat is sent immediately on start, the rest come from the timer
+        for tick in 0..=ticks {
+            if tick > 0 && sub_beat + 1 < subdivision {
+                played.push(state.off_beat(bar, pickup, sub_beat + 2 >= subdivision));
+                sub_beat += 1;
+            } else {
+                let (beat, next) = state.advance(bar).unwrap();
+                played.push(Some(beat));
+                state = next;
+                sub_beat = 0;
+            }
+        }
+        played
+    }
+
+    fn run(bar: u32, subdivision: u32, ticks: usize) -> Vec<Beat> {
+        run_with(bar, subdivision, PickupOffBeat::Play, ticks)
+            .into_iter()
+            .map(Option::unwrap)
+            .collect()
+    }
+
+    #[test]
+    fn starts_on_the_downbeat() {
+        assert_eq!(
+            run(4, 1, 8),
+            [
+                Beat::FirstBeat,
+                Beat::Beat,
+                Beat::Beat,
+                Beat::Beat,
+                Beat::FirstBeat,
+                Beat::Beat,
+                Beat::Beat,
+                Beat::Beat,
+                Beat::FirstBeat,
+            ]
+        );
+    }
+
+    #[test]
+    fn off_beats_alternate_from_the_downbeat() {
+        assert_eq!(
+            run(2, 2, 8),
+            [
+                Beat::FirstBeat,
+                Beat::OffBeat,
+                Beat::Beat,
+                Beat::OffBeat,
+                Beat::FirstBeat,
+                Beat::OffBeat,
+                Beat::Beat,
+                Beat::OffBeat,
+                Beat::FirstBeat,
+            ]
+        );
+    }
+
+    #[test]
+    fn off_beat_before_the_downbeat_follows_the_option() {
+        let two_bars = |pickup| run_with(3, 2, pickup, 12);
+        let expected = |pickup: fn() -> Option<Beat>| {
+            let mut expected = Vec::new();
+            for _ in 0..2 {
+                expected.extend([
+                    Some(Beat::FirstBeat),
+                    Some(Beat::OffBeat),
+                    Some(Beat::Beat),
+                    Some(Beat::OffBeat),
+                    Some(Beat::Beat),
+                    pickup(),
+                ]);
+            }
+            expected.push(Some(Beat::FirstBeat));
+            expected
+        };
+        assert_eq!(
+            two_bars(PickupOffBeat::Play),
+            expected(|| Some(Beat::OffBeat))
+        );
+        assert_eq!(
+            two_bars(PickupOffBeat::Soften),
+            expected(|| Some(Beat::SoftOffBeat))
+        );
+        assert_eq!(two_bars(PickupOffBeat::Skip), expected(|| None));
+    }
+
+    #[test]
+    fn triplets_only_advance_on_the_beat() {
+        assert_eq!(
+            run_with(2, 3, PickupOffBeat::Skip, 6),
+            [
+                Some(Beat::FirstBeat),
+                Some(Beat::OffBeat),
+                Some(Beat::OffBeat),
+                Some(Beat::Beat),
+                Some(Beat::OffBeat),
+                None,
+                Some(Beat::FirstBeat),
+            ]
+        );
+    }
+
+    /// Plays main beats from `state` until `count` beats have been played.
+    fn beats_from(mut state: MetroState, bar: u32, count: usize) -> Vec<Beat> {
+        (0..count)
+            .map(|_| {
+                let (beat, next) = state.advance(bar).unwrap();
+                state = next;
+                beat
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resuming_carries_on_from_the_paused_beat() {
+        let paused = MetroState::Paused(MetroState::Beat(1).index().unwrap());
+        assert_eq!(paused.advance(4), None);
+        assert_eq!(
+            beats_from(paused.resumed(), 4, 3),
+            [Beat::Beat, Beat::Beat, Beat::FirstBeat]
+        );
+        // Paused on the downbeat goes on to the second beat, not to another downbeat
+        assert_eq!(
+            beats_from(MetroState::Paused(0).resumed(), 4, 1),
+            [Beat::Beat]
+        );
+    }
+
+    #[test]
+    fn shrinking_the_bar_mid_play_wraps_once() {
+        // On beat 7 of 8 when the bar shrinks to 4
+        let state = MetroState::Beat(6).resized(4);
+        assert_eq!(state, MetroState::Beat(3));
+        assert_eq!(
+            beats_from(state, 4, 5),
+            [
+                Beat::FirstBeat,
+                Beat::Beat,
+                Beat::Beat,
+                Beat::Beat,
+                Beat::FirstBeat,
+            ]
+        );
+    }
+
+    #[test]
+    fn shrinking_the_bar_keeps_earlier_positions() {
+        assert_eq!(MetroState::Beat(2).resized(4), MetroState::Beat(2));
+        assert_eq!(MetroState::FirstBeat.resized(2), MetroState::FirstBeat);
+        assert_eq!(MetroState::Stopped.resized(2), MetroState::Stopped);
+    }
+
+    #[test]
+    fn growing_the_bar_mid_play_extends_the_current_bar() {
+        // On beat 3 of 4 when the bar grows to 6
+        let state = MetroState::Beat(2).resized(6);
+        assert_eq!(
+            beats_from(state, 6, 4),
+            [Beat::Beat, Beat::Beat, Beat::Beat, Beat::FirstBeat]
+        );
+    }
+
+    #[test]
+    fn starts_on_the_chosen_beat() {
+        let beats = |start| beats_from(MetroState::starting_on(start, 4), 4, 3);
+        assert_eq!(beats(1), [Beat::FirstBeat, Beat::Beat, Beat::Beat]);
+        assert_eq!(beats(2), [Beat::Beat, Beat::Beat, Beat::Beat]);
+        assert_eq!(beats(3), [Beat::Beat, Beat::Beat, Beat::FirstBeat]);
+        assert_eq!(beats(4), [Beat::Beat, Beat::FirstBeat, Beat::Beat]);
+        // The indicator lights the chosen beat first
+        let (_, state) = MetroState::starting_on(3, 4).advance(4).unwrap();
+        assert_eq!(state, MetroState::Beat(2));
+    }
+
+    #[test]
+    fn count_in_leads_into_the_downbeat() {
+        let mut state = MetroState::counting_in(2, 3);
+        let mut played = Vec::new();
+        for _ in 0..7 {
+            let (beat, next) = state.advance(3).unwrap();
+            played.push((beat, matches!(next, MetroState::CountIn { .. })));
+            state = next;
+        }
+        let count_in = [Beat::FirstBeat, Beat::Beat, Beat::Beat].map(|beat| (beat, true));
+        assert_eq!(played[..3], count_in);
+        assert_eq!(played[3..6], count_in);
+        assert_eq!(played[6], (Beat::FirstBeat, false));
+        assert_eq!(state, MetroState::FirstBeat);
+    }
+
+    /// Counts the downbeats in `count` main beats from a fresh start, changing the tempo
+    /// after `change_after` of them.
+    fn downbeats_across_tempo_change(change_after: usize, restart_bar: bool) -> Vec<usize> {
+        let mut state = MetroState::starting_on(1, 4);
+        let mut downbeats = Vec::new();
+        for i in 0..12 {
+            if i == change_after {
+                state = state.retimed(4, restart_bar);
+            }
+            let (beat, next) = state.advance(4).unwrap();
+            if beat == Beat::FirstBeat {
+                downbeats.push(i);
+            }
+            state = next;
+        }
+        downbeats
+    }
+
+    #[test]
+    fn tempo_changes_keep_the_downbeat() {
+        for change_after in 0..12 {
+            assert_eq!(
+                downbeats_across_tempo_change(change_after, false),
+                [0, 4, 8],
+                "tempo changed after {} beats",
+                change_after
+            );
+        }
+    }
+
+    #[test]
+    fn restarting_the_bar_on_tempo_changes_plays_one_downbeat() {
+        // Mid-bar, the next beat becomes the downbeat and the count restarts from it
+        assert_eq!(downbeats_across_tempo_change(2, true), [0, 2, 6, 10]);
+        // Right after a downbeat nothing changes, so it isn't doubled
+        assert_eq!(downbeats_across_tempo_change(1, true), [0, 4, 8]);
+        // On the last beat the next beat was the downbeat anyway
+        assert_eq!(downbeats_across_tempo_change(4, true), [0, 4, 8]);
+    }
+
+    #[test]
+    fn count_in_counts_down_to_one() {
+        let mut state = MetroState::counting_in(2, 3);
+        let mut left = Vec::new();
+        while let MetroState::CountIn { .. } = state {
+            state = state.advance(3).unwrap().1;
+            left.extend(state.countdown(3));
+        }
+        assert_eq!(left, [6, 5, 4, 3, 2, 1]);
+        assert_eq!(state.countdown(3), None);
+    }
+
+    #[test]
+    fn stopped_does_not_advance() {
+        assert_eq!(MetroState::Stopped.advance(4), None);
+    }
+}
@@ -0,0 +1,229 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use iced::futures::channel::oneshot;
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample};
+use rodio::OutputStream;
+
+use crate::sounds::Click;
+use crate::strings::{fill, Label, Language};
+
+const CLICKS: u32 = 6;
+const CLICK_SPACING: Duration = Duration::from_millis(500);
+/// Onsets later than this after a click are treated as missing rather than as latency.
+const MAX_LATENCY: Duration = Duration::from_millis(400);
+
+/// What the microphone has heard so far, timed from the first input callback.
+#[derive(Default)]
+struct Recording {
+    started: Option<Instant>,
+    samples: Vec<f32>,
+}
+
+/// Why the latency couldn't be measured.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CalibrationError {
+    NoMicrophone,
+    /// The microphone's settings couldn't be read, for this reason.
+    MicrophoneUnavailable(String),
+    /// The microphone records in a sample format, named here, that can't be read.
+    UnsupportedFormat(String),
+    /// Recording couldn't be opened or started, for this reason.
+    RecordingFailed(String),
+    /// No output could be opened to play the clicks, for this reason.
+    NoOutput(String),
+    /// A click couldn't be played, for this reason.
+    ClickFailed(String),
+    NothingRecorded,
+    /// Too few of the clicks stood out from the recording.
+    NotHeard,
+    Stopped,
+}
+
+impl CalibrationError {
+    pub fn text(&self, language: Language) -> String {
+        match self {
+            CalibrationError::NoMicrophone => language.get(Label::NoMicrophone).to_string(),
+            CalibrationError::MicrophoneUnavailable(err) => {
+                fill(language.get(Label::MicrophoneUnavailable), &[err])
+            }
+            CalibrationError::UnsupportedFormat(format) => {
+                fill(language.get(Label::UnsupportedMicrophoneFormat), &[format])
+            }
+            CalibrationError::RecordingFailed(err) => {
+                fill(language.get(Label::RecordingFailed), &[err])
+            }
+            CalibrationError::NoOutput(err) => {
+                fill(language.get(Label::CalibrationNoOutput), &[err])
+            }
+            CalibrationError::ClickFailed(err) => {
+                fill(language.get(Label::CalibrationClickFailed), &[err])
+            }
+            CalibrationError::NothingRecorded => language.get(Label::NothingRecorded).to_string(),
+            CalibrationError::NotHeard => language.get(Label::ClicksNotHeard).to_string(),
+            CalibrationError::Stopped => language.get(Label::CalibrationStopped).to_string(),
+        }
+    }
+}
+
+/// Plays a few clicks while recording the default microphone, resolving to the median delay
+/// between playing a click and hearing it (the round-trip latency).
+pub async fn measure_latency(click: Click) -> Result<Duration, CalibrationError> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let _ = tx.send(run(click));
+    });
+    rx.await.unwrap_or(Err(CalibrationError::Stopped))
+}
+
+fn run(click: Click) -> Result<Duration, CalibrationError> {
+    let device = cpal::default_host()
+        .default_input_device()
+        .ok_or(CalibrationError::NoMicrophone)?;
+    let config = device
+        .default_input_config()
+        .map_err(|err| CalibrationError::MicrophoneUnavailable(err.to_string()))?;
+    let sample_rate = config.sample_rate().0;
+    let recording = Arc::new(Mutex::new(Recording::default()));
+
+    let stream_config = config.config();
+    let stream = match config.sample_format() {
+        SampleFormat::F32 => record::<f32>(&device, &stream_config, &recording),
+        SampleFormat::I16 => record::<i16>(&device, &stream_config, &recording),
+        SampleFormat::U16 => record::<u16>(&device, &stream_config, &recording),
+        SampleFormat::I32 => record::<i32>(&device, &stream_config, &recording),
+        format => return Err(CalibrationError::UnsupportedFormat(format!("{:?}", format))),
+    }?;
+    stream
+        .play()
+        .map_err(|err| CalibrationError::RecordingFailed(err.to_string()))?;
+
+    let (_output, output) =
+        OutputStream::try_default().map_err(|err| CalibrationError::NoOutput(err.to_string()))?;
+    // Let the input settle before the first click
+    thread::sleep(CLICK_SPACING);
+    let mut played = Vec::new();
+    for _ in 0..CLICKS {
+        played.push(Instant::now());
+        output
+            .play_raw(click.clone())
+            .map_err(|err| CalibrationError::ClickFailed(err.to_string()))?;
+        thread::sleep(CLICK_SPACING);
+    }
+    drop(stream);
+
+    let recording = recording.lock().unwrap();
+    let started = recording.started.ok_or(CalibrationError::NothingRecorded)?;
+    let clicks = played
+        .iter()
+        .map(|at| at.saturating_duration_since(started))
+        .collect::<Vec<_>>();
+    detect_latency(&recording.samples, sample_rate, &clicks).ok_or(CalibrationError::NotHeard)
+}
+
+fn record<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    recording: &Arc<Mutex<Recording>>,
+) -> Result<cpal::Stream, CalibrationError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let recording = Arc::clone(recording);
+    let channels = config.channels as usize;
+    let sample_rate = config.sample_rate.0;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mut recording = recording.lock().unwrap();
+                // The first buffer arrives once it's full, so it began that long ago
+                recording.started.get_or_insert_with(|| {
+                    let now = Instant::now();
+                    let frames = (data.len() / channels) as f64;
+                    now.checked_sub(Duration::from_secs_f64(frames / sample_rate as f64))
+                        .unwrap_or(now)
+                });
+                // The first channel is enough to find onsets
+                recording
+                    .samples
+                    .extend(data.iter().step_by(channels).map(|s| s.to_sample::<f32>()));
+            },
+            |_| {},
+            None,
+        )
+        .map_err(|err| CalibrationError::RecordingFailed(err.to_string()))
+}
+
+/// Finds the first loud sample after each click and returns the median delay, if at least
+/// half of the clicks were heard.
+fn detect_latency(samples: &[f32], sample_rate: u32, clicks: &[Duration]) -> Option<Duration> {
+    let peak = samples.iter().fold(0.0_f32, |peak, s| peak.max(s.abs()));
+    if peak == 0.0 {
+        return None;
+    }
+    let threshold = peak * 0.5;
+    let to_index = |time: Duration| (time.as_secs_f64() * sample_rate as f64) as usize;
+    let mut delays = clicks
+        .iter()
+        .filter_map(|&click| {
+            let start = to_index(click).min(samples.len());
+            let end = to_index(click + MAX_LATENCY).min(samples.len());
+            samples[start..end]
+                .iter()
+                .position(|s| s.abs() >= threshold)
+                .map(|offset| Duration::from_secs_f64(offset as f64 / sample_rate as f64))
+        })
+        .collect::<Vec<_>>();
+    if delays.len() * 2 < clicks.len() {
+        return None;
+    }
+    delays.sort();
+    Some(delays[delays.len() / 2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A recording at 1 kHz of a click played every 500 ms, each heard its delay in
+    /// milliseconds later, or not at all where the delay is `None`.
+    fn recording(delays: &[Option<u64>]) -> (Vec<f32>, Vec<Duration>) {
+        let mut samples = vec![0.0; 500 * (delays.len() + 1)];
+        let clicks = (0..delays.len() as u64)
+            .map(|click| Duration::from_millis(500 * click))
+            .collect::<Vec<_>>();
+        for (click, delay) in clicks.iter().zip(delays) {
+            if let Some(delay) = delay {
+                let at = click.as_millis() as usize + *delay as usize;
+                samples[at..at + 3].copy_from_slice(&[0.8, -0.6, 0.3]);
+            }
+        }
+        (samples, clicks)
+    }
+
+    #[test]
+    fn latency_is_the_median_delay_heard() {
+        let (samples, clicks) = recording(&[Some(30), Some(50), Some(40), Some(45), Some(35)]);
+        assert_eq!(
+            detect_latency(&samples, 1000, &clicks),
+            Some(Duration::from_millis(40))
+        );
+        let (samples, clicks) = recording(&[Some(30), None, Some(40), None, Some(35), None]);
+        assert_eq!(
+            detect_latency(&samples, 1000, &clicks),
+            Some(Duration::from_millis(35))
+        );
+    }
+
+    #[test]
+    fn latency_needs_half_the_clicks_heard() {
+        let (samples, clicks) = recording(&[Some(30), None, None, Some(40), None, None]);
+        assert_eq!(detect_latency(&samples, 1000, &clicks), None);
+        let (samples, clicks) = recording(&[None, None, None]);
+        assert_eq!(detect_latency(&samples, 1000, &clicks), None);
+    }
+}
@@ -0,0 +1,141 @@
+use crate::{groups_fill, round_bpm, BeatSound, MetronomeSettings, MAX_BAR};
+
+pub const USAGE: &str = "\
+Usage: metronome [options]
+
+Options:
+  --bpm <30-300>  Tempo in quarter notes per minute
+  --bar <2-32>    Beats per bar
+  --volume <10-500>
+                  Volume in percent
+  --accent        Accent the downbeat
+  --no-accent     Play the downbeat like every other beat
+  --offbeats      Click the eighths between beats
+  --no-gui        Play without opening a window; Ctrl-C stops
+  -h, --help      Show this help";
+
+/// What the command line asked for. Anything given overrides the saved settings.
+#[derive(Debug, Default, PartialEq)]
+pub struct Args {
+    pub help: bool,
+    pub no_gui: bool,
+    pub bpm: Option<f64>,
+    pub bar: Option<u32>,
+    /// Volume as a factor, where 1.0 is 100%.
+    pub volume: Option<f32>,
+    pub accent: Option<bool>,
+    pub off_beats: bool,
+}
+
+impl Args {
+    /// Reads the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // `--bpm=120` works as well as `--bpm 120`
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{} needs a value", flag))
+            };
+            match flag.as_str() {
+                "-h" | "--help" => parsed.help = true,
+                "--no-gui" => parsed.no_gui = true,
+                "--bpm" => parsed.bpm = Some(tempo(&flag, &value()?)?),
+                "--bar" => parsed.bar = Some(number(&flag, &value()?, 2, MAX_BAR)?),
+                "--volume" => {
+                    parsed.volume = Some(number(&flag, &value()?, 10, 500)? as f32 / 100.)
+                }
+                "--accent" => parsed.accent = Some(true),
+                "--no-accent" => parsed.accent = Some(false),
+                "--offbeats" => parsed.off_beats = true,
+                _ => return Err(format!("Unknown option {}", flag)),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// Overrides `settings` with whatever was given.
+    pub fn apply(&self, settings: &mut MetronomeSettings) {
+        if let Some(bpm) = self.bpm {
+            settings.bpm = bpm;
+        }
+        if let Some(bar) = self.bar {
+            settings.bar = bar;
+            settings.start_beat = settings.start_beat.min(bar);
+            settings.beat_sounds.resize(bar as usize, BeatSound::Normal);
+            if !groups_fill(&settings.groups, bar) {
+                settings.groups.clear();
+            }
+        }
+        if let Some(volume) = self.volume {
+            settings.volume = volume;
+        }
+        if let (Some(accent), Some(downbeat)) = (self.accent, settings.beat_sounds.first_mut()) {
+            *downbeat = if accent {
+                BeatSound::Accent
+            } else {
+                BeatSound::Normal
+            };
+        }
+        if self.off_beats {
+            settings.subdivision = 2;
+        }
+    }
+}
+
+fn number(flag: &str, value: &str, min: u32, max: u32) -> Result<u32, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|number| (min..=max).contains(number))
+        .ok_or_else(|| format!("{} must be a whole number from {} to {}", flag, min, max))
+}
+
+fn tempo(flag: &str, value: &str) -> Result<f64, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|bpm| (30.0..=300.0).contains(bpm))
+        .map(round_bpm)
+        .ok_or_else(|| format!("{} must be a number from 30 to 300", flag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_line_overrides_are_checked() {
+        let args = |line: &str| Args::parse(line.split_whitespace().map(String::from));
+        assert_eq!(
+            args("--bpm 128.5 --bar=32 --no-gui"),
+            Ok(Args {
+                no_gui: true,
+                bpm: Some(128.5),
+                bar: Some(32),
+                ..Args::default()
+            })
+        );
+        assert_eq!(
+            args("--volume 150 --no-accent --offbeats"),
+            Ok(Args {
+                volume: Some(1.5),
+                accent: Some(false),
+                off_beats: true,
+                ..Args::default()
+            })
+        );
+        assert!(args("--bpm 500").is_err());
+        assert!(args("--volume 0.5").is_err());
+        assert!(args("--bar").is_err());
+        assert!(args("--bar 33").is_err());
+        assert!(args("--tempo 120").is_err());
+    }
+}
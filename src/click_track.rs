@@ -0,0 +1,183 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+use rodio::source::UniformSourceIterator;
+use rodio::Source;
+
+/// A sound to mix into the track.
+pub type Sound = Box<dyn Source<Item = f32> + Send>;
+
+/// How long after its beat each click is mixed in. A click only has to reach the track
+/// within this long of its beat to land on exactly the right sample.
+pub const LOOKAHEAD: Duration = Duration::from_millis(30);
+
+/// One endless output stream that every click is mixed into at the sample its beat falls
+/// on, so the beats keep time with the audio clock instead of each waiting for a stream of
+/// its own to get going. Sounds come in with the time of their beat; the track plays
+/// silence in between, and ends once the sender is dropped and the last sound has rung.
+pub struct ClickTrack {
+    sounds: Receiver<(Instant, Sound)>,
+    sample_rate: u32,
+    channels: u16,
+    /// Frames played so far, and the channel of the next sample within the current frame.
+    frame: u64,
+    channel: u16,
+    /// The time frame 0 stands for, lining the track's clock up with the beats'.
+    start: Instant,
+    /// Sounds waiting for their frame to come round, and those ringing.
+    waiting: Vec<(u64, UniformSourceIterator<Sound, f32>)>,
+    playing: Vec<UniformSourceIterator<Sound, f32>>,
+    closed: bool,
+}
+
+impl ClickTrack {
+    /// A track whose first frame plays at `start`, in `channels` at `sample_rate`.
+    pub fn new(
+        sounds: Receiver<(Instant, Sound)>,
+        sample_rate: u32,
+        channels: u16,
+        start: Instant,
+    ) -> Self {
+        Self {
+            sounds,
+            sample_rate,
+            channels,
+            frame: 0,
+            channel: 0,
+            start,
+            waiting: Vec::new(),
+            playing: Vec::new(),
+            closed: false,
+        }
+    }
+
+    fn frames(&self, duration: Duration) -> u64 {
+        (duration.as_secs_f64() * self.sample_rate as f64).round() as u64
+    }
+
+    fn duration(&self, frames: u64) -> Duration {
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
+    /// The frame a sound for the beat at `at` starts on.
+    fn frame_at(&mut self, at: Instant) -> u64 {
+        let frame = self.frames(at.saturating_duration_since(self.start) + LOOKAHEAD);
+        let lookahead = self.frames(LOOKAHEAD);
+        // The audio clock and the beats' clock drift apart a little over time. Rather than
+        // let a beat land late, or ever further behind, the track is moved along just far
+        // enough that it lands in time again
+        if frame < self.frame {
+            self.start += self.duration(self.frame - frame);
+            self.frame
+        } else if frame > self.frame + 2 * lookahead {
+            let behind = self.duration(frame - self.frame - lookahead);
+            self.start = self.start.checked_sub(behind).unwrap_or(self.start);
+            self.frame + lookahead
+        } else {
+            frame
+        }
+    }
+
+    fn receive(&mut self) {
+        loop {
+            match self.sounds.try_recv() {
+                Ok((at, sound)) => {
+                    let frame = self.frame_at(at);
+                    let sound = UniformSourceIterator::new(sound, self.channels, self.sample_rate);
+                    self.waiting.push((frame, sound));
+                }
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    return;
+                }
+            }
+        }
+    }
+}
+
+impl Iterator for ClickTrack {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            self.receive();
+            let frame = self.frame;
+            let mut i = 0;
+            while i < self.waiting.len() {
+                if self.waiting[i].0 <= frame {
+                    let (_, sound) = self.waiting.swap_remove(i);
+                    self.playing.push(sound);
+                } else {
+                    i += 1;
+                }
+            }
+            if self.closed && self.waiting.is_empty() && self.playing.is_empty() {
+                return None;
+            }
+        }
+        // Every sound has the track's channels, so each one ends on a frame boundary
+        let mut sample = 0.0;
+        self.playing.retain_mut(|sound| match sound.next() {
+            Some(value) => {
+                sample += value;
+                true
+            }
+            None => false,
+        });
+        self.channel += 1;
+        if self.channel == self.channels {
+            self.channel = 0;
+            self.frame += 1;
+        }
+        Some(sample)
+    }
+}
+
+impl Source for ClickTrack {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn click_track_lands_clicks_on_their_beat() {
+        let start = Instant::now();
+        let (tx, rx) = mpsc::channel();
+        let mut track = ClickTrack::new(rx, 1000, 1, start);
+        let click =
+            || -> Sound { Box::new(rodio::buffer::SamplesBuffer::new(1, 1000, vec![1.0; 5])) };
+        // Each click reaches the track a little after its beat, as it would from the UI
+        tx.send((start, click())).unwrap();
+        let mut samples = track.by_ref().take(100).collect::<Vec<_>>();
+        tx.send((start + Duration::from_millis(100), click()))
+            .unwrap();
+        drop(tx);
+        samples.extend(track);
+        let onsets = (0..samples.len())
+            .filter(|&i| samples[i] > 0.0 && (i == 0 || samples[i - 1] == 0.0))
+            .collect::<Vec<_>>();
+        let lookahead = LOOKAHEAD.as_millis() as usize;
+        assert_eq!(onsets, [lookahead, 100 + lookahead]);
+        // It ends once nothing more can come and the last click has rung
+        assert_eq!(samples.iter().sum::<f32>(), 10.0);
+        assert!(samples.len() <= 100 + lookahead + 6);
+    }
+}
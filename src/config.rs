@@ -0,0 +1,1081 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use iced::futures::channel::oneshot;
+use iced::{Color, Theme};
+
+use crate::keys::{Action, Binding, KeyMap};
+use crate::sounds::{Role, Sample, SoundPack};
+use crate::strings::{fill, Label, Language};
+use crate::{
+    groups_text, meter_sequence_text, parse_groups, parse_meter_sequence, round_bpm, BeatSound,
+    GlideCurve, MetronomeSettings, PickupOffBeat, TempoChange, VisualStyle, BEAT_UNITS, MAX_BAR,
+};
+
+const TEMPO_CHANGES: [(TempoChange, &str); 3] = [
+    (TempoChange::Immediate, "immediate"),
+    (TempoChange::NextBeat, "next_beat"),
+    (TempoChange::NextBar, "next_bar"),
+];
+const GLIDE_CURVES: [(GlideCurve, &str); 2] = [
+    (GlideCurve::Straight, "straight"),
+    (GlideCurve::Curved, "curved"),
+];
+const BEAT_SOUNDS: [(BeatSound, &str); 4] = [
+    (BeatSound::Accent, "accent"),
+    (BeatSound::Normal, "normal"),
+    (BeatSound::OffBeat, "off_beat"),
+    (BeatSound::Silent, "silent"),
+];
+const PICKUP_OFF_BEATS: [(PickupOffBeat, &str); 3] = [
+    (PickupOffBeat::Play, "play"),
+    (PickupOffBeat::Soften, "soften"),
+    (PickupOffBeat::Skip, "skip"),
+];
+const VISUAL_STYLES: [(VisualStyle, &str); 5] = [
+    (VisualStyle::AllBeats, "all_beats"),
+    (VisualStyle::DownbeatOnly, "downbeat_only"),
+    (VisualStyle::Single, "single"),
+    (VisualStyle::Conductor, "conductor"),
+    (VisualStyle::Pendulum, "pendulum"),
+];
+const LANGUAGES: [(Language, &str); 4] = [
+    (Language::English, "en"),
+    (Language::German, "de"),
+    (Language::French, "fr"),
+    (Language::Spanish, "es"),
+];
+const SAMPLES: [(Sample, &str); 3] = [
+    (Sample::E, "e"),
+    (Sample::EFlat, "e_flat"),
+    (Sample::F, "f"),
+];
+const SOUND_PACKS: [(SoundPack, &str); 4] = [
+    (SoundPack::Classic, "classic"),
+    (SoundPack::WoodBlock, "wood_block"),
+    (SoundPack::Cowbell, "cowbell"),
+    (SoundPack::Beep, "beep"),
+];
+const ROLES: [(Role, &str); 6] = [
+    (Role::Downbeat, "downbeat"),
+    (Role::Accent, "accent"),
+    (Role::Beat, "beat"),
+    (Role::OffBeat, "off_beat"),
+    (Role::Subdivision, "subdivision"),
+    (Role::End, "end"),
+];
+const ACTIONS: [(Action, &str); 18] = [
+    (Action::Toggle, "toggle"),
+    (Action::Pause, "pause"),
+    (Action::Silence, "silence"),
+    (Action::Tap, "tap"),
+    (Action::Faster, "faster"),
+    (Action::Slower, "slower"),
+    (Action::MuchFaster, "much_faster"),
+    (Action::MuchSlower, "much_slower"),
+    (Action::RecallA, "recall_a"),
+    (Action::RecallB, "recall_b"),
+    (Action::SwapTempoSlots, "swap_tempo_slots"),
+    (Action::Undo, "undo"),
+    (Action::Redo, "redo"),
+    (Action::NextSong, "next_song"),
+    (Action::PrevSong, "prev_song"),
+    (Action::Mute, "mute"),
+    (Action::HalfTime, "half_time"),
+    (Action::DoubleTime, "double_time"),
+];
+
+/// The format of exported config files. Files from older versions still import, with
+/// defaults for whatever they don't mention; newer ones are refused.
+const EXPORT_VERSION: u32 = 1;
+
+/// Where settings are kept between runs, following each platform's convention.
+pub fn path() -> Option<PathBuf> {
+    Some(dir()?.join("settings.json"))
+}
+
+/// Where tempo presets are kept, next to the settings.
+pub fn presets_path() -> Option<PathBuf> {
+    Some(dir()?.join("presets.json"))
+}
+
+/// Where the setlist is kept, next to the settings.
+pub fn setlist_path() -> Option<PathBuf> {
+    Some(dir()?.join("setlist.json"))
+}
+
+/// Where the slowest and fastest tempos ever played are kept, next to the settings.
+pub fn record_path() -> Option<PathBuf> {
+    Some(dir()?.join("record.json"))
+}
+
+fn dir() -> Option<PathBuf> {
+    let env = |name| std::env::var_os(name).map(PathBuf::from);
+    let dir = if cfg!(target_os = "windows") {
+        env("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        env("HOME")?.join("Library/Application Support")
+    } else {
+        env("XDG_CONFIG_HOME").or_else(|| env("HOME").map(|home| home.join(".config")))?
+    };
+    Some(dir.join("metronome"))
+}
+
+/// The saved settings, or the defaults if there are none or they can't be read.
+pub fn load() -> MetronomeSettings {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| from_json(&json))
+        .unwrap_or_default()
+}
+
+/// The saved presets in the order they were made, or none if they can't be read.
+pub fn load_presets() -> Vec<(String, MetronomeSettings)> {
+    presets_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| presets_from_json(&json))
+        .unwrap_or_default()
+}
+
+/// The saved setlist's songs in order, written in the same form as presets.
+pub fn load_setlist() -> Vec<(String, MetronomeSettings)> {
+    setlist_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| presets_from_json(&json))
+        .unwrap_or_default()
+}
+
+/// The slowest and fastest tempos ever played, or `None` before the first beat.
+pub fn load_record() -> Option<(f64, f64)> {
+    record_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| record_from_json(&json))
+}
+
+/// Starts a thread that saves every settings snapshot sent to it, so writing never holds up
+/// the UI. Snapshots that queue up while a write is in progress are collapsed into the latest.
+pub fn spawn_writer() -> Sender<MetronomeSettings> {
+    writer(path, to_json)
+}
+
+/// Like [`spawn_writer`], for the list of presets.
+pub fn spawn_presets_writer() -> Sender<Vec<(String, MetronomeSettings)>> {
+    writer(presets_path, |presets| presets_to_json(presets))
+}
+
+/// Like [`spawn_writer`], for the songs of the setlist.
+pub fn spawn_setlist_writer() -> Sender<Vec<(String, MetronomeSettings)>> {
+    writer(setlist_path, |songs| presets_to_json(songs))
+}
+
+/// Like [`spawn_writer`], for the slowest and fastest tempos ever played.
+pub fn spawn_record_writer() -> Sender<(f64, f64)> {
+    writer(record_path, |&record| record_to_json(record))
+}
+
+fn writer<T: Send + 'static>(
+    path: fn() -> Option<PathBuf>,
+    to_json: fn(&T) -> String,
+) -> Sender<T> {
+    let (tx, rx) = mpsc::channel::<T>();
+    thread::spawn(move || {
+        while let Ok(mut snapshot) = rx.recv() {
+            while let Ok(newer) = rx.try_recv() {
+                snapshot = newer;
+            }
+            if let Some(path) = path() {
+                // Failing to save only costs the settings next time, so errors are dropped
+                let _ = save(&path, &to_json(&snapshot));
+            }
+        }
+    });
+    tx
+}
+
+fn save(path: &PathBuf, json: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // Write-then-rename, so a crash mid-write can't leave a truncated file behind
+    let partial = path.with_extension("json.partial");
+    fs::write(&partial, json)?;
+    fs::rename(partial, path)
+}
+
+fn quoted(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Why a config file couldn't be exported or imported.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigFileError {
+    /// Writing to the path failed, for this reason.
+    Write(String, String),
+    /// Reading from the path failed, for this reason.
+    Read(String, String),
+    NotAConfig,
+    /// The file is from a newer version of its format.
+    TooNew,
+    ExportStopped,
+    ImportStopped,
+}
+
+impl ConfigFileError {
+    pub fn text(&self, language: Language) -> String {
+        match self {
+            ConfigFileError::Write(path, err) => {
+                fill(language.get(Label::ConfigWriteFailed), &[path, err])
+            }
+            ConfigFileError::Read(path, err) => {
+                fill(language.get(Label::ConfigReadFailed), &[path, err])
+            }
+            ConfigFileError::NotAConfig => language.get(Label::NotAConfig).to_string(),
+            ConfigFileError::TooNew => language.get(Label::ConfigTooNew).to_string(),
+            ConfigFileError::ExportStopped => language.get(Label::ExportStopped).to_string(),
+            ConfigFileError::ImportStopped => language.get(Label::ImportStopped).to_string(),
+        }
+    }
+}
+
+/// Writes `settings` to `path` as a config file to share, off the UI thread.
+pub async fn export(path: String, settings: MetronomeSettings) -> Result<(), ConfigFileError> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let saved = save(&PathBuf::from(&path), &export_to_json(&settings))
+            .map_err(|err| ConfigFileError::Write(path, err.to_string()));
+        let _ = tx.send(saved);
+    });
+    rx.await.unwrap_or(Err(ConfigFileError::ExportStopped))
+}
+
+/// Reads a config file written by [`export`], off the UI thread.
+pub async fn import(path: String) -> Result<MetronomeSettings, ConfigFileError> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let settings = fs::read_to_string(&path)
+            .map_err(|err| ConfigFileError::Read(path, err.to_string()))
+            .and_then(|json| import_from_json(&json));
+        let _ = tx.send(settings);
+    });
+    rx.await.unwrap_or(Err(ConfigFileError::ImportStopped))
+}
+
+/// Wraps the settings object with the version of its format.
+pub fn export_to_json(settings: &MetronomeSettings) -> String {
+    format!(
+        "{{\n  \"version\": {},\n  \"settings\": {}\n}}\n",
+        EXPORT_VERSION,
+        to_json(settings).trim_end().replace('\n', "\n  ")
+    )
+}
+
+/// Reads a file written by [`export_to_json`] from this version or an older one.
+pub fn import_from_json(json: &str) -> Result<MetronomeSettings, ConfigFileError> {
+    let (mut version, mut settings) = (None, None);
+    for (key, value) in parse_object(json).ok_or(ConfigFileError::NotAConfig)? {
+        match (key.as_str(), value) {
+            ("version", Value::Number(number)) => version = Some(number),
+            ("settings", Value::Nested(json)) => settings = Some(json),
+            _ => {}
+        }
+    }
+    match version {
+        Some(version) if version > EXPORT_VERSION as f64 => Err(ConfigFileError::TooNew),
+        Some(version) if version >= 1.0 && version.fract() == 0.0 => settings
+            .and_then(|json| from_json(&json))
+            .ok_or(ConfigFileError::NotAConfig),
+        _ => Err(ConfigFileError::NotAConfig),
+    }
+}
+
+/// Writes presets as one object keyed by name, each holding a settings object.
+pub fn presets_to_json(presets: &[(String, MetronomeSettings)]) -> String {
+    let body = presets
+        .iter()
+        .map(|(name, settings)| {
+            format!(
+                "  {}: {}",
+                quoted(name),
+                to_json(settings).trim_end().replace('\n', "\n  ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("{{\n{}\n}}\n", body)
+}
+
+/// Reads presets written by [`presets_to_json`], keeping their order. A preset that isn't a
+/// settings object is dropped rather than losing the rest.
+pub fn presets_from_json(json: &str) -> Option<Vec<(String, MetronomeSettings)>> {
+    let presets = parse_object(json)?
+        .into_iter()
+        .filter_map(|(name, value)| match value {
+            Value::Nested(json) => Some((name, from_json(&json)?)),
+            _ => None,
+        })
+        .collect();
+    Some(presets)
+}
+
+pub fn record_to_json((slowest, fastest): (f64, f64)) -> String {
+    format!(
+        "{{\n  \"slowest\": {},\n  \"fastest\": {}\n}}\n",
+        slowest, fastest
+    )
+}
+
+/// Reads a record written by [`record_to_json`], or `None` if either tempo is missing.
+pub fn record_from_json(json: &str) -> Option<(f64, f64)> {
+    let (mut slowest, mut fastest) = (None, None);
+    for (key, value) in parse_object(json)? {
+        match (key.as_str(), value) {
+            ("slowest", Value::Number(bpm)) => slowest = Some(bpm),
+            ("fastest", Value::Number(bpm)) => fastest = Some(bpm),
+            _ => {}
+        }
+    }
+    Some((slowest?, fastest?)).filter(|(slowest, fastest)| slowest <= fastest)
+}
+
+pub fn to_json(settings: &MetronomeSettings) -> String {
+    let color = |color: Option<Color>| match color {
+        Some(color) => {
+            let [r, g, b, _] = color.into_rgba8();
+            format!("\"#{:02x}{:02x}{:02x}\"", r, g, b)
+        }
+        None => String::from("null"),
+    };
+    let mut fields = vec![
+        ("bar", settings.bar.to_string()),
+        ("beat_unit", settings.beat_unit.to_string()),
+        ("bpm", settings.bpm.to_string()),
+        (
+            "recent_tempos",
+            quoted(&tempos_text(&settings.recent_tempos)),
+        ),
+        ("step_size", settings.step_size.to_string()),
+        ("tempo_decimals", settings.tempo_decimals.to_string()),
+        (
+            "tempo_change",
+            quoted(name(&TEMPO_CHANGES, settings.tempo_change)),
+        ),
+        (
+            "restart_bar_on_tempo",
+            settings.restart_bar_on_tempo.to_string(),
+        ),
+        ("glide_target", settings.glide_target.to_string()),
+        ("glide_bars", settings.glide_bars.to_string()),
+        (
+            "glide_curve",
+            quoted(name(&GLIDE_CURVES, settings.glide_curve)),
+        ),
+        ("warm_up", settings.warm_up.to_string()),
+        ("warm_up_from", settings.warm_up_from.to_string()),
+        ("warm_up_secs", settings.warm_up_secs.to_string()),
+        ("gap_trainer", settings.gap_trainer.to_string()),
+        ("gap_audible_bars", settings.gap_audible_bars.to_string()),
+        ("gap_silent_bars", settings.gap_silent_bars.to_string()),
+        ("ramp_trainer", settings.ramp_trainer.to_string()),
+        ("ramp_start", settings.ramp_start.to_string()),
+        ("ramp_end", settings.ramp_end.to_string()),
+        ("ramp_step", settings.ramp_step.to_string()),
+        ("ramp_every_bars", settings.ramp_every_bars.to_string()),
+        ("ramp_loop", settings.ramp_loop.to_string()),
+        ("drill", settings.drill.to_string()),
+        ("drill_min", settings.drill_min.to_string()),
+        ("drill_max", settings.drill_max.to_string()),
+        ("drill_every_bars", settings.drill_every_bars.to_string()),
+        ("start_beat", settings.start_beat.to_string()),
+        ("count_in_bars", settings.count_in_bars.to_string()),
+        (
+            "beat_sounds",
+            quoted(&beat_sounds_text(&settings.beat_sounds)),
+        ),
+        ("groups", quoted(&groups_text(&settings.groups))),
+        (
+            "meter_sequence",
+            quoted(&meter_sequence_text(&settings.meter_sequence)),
+        ),
+        ("subdivision", settings.subdivision.to_string()),
+        ("swing", settings.swing.to_string()),
+        ("polyrhythm", settings.polyrhythm.to_string()),
+        ("poly_divisions", settings.poly_voice.divisions.to_string()),
+        (
+            "off_beat_mask",
+            quoted(&off_beat_mask_text(&settings.off_beat_mask)),
+        ),
+        (
+            "pickup_off_beat",
+            quoted(name(&PICKUP_OFF_BEATS, settings.pickup_off_beat)),
+        ),
+        ("downbeat_only", settings.downbeat_only.to_string()),
+        ("visual_only", settings.visual_only.to_string()),
+        ("show_beat_bar", settings.show_beat_bar.to_string()),
+        ("beat_counter", settings.beat_counter.to_string()),
+        ("compact", settings.compact.to_string()),
+        (
+            "visual_style",
+            quoted(name(&VISUAL_STYLES, settings.visual_style)),
+        ),
+        ("pulse", settings.pulse.to_string()),
+        ("big_downbeat", settings.big_downbeat.to_string()),
+        ("stage_flash", settings.stage_flash.to_string()),
+        (
+            "background_gradient",
+            settings.background_gradient.to_string(),
+        ),
+        (
+            "gradient_intensity",
+            settings.gradient_intensity.to_string(),
+        ),
+        ("volume", settings.volume.to_string()),
+        ("start_stop_cues", settings.start_stop_cues.to_string()),
+        ("truncate_clicks", settings.truncate_clicks.to_string()),
+        ("max_click_ms", settings.max_click_ms.to_string()),
+        ("latency_offset_ms", settings.latency_offset_ms.to_string()),
+        ("sample_accurate", settings.sample_accurate.to_string()),
+        ("active_color", color(settings.active_color)),
+        ("idle_color", color(settings.idle_color)),
+        (
+            "attention_on_downbeat",
+            settings.attention_on_downbeat.to_string(),
+        ),
+        ("beat_in_title", settings.beat_in_title.to_string()),
+        ("inhibit_sleep", settings.inhibit_sleep.to_string()),
+        ("share_status", settings.share_status.to_string()),
+        ("language", quoted(name(&LANGUAGES, settings.language))),
+        ("dark_theme", (settings.theme == Theme::Dark).to_string()),
+        (
+            "output_device",
+            settings
+                .output_device
+                .as_deref()
+                .map_or_else(|| String::from("null"), quoted),
+        ),
+    ];
+    fields.push((
+        "sound.pack",
+        quoted(name(&SOUND_PACKS, settings.sound_map.pack)),
+    ));
+    let mut sounds = Vec::new();
+    for (bar, beat_sounds) in &settings.meter_beat_sounds {
+        sounds.push((
+            format!("beat_sounds.{}", bar),
+            quoted(&beat_sounds_text(beat_sounds)),
+        ));
+    }
+    for (role, role_name) in ROLES {
+        let sound = settings.sound_map.get(role);
+        sounds.push((
+            format!("sound.{}.sample", role_name),
+            quoted(name(&SAMPLES, sound.sample)),
+        ));
+        sounds.push((
+            format!("sound.{}.volume", role_name),
+            sound.volume.to_string(),
+        ));
+        sounds.push((
+            format!("sound.{}.pitch", role_name),
+            sound.pitch.to_string(),
+        ));
+        sounds.push((
+            format!("sound.{}.file", role_name),
+            settings.sample_files[role.index()]
+                .as_deref()
+                .map_or_else(|| String::from("null"), quoted),
+        ));
+    }
+    for (action, action_name) in ACTIONS {
+        sounds.push((
+            format!("keys.{}", action_name),
+            quoted(&bindings_text(&settings.key_map, action)),
+        ));
+    }
+    fields.extend(
+        sounds
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.clone())),
+    );
+    let body = fields
+        .iter()
+        .map(|(key, value)| format!("  \"{}\": {}", key, value))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("{{\n{}\n}}\n", body)
+}
+
+/// Reads settings written by [`to_json`]. Missing or malformed values keep their defaults and
+/// unknown keys are ignored, so files from older and newer versions both load; `None` means
+/// the file isn't a settings object at all.
+pub fn from_json(json: &str) -> Option<MetronomeSettings> {
+    let values = parse_object(json)?.into_iter().collect::<HashMap<_, _>>();
+    let mut settings = MetronomeSettings::default();
+    let number = |key: &str| match values.get(key) {
+        Some(Value::Number(number)) => Some(*number),
+        _ => None,
+    };
+    let uint = |key: &str, min: u32, max: u32| {
+        number(key)
+            .filter(|number| number.fract() == 0.0)
+            .map(|number| (number.max(0.0) as u32).clamp(min, max))
+    };
+    let float = |key: &str, min: f32, max: f32| number(key).map(|n| (n as f32).clamp(min, max));
+    let boolean = |key: &str| match values.get(key) {
+        Some(Value::Bool(value)) => Some(*value),
+        _ => None,
+    };
+    let string = |key: &str| match values.get(key) {
+        Some(Value::String(value)) => Some(value.as_str()),
+        _ => None,
+    };
+    let color = |key: &str| match values.get(key) {
+        Some(Value::Null) => Some(None),
+        Some(Value::String(hex)) => parse_color(hex).map(Some),
+        _ => None,
+    };
+
+    macro_rules! read {
+        ($field:ident, $value:expr) => {
+            if let Some(value) = $value {
+                settings.$field = value;
+            }
+        };
+    }
+    read!(bar, uint("bar", 2, MAX_BAR));
+    read!(
+        beat_unit,
+        uint("beat_unit", 2, 16).filter(|unit| BEAT_UNITS.contains(unit))
+    );
+    read!(
+        bpm,
+        number("bpm").map(|bpm| round_bpm(bpm.clamp(30., 300.)))
+    );
+    read!(
+        recent_tempos,
+        string("recent_tempos").and_then(parse_tempos)
+    );
+    read!(
+        step_size,
+        number("step_size").map(|step| round_bpm(step.clamp(0.5, 10.)))
+    );
+    read!(tempo_decimals, uint("tempo_decimals", 0, 2));
+    read!(
+        tempo_change,
+        string("tempo_change").and_then(|s| lookup(&TEMPO_CHANGES, s))
+    );
+    read!(restart_bar_on_tempo, boolean("restart_bar_on_tempo"));
+    read!(glide_target, uint("glide_target", 30, 300));
+    read!(glide_bars, uint("glide_bars", 1, 32));
+    read!(
+        glide_curve,
+        string("glide_curve").and_then(|s| lookup(&GLIDE_CURVES, s))
+    );
+    read!(warm_up, boolean("warm_up"));
+    read!(warm_up_from, uint("warm_up_from", 30, 300));
+    read!(warm_up_secs, uint("warm_up_secs", 5, 120));
+    read!(gap_trainer, boolean("gap_trainer"));
+    read!(gap_audible_bars, uint("gap_audible_bars", 1, 16));
+    read!(gap_silent_bars, uint("gap_silent_bars", 1, 16));
+    read!(ramp_trainer, boolean("ramp_trainer"));
+    read!(ramp_start, uint("ramp_start", 30, 300));
+    read!(ramp_end, uint("ramp_end", 30, 300));
+    read!(ramp_step, uint("ramp_step", 1, 20));
+    read!(ramp_every_bars, uint("ramp_every_bars", 1, 16));
+    read!(ramp_loop, boolean("ramp_loop"));
+    read!(drill, boolean("drill"));
+    read!(drill_min, uint("drill_min", 30, 300));
+    read!(drill_max, uint("drill_max", 30, 300));
+    read!(drill_every_bars, uint("drill_every_bars", 1, 16));
+    read!(start_beat, uint("start_beat", 1, MAX_BAR));
+    read!(count_in_bars, uint("count_in_bars", 0, 4));
+    match string("beat_sounds").and_then(parse_beat_sounds) {
+        Some(sounds) => settings.beat_sounds = sounds,
+        // Files from before the pattern only knew whether the downbeat was accented
+        None => {
+            if let (Some(false), Some(downbeat)) = (
+                boolean("accentuate_first_beat"),
+                settings.beat_sounds.first_mut(),
+            ) {
+                *downbeat = BeatSound::Normal;
+            }
+        }
+    }
+    settings
+        .beat_sounds
+        .resize(settings.bar as usize, BeatSound::Normal);
+    for (key, value) in &values {
+        let bar = key
+            .strip_prefix("beat_sounds.")
+            .and_then(|bar| bar.parse::<u32>().ok())
+            .filter(|bar| (2..=MAX_BAR).contains(bar) && *bar != settings.bar);
+        if let (Some(bar), Value::String(sounds)) = (bar, value) {
+            if let Some(mut sounds) = parse_beat_sounds(sounds) {
+                sounds.resize(bar as usize, BeatSound::Normal);
+                settings.meter_beat_sounds.insert(bar, sounds);
+            }
+        }
+    }
+    read!(
+        groups,
+        string("groups").and_then(|s| parse_groups(s, settings.bar))
+    );
+    read!(
+        meter_sequence,
+        string("meter_sequence").and_then(parse_meter_sequence)
+    );
+    if boolean("off_beats") == Some(true) {
+        settings.subdivision = 2;
+    }
+    read!(subdivision, uint("subdivision", 1, 4));
+    read!(swing, float("swing", 0.5, 0.75));
+    read!(polyrhythm, boolean("polyrhythm"));
+    if let Some(divisions) = uint("poly_divisions", 2, 16) {
+        settings.poly_voice.divisions = divisions;
+    }
+    read!(
+        off_beat_mask,
+        string("off_beat_mask").and_then(parse_off_beat_mask)
+    );
+    read!(
+        pickup_off_beat,
+        string("pickup_off_beat").and_then(|s| lookup(&PICKUP_OFF_BEATS, s))
+    );
+    read!(downbeat_only, boolean("downbeat_only"));
+    read!(visual_only, boolean("visual_only"));
+    read!(show_beat_bar, boolean("show_beat_bar"));
+    read!(beat_counter, boolean("beat_counter"));
+    read!(compact, boolean("compact"));
+    read!(
+        visual_style,
+        string("visual_style").and_then(|s| lookup(&VISUAL_STYLES, s))
+    );
+    read!(pulse, boolean("pulse"));
+    read!(big_downbeat, boolean("big_downbeat"));
+    read!(stage_flash, boolean("stage_flash"));
+    read!(background_gradient, boolean("background_gradient"));
+    read!(gradient_intensity, float("gradient_intensity", 0.05, 0.5));
+    read!(volume, float("volume", 0.1, 5.0));
+    read!(start_stop_cues, boolean("start_stop_cues"));
+    read!(truncate_clicks, boolean("truncate_clicks"));
+    read!(max_click_ms, uint("max_click_ms", 10, 130));
+    read!(latency_offset_ms, uint("latency_offset_ms", 0, 1000));
+    read!(sample_accurate, boolean("sample_accurate"));
+    read!(active_color, color("active_color"));
+    read!(idle_color, color("idle_color"));
+    read!(attention_on_downbeat, boolean("attention_on_downbeat"));
+    read!(beat_in_title, boolean("beat_in_title"));
+    read!(inhibit_sleep, boolean("inhibit_sleep"));
+    read!(share_status, boolean("share_status"));
+    read!(
+        language,
+        string("language").and_then(|s| lookup(&LANGUAGES, s))
+    );
+    if let Some(dark) = boolean("dark_theme") {
+        settings.theme = if dark { Theme::Dark } else { Theme::Light };
+    }
+    if let Some(pack) = string("sound.pack").and_then(|s| lookup(&SOUND_PACKS, s)) {
+        settings.sound_map.pack = pack;
+    }
+    for (role, role_name) in ROLES {
+        let key = |field| format!("sound.{}.{}", role_name, field);
+        let sound = settings.sound_map.get_mut(role);
+        if let Some(sample) = string(&key("sample")).and_then(|s| lookup(&SAMPLES, s)) {
+            sound.sample = sample;
+        }
+        if let Some(volume) = float(&key("volume"), 0.0, 2.0) {
+            sound.volume = volume;
+        }
+        if let Some(pitch) = float(&key("pitch"), 0.5, 2.0) {
+            sound.pitch = pitch;
+        }
+        settings.sample_files[role.index()] = string(&key("file")).map(String::from);
+    }
+    settings.output_device = string("output_device").map(String::from);
+    // Actions without keys of their own keep their default ones, unless those were taken
+    let mut key_map = KeyMap::default();
+    for (action, action_name) in ACTIONS {
+        if let Some(bindings) = string(&format!("keys.{}", action_name)).and_then(parse_bindings) {
+            key_map.bind(action, &bindings);
+        }
+    }
+    settings.key_map = key_map;
+    Some(settings)
+}
+
+fn name<T: Copy + PartialEq>(table: &[(T, &'static str)], value: T) -> &'static str {
+    table
+        .iter()
+        .find(|(entry, _)| *entry == value)
+        .map(|&(_, name)| name)
+        .unwrap()
+}
+
+fn lookup<T: Copy>(table: &[(T, &str)], name: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(_, entry)| *entry == name)
+        .map(|&(value, _)| value)
+}
+
+fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::from_rgb8(channel(0)?, channel(2)?, channel(4)?))
+}
+
+fn tempos_text(tempos: &VecDeque<f64>) -> String {
+    tempos
+        .iter()
+        .map(f64::to_string)
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_tempos(text: &str) -> Option<VecDeque<f64>> {
+    if text.is_empty() {
+        return Some(VecDeque::new());
+    }
+    text.split(',')
+        .map(|bpm| {
+            bpm.parse::<f64>()
+                .ok()
+                .filter(|bpm| (30.0..=300.0).contains(bpm))
+                .map(round_bpm)
+        })
+        .collect()
+}
+
+fn beat_sounds_text(sounds: &[BeatSound]) -> String {
+    sounds
+        .iter()
+        .map(|&sound| name(&BEAT_SOUNDS, sound))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_beat_sounds(text: &str) -> Option<Vec<BeatSound>> {
+    text.split(',')
+        .map(|sound| lookup(&BEAT_SOUNDS, sound))
+        .collect()
+}
+
+fn off_beat_mask_text(mask: &[bool]) -> String {
+    mask.iter()
+        .map(|&flag| if flag { "on" } else { "off" })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_off_beat_mask(text: &str) -> Option<Vec<bool>> {
+    text.split(',')
+        .map(|flag| match flag {
+            "on" => Some(true),
+            "off" => Some(false),
+            _ => None,
+        })
+        .collect()
+}
+
+fn bindings_text(key_map: &KeyMap, action: Action) -> String {
+    key_map
+        .bindings(action)
+        .map(|binding| binding.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_bindings(text: &str) -> Option<Vec<Binding>> {
+    if text.is_empty() {
+        return Some(Vec::new());
+    }
+    text.split(',').map(Binding::parse).collect()
+}
+
+/// A JSON value, as far as the settings files need them. Nested objects and arrays are kept
+/// as their raw text, for the caller to parse if it expects one.
+enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Nested(String),
+}
+
+/// Parses a JSON object into its top-level keys and values, in file order.
+fn parse_object(json: &str) -> Option<Vec<(String, Value)>> {
+    let mut parser = Parser::new(json);
+    let mut values = Vec::new();
+    parser.expect('{')?;
+    if !parser.eat('}') {
+        loop {
+            let key = parser.string()?;
+            parser.expect(':')?;
+            let value = parser.value()?;
+            values.push((key, value));
+            if parser.eat('}') {
+                break;
+            }
+            parser.expect(',')?;
+        }
+    }
+    parser.skip_whitespace();
+    parser.chars.peek().is_none().then_some(values)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(json: &'a str) -> Self {
+        Self {
+            chars: json.chars().peekable(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&expected).is_some()
+    }
+
+    fn expect(&mut self, expected: char) -> Option<()> {
+        self.eat(expected).then_some(())
+    }
+
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        match *self.chars.peek()? {
+            '"' => self.string().map(Value::String),
+            '{' | '[' => self.nested().map(Value::Nested),
+            't' => self.word("true").map(|()| Value::Bool(true)),
+            'f' => self.word("false").map(|()| Value::Bool(false)),
+            'n' => self.word("null").map(|()| Value::Null),
+            _ => {
+                let mut number = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| c.is_ascii_digit() || "+-.eE".contains(*c))
+                {
+                    number.push(c);
+                }
+                number.parse().ok().map(Value::Number)
+            }
+        }
+    }
+
+    fn word(&mut self, word: &str) -> Option<()> {
+        word.chars()
+            .all(|expected| self.chars.next() == Some(expected))
+            .then_some(())
+    }
+
+    fn string(&mut self) -> Option<String> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.chars.next()? {
+                '"' => return Some(string),
+                '\\' => match self.chars.next()? {
+                    'n' => string.push('\n'),
+                    't' => string.push('\t'),
+                    'r' => string.push('\r'),
+                    'b' => string.push('\u{8}'),
+                    'f' => string.push('\u{c}'),
+                    'u' => {
+                        let hex = (0..4)
+                            .map(|_| self.chars.next())
+                            .collect::<Option<String>>()?;
+                        string.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                    }
+                    escaped => string.push(escaped),
+                },
+                c => string.push(c),
+            }
+        }
+    }
+
+    /// Reads a whole object or array as raw text, minding brackets inside strings.
+    fn nested(&mut self) -> Option<String> {
+        let mut raw = String::new();
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut escaped = false;
+        loop {
+            let c = self.chars.next()?;
+            raw.push(c);
+            if in_string {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    '"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match c {
+                '"' => in_string = true,
+                '{' | '[' => depth += 1,
+                '}' | ']' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Some(raw);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::default_beat_sounds;
+    use iced::keyboard::{KeyCode, Modifiers};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn settings_survive_a_round_trip() {
+        let mut settings = MetronomeSettings {
+            bar: 7,
+            beat_unit: 8,
+            bpm: 128.5,
+            groups: vec![3, 2, 2],
+            meter_sequence: vec![7, 6],
+            recent_tempos: VecDeque::from([96.5, 120.]),
+            tempo_decimals: 2,
+            beat_sounds: vec![
+                BeatSound::Accent,
+                BeatSound::Normal,
+                BeatSound::Silent,
+                BeatSound::Accent,
+                BeatSound::OffBeat,
+                BeatSound::Accent,
+                BeatSound::Normal,
+            ],
+            meter_beat_sounds: BTreeMap::from([(
+                3,
+                vec![BeatSound::Accent, BeatSound::Accent, BeatSound::Silent],
+            )]),
+            tempo_change: TempoChange::NextBar,
+            visual_style: VisualStyle::Conductor,
+            volume: 0.75,
+            subdivision: 3,
+            off_beat_mask: vec![true, false, true, true, false, true, true],
+            active_color: Some(Color::from_rgb8(0x12, 0xab, 0xef)),
+            language: Language::French,
+            theme: Theme::Dark,
+            ..MetronomeSettings::default()
+        };
+        settings.sound_map.off_beat.pitch = 1.5;
+        settings.sound_map.accent.volume = 0.5;
+        settings.sound_map.end.sample = Sample::F;
+        settings.sound_map.pack = SoundPack::Cowbell;
+        settings.sample_files[1] = Some(String::from(r#"C:\Clicks\"wood".wav"#));
+        settings
+            .key_map
+            .rebind(Action::Tap, Binding::parse("Shift+T").unwrap());
+        settings
+            .key_map
+            .rebind(Action::Mute, Binding::parse("Ctrl+Alt+Key0").unwrap());
+        settings
+            .key_map
+            .rebind(Action::Pause, Binding::parse("Space").unwrap());
+        assert_eq!(from_json(&to_json(&settings)), Some(settings));
+    }
+
+    #[test]
+    fn tempo_records_survive_a_round_trip() {
+        assert_eq!(
+            record_from_json(&record_to_json((62.5, 180.))),
+            Some((62.5, 180.))
+        );
+        assert_eq!(record_from_json(r#"{"slowest": 90}"#), None);
+    }
+
+    #[test]
+    fn presets_keep_their_order() {
+        let presets = vec![
+            (
+                String::from("Slow {ballad}"),
+                MetronomeSettings {
+                    bpm: 60.,
+                    subdivision: 3,
+                    ..MetronomeSettings::default()
+                },
+            ),
+            (
+                String::from("A \"fast\" one"),
+                MetronomeSettings {
+                    bpm: 200.,
+                    bar: 7,
+                    beat_sounds: default_beat_sounds(7),
+                    ..MetronomeSettings::default()
+                },
+            ),
+        ];
+        assert_eq!(presets_from_json(&presets_to_json(&presets)), Some(presets));
+    }
+
+    #[test]
+    fn exported_configs_check_their_version() {
+        let mut settings = MetronomeSettings {
+            bpm: 72.5,
+            bar: 5,
+            beat_sounds: default_beat_sounds(5),
+            ..MetronomeSettings::default()
+        };
+        // The key bindings are part of the setup too
+        settings
+            .key_map
+            .rebind(Action::Toggle, Binding::new(KeyCode::S, Modifiers::empty()));
+        assert_eq!(import_from_json(&export_to_json(&settings)), Ok(settings));
+        // Older files leave out what they didn't know about
+        let old = import_from_json(r#"{"version": 1, "settings": {"bpm": 90}}"#).unwrap();
+        assert_eq!(old.bpm, 90.);
+        assert_eq!(old.bar, 4);
+        assert_eq!(
+            import_from_json(r#"{"version": 2, "settings": {}}"#),
+            Err(ConfigFileError::TooNew)
+        );
+        // A plain settings file isn't an export
+        assert_eq!(
+            import_from_json(r#"{"bpm": 90}"#),
+            Err(ConfigFileError::NotAConfig)
+        );
+    }
+
+    #[test]
+    fn bad_settings_fall_back_to_defaults() {
+        assert_eq!(from_json("not json"), None);
+        assert_eq!(from_json("{\"bpm\": 120,"), None);
+        let settings = from_json(
+            r#"{"bpm": 9000, "bar": "seven", "off_beats": true, "accentuate_first_beat": false,
+                "future": {"a": [1, "}"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(settings.bpm, 300.);
+        assert_eq!(settings.bar, 4);
+        // Files from before subdivisions only knew about eighths
+        assert_eq!(settings.subdivision, 2);
+        assert_eq!(settings.beat_sounds, [BeatSound::Normal; 4]);
+    }
+}
@@ -0,0 +1,1650 @@
+use std::collections::BTreeMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    default_beat_sounds, round_bpm, tick_interval, Beat, BeatSound, MetroState, PickupOffBeat,
+    Schedule, BEAT_UNITS, MAX_BAR,
+};
+
+/// A sound the metronome is due to make, sent as it comes due.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeatEvent {
+    pub tick: Tick,
+    /// What to play for it, or `None` for a silent tick.
+    pub beat: Option<Beat>,
+    /// Whether it's part of the count-in rather than the take.
+    pub counting_in: bool,
+    /// When it was due, which a late wake-up can leave just before it was sent.
+    pub at: Instant,
+}
+
+/// Which tick of which clock a [`BeatEvent`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tick {
+    /// Beat `index` of the bar, counting from 0.
+    Beat(u32),
+    /// One of the ticks splitting up beat `index`, the first after the beat itself being 1.
+    OffBeat(u32, u32),
+    /// Beat `index` of the polyrhythm's second voice within the bar, counting from 0.
+    Poly(u32),
+}
+
+/// When a tempo picked during playback takes over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TempoChange {
+    Immediate,
+    /// On the next main beat.
+    NextBeat,
+    /// On the next downbeat.
+    NextBar,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlideCurve {
+    Straight,
+    /// Eases out of the starting tempo and into the target.
+    Curved,
+}
+
+/// A second pulse that splits each bar into its own number of even beats, like the 3 of
+/// 3 against 4.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PolyVoice {
+    pub divisions: u32,
+}
+
+/// A gradual change of tempo, stepped once per main beat.
+#[derive(Debug, Clone, PartialEq)]
+struct Glide {
+    from: f64,
+    to: f64,
+    curve: GlideCurve,
+    beats: u32,
+    done: u32,
+}
+
+impl Glide {
+    fn new(from: f64, to: f64, curve: GlideCurve, beats: u32) -> Self {
+        Self {
+            from,
+            to,
+            curve,
+            beats: beats.max(1),
+            done: 0,
+        }
+    }
+
+    /// Moves one beat along and returns the tempo for it, or `None` once past the target.
+    fn step(&mut self) -> Option<f64> {
+        if self.done >= self.beats {
+            return None;
+        }
+        self.done += 1;
+        let t = self.done as f64 / self.beats as f64;
+        let t = match self.curve {
+            GlideCurve::Straight => t,
+            GlideCurve::Curved => t * t * (3. - 2. * t),
+        };
+        Some(round_bpm(self.from + (self.to - self.from) * t))
+    }
+}
+
+/// A linear, time-based climb to the chosen tempo right after starting.
+#[derive(Debug, Clone, PartialEq)]
+struct WarmUp {
+    from: f64,
+    to: f64,
+    started: Instant,
+    duration: Duration,
+}
+
+impl WarmUp {
+    /// The tempo at `now`, or `None` once the ramp is over.
+    fn bpm_at(&self, now: Instant) -> Option<f64> {
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed >= self.duration {
+            return None;
+        }
+        let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+        Some(round_bpm(self.from + (self.to - self.from) * t))
+    }
+}
+
+/// Whether bar `bar` of the take, counting from 0, is one of the silent ones when `audible`
+/// bars of clicks alternate with `silent` bars of silence.
+fn gap_is_silent(bar: u32, audible: u32, silent: u32) -> bool {
+    bar % (audible + silent) >= audible
+}
+
+/// The ramp trainer's tempo after `steps` steps of `step` from `start` towards `end`, which
+/// may be slower. Past `end` it holds there, or starts over from `start` if `looped`.
+fn ramp_bpm(start: u32, end: u32, step: u32, steps: u32, looped: bool) -> u32 {
+    let distance = start.abs_diff(end);
+    let mut steps = steps;
+    if looped {
+        steps %= distance.div_ceil(step) + 1;
+    }
+    let moved = steps.saturating_mul(step).min(distance);
+    let bpm = if end >= start {
+        start + moved
+    } else {
+        start - moved
+    };
+    bpm.clamp(30, 300)
+}
+
+/// How far the ramp trainer is into its step `bars_played` bars into the take: the bar of the
+/// step, counting from 1, and the tempo the next step brings, or `None` once it holds.
+fn ramp_next_step(
+    (start, end, step, every): (u32, u32, u32, u32),
+    looped: bool,
+    bars_played: u32,
+) -> Option<(u32, u32)> {
+    let bar = bars_played.checked_sub(1)?;
+    let steps = bar / every;
+    let next = ramp_bpm(start, end, step, steps + 1, looped);
+    (next != ramp_bpm(start, end, step, steps, looped)).then_some((bar % every + 1, next))
+}
+
+/// What tick `position` of a beat split into `subdivision` ticks, counting from 1 after the
+/// beat itself, plays instead of `off_beat`: only the tick halfway through is the off-beat.
+fn subdivision_beat(off_beat: Beat, position: u32, subdivision: u32) -> Beat {
+    match off_beat {
+        Beat::OffBeat if 2 * position != subdivision => Beat::Subdivision,
+        beat => beat,
+    }
+}
+
+/// The drill's tempo after its `change`th change, somewhere from `min` to `max` either way
+/// round. The same `seed` always gives the same tempos.
+fn drill_bpm(seed: u64, change: u32, min: u32, max: u32) -> u32 {
+    let (low, high) = (min.min(max), min.max(max));
+    // splitmix64, which scatters even neighbouring inputs
+    let mut z = seed.wrapping_add((change as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    (low + (z % ((high - low) as u64 + 1)) as u32).clamp(30, 300)
+}
+
+/// A seed for a new take's drill, different every time.
+fn drill_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_nanos() as u64)
+}
+
+/// The length of bar `bar_index` of the take, counting from 0, when cycling through
+/// `sequence`, or `None` without one.
+fn sequence_bar(sequence: &[u32], bar_index: u32) -> Option<u32> {
+    (!sequence.is_empty()).then(|| sequence[bar_index as usize % sequence.len()])
+}
+
+/// Whether `groups` add up to exactly `bar` beats, which is the only bar they can split.
+pub fn groups_fill(groups: &[u32], bar: u32) -> bool {
+    groups
+        .iter()
+        .try_fold(0u32, |sum, &group| sum.checked_add(group))
+        == Some(bar)
+}
+
+/// Whether beat `index` of the bar, counting from 0, is the first of one of `groups`.
+pub fn starts_group(groups: &[u32], index: u32) -> bool {
+    groups
+        .iter()
+        .scan(0u32, |start, &group| {
+            let this = *start;
+            *start = start.saturating_add(group);
+            Some(this)
+        })
+        .any(|start| start == index)
+}
+
+/// What beat `index` of the bar plays when it would play `beat`: groups after the first start
+/// on a lighter accent than the bar does.
+fn grouped_beat(beat: Option<Beat>, groups: &[u32], index: u32) -> Option<Beat> {
+    match beat {
+        Some(Beat::FirstBeat) if index > 0 && starts_group(groups, index) => {
+            Some(Beat::MediumAccent)
+        }
+        beat => beat,
+    }
+}
+
+/// A metronome without a window or an output: it keeps time on a thread of its own and
+/// sends a [`BeatEvent`] for every tick, leaving what to make of them to whoever listens.
+/// What it plays is up to its [`Sequencer`]. Dropping the engine stops it.
+pub struct MetronomeEngine {
+    sequencer: Arc<Mutex<Sequencer>>,
+    /// Wakes the thread to follow a change to the clock.
+    wake: Sender<()>,
+    listeners: Arc<Mutex<Vec<Listener>>>,
+}
+
+/// Hands a beat on to one listener, saying whether it's still listening.
+type Listener = Box<dyn FnMut(&BeatEvent) -> bool + Send>;
+
+impl MetronomeEngine {
+    /// A stopped engine at `bpm` quarter notes a minute in bars of `bar` beats of
+    /// `beat_unit` notes, with an accented downbeat, and where its beats will be sent.
+    pub fn new(bpm: f64, bar: u32, beat_unit: u32) -> (Self, Receiver<BeatEvent>) {
+        let engine = Self::with_sequencer(Sequencer::new(4));
+        engine.0.set_bpm(bpm);
+        engine.0.set_meter(bar, beat_unit);
+        engine
+    }
+
+    /// A stopped engine playing what `sequencer` is set up for, and where its beats will be
+    /// sent.
+    pub fn with_sequencer(sequencer: Sequencer) -> (Self, Receiver<BeatEvent>) {
+        let sequencer = Arc::new(Mutex::new(Sequencer {
+            state: MetroState::Stopped,
+            ..sequencer
+        }));
+        let (wake, woken) = mpsc::channel();
+        let listeners = Arc::new(Mutex::new(Vec::new()));
+        let thread_sequencer = Arc::clone(&sequencer);
+        let thread_listeners = Arc::clone(&listeners);
+        thread::spawn(move || engine_thread(thread_sequencer, woken, thread_listeners));
+        let engine = Self {
+            sequencer,
+            wake,
+            listeners,
+        };
+        let (events, events_rx) = mpsc::channel();
+        engine.add_listener(events);
+        (engine, events_rx)
+    }
+
+    /// Sends every beat from now on to `listener` as well, as whatever it takes them as,
+    /// until its receiver is dropped. Nothing waits on a listener, so each can keep its own
+    /// pace without holding up the clock or the others.
+    pub fn add_listener<T: From<BeatEvent> + Send + 'static>(&self, listener: Sender<T>) {
+        lock(&self.listeners).push(Box::new(move |event: &BeatEvent| {
+            listener.send(T::from(event.clone())).is_ok()
+        }));
+    }
+
+    /// The sequencer, for reading what it's up to or changing what it plays. The clock waits
+    /// while it's held, so it should be let go of straight away; tempo changes and the like
+    /// reach the clock once it is.
+    pub fn sequencer(&self) -> SequencerGuard<'_> {
+        let sequencer = lock(&self.sequencer);
+        let timing = sequencer.timing();
+        SequencerGuard {
+            sequencer,
+            wake: &self.wake,
+            timing,
+        }
+    }
+
+    /// Changes the tempo, from 30 to 300 quarter notes a minute. While playing, the next
+    /// beat comes one new interval after the last. A tempo that isn't a number is ignored.
+    pub fn set_bpm(&self, bpm: f64) {
+        self.sequencer().set_bpm(bpm);
+    }
+
+    /// Switches to bars of `bar` beats of `beat_unit` notes, keeping the place in the bar
+    /// where it still fits. A `beat_unit` other than 2, 4, 8 or 16 is ignored.
+    pub fn set_meter(&self, bar: u32, beat_unit: u32) {
+        let mut sequencer = self.sequencer();
+        if BEAT_UNITS.contains(&beat_unit) {
+            sequencer.beat_unit = beat_unit;
+        }
+        sequencer.set_bar(bar.clamp(1, MAX_BAR));
+    }
+
+    /// Sets what each beat of the bar plays, the first being the downbeat. Beats past the
+    /// end of `sounds` play normally.
+    pub fn set_beat_sounds(&self, sounds: Vec<BeatSound>) {
+        self.sequencer().beat_sounds = sounds;
+    }
+
+    /// Counts in for `bars` bars whenever playback starts; 0 starts on the start beat.
+    pub fn set_count_in(&self, bars: u32) {
+        self.sequencer().count_in_bars = bars;
+    }
+
+    /// Starts playing from the start beat, or the count-in, which is sent straight away.
+    /// Does nothing unless stopped.
+    pub fn start(&self) {
+        let mut sequencer = self.sequencer();
+        if *sequencer.state() == MetroState::Stopped {
+            let now = Instant::now();
+            sequencer.start(now);
+            send(&self.listeners, sequencer.tick(now));
+        }
+    }
+
+    /// Pauses on the beat last played, to carry on from the one after it.
+    pub fn pause(&self) {
+        self.sequencer().pause();
+    }
+
+    /// Carries on from a pause, sending the next beat straight away. Does nothing unless
+    /// paused.
+    pub fn resume(&self) {
+        let mut sequencer = self.sequencer();
+        if matches!(sequencer.state(), MetroState::Paused(_)) {
+            let now = Instant::now();
+            sequencer.resume(now);
+            send(&self.listeners, sequencer.tick(now));
+        }
+    }
+
+    /// Stops playing; the next start begins a fresh take.
+    pub fn stop(&self) {
+        self.sequencer().stop();
+    }
+}
+
+/// Locks `mutex`. A panic elsewhere while holding it leaves nothing half-changed worth
+/// stopping for.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Sends `events` to every listener, forgetting those that have stopped listening.
+fn send(listeners: &Mutex<Vec<Listener>>, events: Vec<BeatEvent>) {
+    if !events.is_empty() {
+        lock(listeners).retain_mut(|listener| events.iter().all(listener));
+    }
+}
+
+/// The engine's [`Sequencer`], held for reading or changing. Letting go of it wakes the
+/// clock if the tempo or playback changed.
+pub struct SequencerGuard<'a> {
+    sequencer: MutexGuard<'a, Sequencer>,
+    wake: &'a Sender<()>,
+    /// The clock's timing when the sequencer was taken.
+    timing: Timing,
+}
+
+impl Deref for SequencerGuard<'_> {
+    type Target = Sequencer;
+
+    fn deref(&self) -> &Sequencer {
+        &self.sequencer
+    }
+}
+
+impl DerefMut for SequencerGuard<'_> {
+    fn deref_mut(&mut self) -> &mut Sequencer {
+        &mut self.sequencer
+    }
+}
+
+impl Drop for SequencerGuard<'_> {
+    fn drop(&mut self) {
+        if self.sequencer.timing() != self.timing {
+            let _ = self.wake.send(());
+        }
+    }
+}
+
+/// When the clock was last set going, and its tick interval and swing, `None` while it's
+/// stopped.
+type Timing = (Option<Instant>, Option<(Duration, f32)>);
+
+/// Keeps time for the engine, turning each tick of the clock and of the polyrhythm's second
+/// voice into what it's due to play.
+fn engine_thread(
+    sequencer: Arc<Mutex<Sequencer>>,
+    woken: Receiver<()>,
+    listeners: Arc<Mutex<Vec<Listener>>>,
+) {
+    let mut timing: Timing = (None, None);
+    let mut schedule: Option<Schedule> = None;
+    // The second voice's schedule, and how many of its ticks are left this bar
+    let mut poly: Option<(Schedule, u32)> = None;
+    loop {
+        let deadline = [
+            schedule.as_ref().map(Schedule::next),
+            poly.as_ref().map(|(poly, _)| poly.next()),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        let woke = match deadline {
+            Some(deadline) => {
+                woken.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => woken.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        if woke == Err(RecvTimeoutError::Disconnected) {
+            return;
+        }
+        let mut sequencer = lock(&sequencer);
+        let now = Instant::now();
+        // Either clock may be the one that's due, or both when beats coincide
+        let mut due = Vec::new();
+        if let Some(schedule) = schedule.as_mut().filter(|s| s.next() <= now) {
+            let at = schedule.next();
+            schedule.advance(now);
+            due.extend(sequencer.tick(at));
+            if due.iter().any(|event| event.tick == Tick::Poly(0)) {
+                poly = sequencer.poly_bar(at);
+            }
+        }
+        if let Some((voice, left)) = poly.as_mut().filter(|(p, _)| p.next() <= now) {
+            let at = voice.next();
+            voice.advance(now);
+            *left -= 1;
+            let event = sequencer.poly_tick(at);
+            if *left == 0 || event.is_none() {
+                poly = None;
+            }
+            due.extend(event);
+        }
+        // Ticks can change the tempo as much as anything done to the sequencer from outside
+        if sequencer.timing() != timing {
+            let (started, clock) = sequencer.timing();
+            schedule = match clock {
+                // A take or a resume counts its ticks from the beat it started with
+                Some((interval, swing)) if started != timing.0 => {
+                    poly = started
+                        .and_then(|at| sequencer.poly_bar(at))
+                        .filter(|_| sequencer.poly_beat == Some(0));
+                    Some(Schedule::new(started.unwrap_or(now), interval).with_swing(swing, false))
+                }
+                Some((interval, swing)) => Some(
+                    match schedule {
+                        Some(schedule) => schedule.retimed(interval),
+                        None => Schedule::new(now, interval),
+                    }
+                    .with_swing(swing, sequencer.sub_beat % 2 == 1),
+                ),
+                None => {
+                    poly = None;
+                    None
+                }
+            };
+            timing = (started, clock);
+        }
+        drop(sequencer);
+        send(&listeners, due);
+    }
+}
+
+/// What a metronome plays and how its tempo and meter move along while playing, apart from
+/// any clock: each tick of the clock is handed to [`Sequencer::tick`], which says what it
+/// plays.
+#[derive(Debug, Clone)]
+pub struct Sequencer {
+    bar: u32,
+    /// The note value of one beat, the bottom of the time signature.
+    pub beat_unit: u32,
+    /// Quarter notes per minute, whatever the beat unit, to a tenth.
+    bpm: f64,
+    /// A tempo picked while playing that waits for `tempo_change` to allow it.
+    pending_bpm: Option<f64>,
+    tempo_change: TempoChange,
+    /// Picking a tempo while playing goes back to the downbeat instead of carrying on from
+    /// the current beat.
+    pub restart_bar_on_tempo: bool,
+    pub glide_target: u32,
+    pub glide_bars: u32,
+    pub glide_curve: GlideCurve,
+    /// The tempo glide in progress, if any.
+    glide: Option<Glide>,
+    /// Ramp up from `warm_up_from` over the first `warm_up_secs` after starting.
+    pub warm_up: bool,
+    pub warm_up_from: u32,
+    pub warm_up_secs: u32,
+    warm_up_ramp: Option<WarmUp>,
+    /// Alternate `gap_audible_bars` bars of clicks with `gap_silent_bars` silent ones.
+    pub gap_trainer: bool,
+    gap_audible_bars: u32,
+    gap_silent_bars: u32,
+    /// Step the tempo from `ramp_start` towards `ramp_end` by `ramp_step` every
+    /// `ramp_every_bars` bars, then hold it or start over if `ramp_loop` is set.
+    ramp_trainer: bool,
+    pub ramp_start: u32,
+    pub ramp_end: u32,
+    ramp_step: u32,
+    ramp_every_bars: u32,
+    pub ramp_loop: bool,
+    /// Whether the ramp trainer is driving the tempo of this take.
+    ramp_active: bool,
+    /// Jump to a random tempo between `drill_min` and `drill_max` every `drill_every_bars`
+    /// bars.
+    drill: bool,
+    pub drill_min: u32,
+    pub drill_max: u32,
+    drill_every_bars: u32,
+    /// What this take's drill tempos are picked from, while the drill is driving the tempo.
+    drill_seed: Option<u64>,
+    /// Downbeats played since the take started.
+    bars_played: u32,
+    /// Whether the gap trainer has silenced the current bar.
+    silent_bar: bool,
+    /// The beat of the bar, counting from 1, that playback starts on.
+    pub start_beat: u32,
+    state: MetroState,
+    /// When the clock was last set going, by starting or resuming, which its ticks are
+    /// counted from.
+    started: Option<Instant>,
+    /// Bars clicked before the take itself starts on a downbeat.
+    pub count_in_bars: u32,
+    /// The sound of each beat of the bar.
+    pub beat_sounds: Vec<BeatSound>,
+    /// The beat sounds set up for other meters, brought back on switching to them again.
+    pub meter_beat_sounds: BTreeMap<u32, Vec<BeatSound>>,
+    /// How the bar splits into groups, like 3+2+2 for 7. Empty for no grouping; otherwise
+    /// it adds up to `bar`.
+    pub groups: Vec<u32>,
+    /// Bar lengths to cycle through while playing, like 4, 3 for a bar of four then one of
+    /// three. Empty to keep to `bar`.
+    pub meter_sequence: Vec<u32>,
+    /// Ticks per beat; every tick after the beat itself plays an off-beat.
+    pub subdivision: u32,
+    /// Where the second eighth or sixteenth of each pair lands within the pair, from 0.5
+    /// (straight) to 0.75 (a hard shuffle).
+    pub swing: f32,
+    /// Play `poly_voice` over the beats, sharing the bar with them.
+    polyrhythm: bool,
+    pub poly_voice: PolyVoice,
+    /// The second voice's beat within the current bar, once the take's first bar started.
+    poly_beat: Option<u32>,
+    /// Which beats of the bar are followed by off-beats, when the beat is subdivided. Beats
+    /// past its end have them.
+    pub off_beat_mask: Vec<bool>,
+    pub pickup_off_beat: PickupOffBeat,
+    /// Only the downbeat is audible; counting carries on as normal.
+    pub downbeat_only: bool,
+    /// Off-beats played since the last main beat.
+    sub_beat: u32,
+}
+
+impl Sequencer {
+    /// A stopped sequencer at 120 quarter notes a minute in bars of `bar` quarter notes, with
+    /// an accented downbeat and nothing else on. A `bar` of 0 counts as 1.
+    pub fn new(bar: u32) -> Self {
+        let bar = bar.max(1);
+        Self {
+            bar,
+            beat_unit: 4,
+            bpm: 120.,
+            pending_bpm: None,
+            tempo_change: TempoChange::Immediate,
+            restart_bar_on_tempo: false,
+            glide_target: 140,
+            glide_bars: 8,
+            glide_curve: GlideCurve::Straight,
+            glide: None,
+            warm_up: false,
+            warm_up_from: 60,
+            warm_up_secs: 30,
+            warm_up_ramp: None,
+            gap_trainer: false,
+            gap_audible_bars: 4,
+            gap_silent_bars: 2,
+            ramp_trainer: false,
+            ramp_start: 80,
+            ramp_end: 120,
+            ramp_step: 5,
+            ramp_every_bars: 4,
+            ramp_loop: false,
+            ramp_active: false,
+            drill: false,
+            drill_min: 80,
+            drill_max: 160,
+            drill_every_bars: 4,
+            drill_seed: None,
+            bars_played: 0,
+            silent_bar: false,
+            start_beat: 1,
+            state: MetroState::Stopped,
+            started: None,
+            count_in_bars: 0,
+            beat_sounds: default_beat_sounds(bar),
+            meter_beat_sounds: BTreeMap::new(),
+            groups: Vec::new(),
+            meter_sequence: Vec::new(),
+            subdivision: 1,
+            swing: 0.5,
+            polyrhythm: false,
+            poly_voice: PolyVoice { divisions: 3 },
+            poly_beat: None,
+            off_beat_mask: Vec::new(),
+            pickup_off_beat: PickupOffBeat::Play,
+            downbeat_only: false,
+            sub_beat: 0,
+        }
+    }
+
+    pub fn bar(&self) -> u32 {
+        self.bar
+    }
+
+    /// The tempo playing now.
+    pub fn bpm(&self) -> f64 {
+        self.bpm
+    }
+
+    /// The tempo last picked, which may still be waiting to take over.
+    pub fn chosen_bpm(&self) -> f64 {
+        self.pending_bpm.unwrap_or(self.bpm)
+    }
+
+    /// The tempo the warm-up is climbing to, while it is.
+    pub fn warm_up_target(&self) -> Option<f64> {
+        self.warm_up_ramp.as_ref().map(|ramp| ramp.to)
+    }
+
+    pub fn state(&self) -> &MetroState {
+        &self.state
+    }
+
+    /// The time from one main beat to the next.
+    pub fn interval(&self) -> Duration {
+        tick_interval(self.bpm, self.beat_unit, 1)
+    }
+
+    pub fn tempo_change(&self) -> TempoChange {
+        self.tempo_change
+    }
+
+    /// Changes when picked tempos take over; going to immediate lets a waiting one in.
+    pub fn set_tempo_change(&mut self, timing: TempoChange) {
+        self.tempo_change = timing;
+        if timing == TempoChange::Immediate {
+            self.apply_pending_bpm();
+        }
+    }
+
+    pub fn gliding(&self) -> bool {
+        self.glide.is_some()
+    }
+
+    pub fn gap_audible_bars(&self) -> u32 {
+        self.gap_audible_bars
+    }
+
+    /// Sets how many bars of clicks the gap trainer plays between silences, at least 1.
+    pub fn set_gap_audible_bars(&mut self, bars: u32) {
+        self.gap_audible_bars = bars.max(1);
+    }
+
+    pub fn gap_silent_bars(&self) -> u32 {
+        self.gap_silent_bars
+    }
+
+    /// Sets how many bars each of the gap trainer's silences lasts, at least 1.
+    pub fn set_gap_silent_bars(&mut self, bars: u32) {
+        self.gap_silent_bars = bars.max(1);
+    }
+
+    pub fn ramp_trainer(&self) -> bool {
+        self.ramp_trainer
+    }
+
+    /// Turns the ramp trainer on or off. Either way it leaves the tempo alone until the
+    /// next take.
+    pub fn set_ramp_trainer(&mut self, on: bool) {
+        if on != self.ramp_trainer {
+            self.ramp_trainer = on;
+            self.ramp_active = false;
+        }
+    }
+
+    pub fn ramp_step(&self) -> u32 {
+        self.ramp_step
+    }
+
+    /// Sets how far each of the ramp trainer's steps moves the tempo, at least 1.
+    pub fn set_ramp_step(&mut self, step: u32) {
+        self.ramp_step = step.max(1);
+    }
+
+    pub fn ramp_every_bars(&self) -> u32 {
+        self.ramp_every_bars
+    }
+
+    /// Sets how many bars the ramp trainer holds each tempo for, at least 1.
+    pub fn set_ramp_every_bars(&mut self, bars: u32) {
+        self.ramp_every_bars = bars.max(1);
+    }
+
+    /// Whether the ramp trainer is driving the tempo of this take.
+    pub fn ramp_active(&self) -> bool {
+        self.ramp_active
+    }
+
+    /// The ramp trainer's next step while it drives the tempo: the bar of this step,
+    /// counting from 1, and the tempo the next one brings.
+    pub fn ramp_next_step(&self) -> Option<(u32, u32)> {
+        if !self.ramp_active {
+            return None;
+        }
+        ramp_next_step(
+            (
+                self.ramp_start,
+                self.ramp_end,
+                self.ramp_step,
+                self.ramp_every_bars,
+            ),
+            self.ramp_loop,
+            self.bars_played,
+        )
+    }
+
+    pub fn drill(&self) -> bool {
+        self.drill
+    }
+
+    /// Turns the tempo drill on from the next take, or off straight away.
+    pub fn set_drill(&mut self, on: bool) {
+        if on != self.drill {
+            self.drill = on;
+            self.drill_seed = None;
+        }
+    }
+
+    pub fn drill_every_bars(&self) -> u32 {
+        self.drill_every_bars
+    }
+
+    /// Sets how many bars the drill holds each tempo for, at least 1.
+    pub fn set_drill_every_bars(&mut self, bars: u32) {
+        self.drill_every_bars = bars.max(1);
+    }
+
+    pub fn polyrhythm(&self) -> bool {
+        self.polyrhythm
+    }
+
+    /// The second voice's beat within the current bar, while it's playing.
+    pub fn poly_beat(&self) -> Option<u32> {
+        self.poly_beat
+    }
+
+    /// Turns the polyrhythm on from the next bar, or off straight away.
+    pub fn set_polyrhythm(&mut self, on: bool) {
+        self.polyrhythm = on;
+        if !on {
+            self.poly_beat = None;
+        }
+    }
+
+    /// Picks a tempo, from 30 to 300 quarter notes a minute, stopping whatever else was
+    /// moving it. While playing it takes over as `tempo_change` allows. A tempo that isn't a
+    /// number is ignored.
+    pub fn set_bpm(&mut self, bpm: f64) {
+        // NaN gets through `clamp`, and the interval can't be worked out from it
+        if bpm.is_nan() {
+            return;
+        }
+        let bpm = round_bpm(bpm.clamp(30., 300.));
+        self.glide = None;
+        self.warm_up_ramp = None;
+        self.ramp_active = false;
+        self.drill_seed = None;
+        if !self.state.is_running() || self.tempo_change == TempoChange::Immediate {
+            self.bpm = bpm;
+            self.pending_bpm = None;
+            self.state = self.state.retimed(self.bar, self.restart_bar_on_tempo);
+        } else {
+            self.pending_bpm = Some(bpm);
+        }
+    }
+
+    /// Switches to `bpm` outright, as bringing back earlier settings does, stopping whatever
+    /// was moving the tempo unless it's already playing at `bpm` with nothing moving it.
+    pub fn jump_to_bpm(&mut self, bpm: f64) {
+        if bpm != self.bpm || self.pending_bpm.is_some() || self.warm_up_ramp.is_some() {
+            self.glide = None;
+            self.warm_up_ramp = None;
+            self.ramp_active = false;
+            self.drill_seed = None;
+            self.pending_bpm = None;
+            self.bpm = bpm;
+        }
+    }
+
+    /// Starts a glide from the current tempo to `glide_target` over `glide_bars` bars, or
+    /// cancels the one going.
+    pub fn toggle_glide(&mut self) {
+        self.glide = match self.glide {
+            Some(_) => None,
+            None => {
+                self.warm_up_ramp = None;
+                self.apply_pending_bpm();
+                Some(Glide::new(
+                    self.bpm,
+                    self.glide_target as f64,
+                    self.glide_curve,
+                    self.glide_bars * self.bar,
+                ))
+            }
+        };
+    }
+
+    /// Switches to a bar of `bar` beats, bringing back the beat sounds last set up for it.
+    /// A `bar` of 0 counts as 1.
+    pub fn set_bar(&mut self, bar: u32) {
+        let bar = bar.max(1);
+        // Each meter keeps its own accents; one not set up yet starts from these
+        let mut sounds = self.beat_sounds.clone();
+        sounds.resize(bar as usize, BeatSound::Normal);
+        let sounds = self.meter_beat_sounds.remove(&bar).unwrap_or(sounds);
+        let left = std::mem::replace(&mut self.beat_sounds, sounds);
+        if left != default_beat_sounds(self.bar) {
+            self.meter_beat_sounds.insert(self.bar, left);
+        }
+        self.bar = bar;
+        self.state = self.state.resized(bar);
+        self.start_beat = self.start_beat.min(bar);
+        // A grouping only fits the bar it was made for
+        if !groups_fill(&self.groups, bar) {
+            self.groups.clear();
+        }
+    }
+
+    /// Starts a take at `now`, from the count-in or the start beat, with whichever trainer
+    /// is on taking over the tempo. The first tick is due straight away.
+    pub fn start(&mut self, now: Instant) {
+        self.bars_played = 0;
+        self.poly_beat = None;
+        // As if the beat before had played out its off-beats, so the take opens on a beat
+        self.sub_beat = self.subdivision.saturating_sub(1);
+        if let Some(bar) = sequence_bar(&self.meter_sequence, 0) {
+            self.set_bar(bar);
+        }
+        self.state = if self.count_in_bars > 0 {
+            MetroState::counting_in(self.count_in_bars, self.bar)
+        } else {
+            MetroState::starting_on(self.start_beat, self.bar)
+        };
+        if self.ramp_trainer {
+            self.ramp_active = true;
+            self.bpm = self.ramp_start as f64;
+        } else if self.drill {
+            self.drill_seed = Some(drill_seed());
+        } else if self.warm_up && (self.warm_up_from as f64) < self.bpm {
+            self.warm_up_ramp = Some(WarmUp {
+                from: self.warm_up_from as f64,
+                to: self.bpm,
+                started: now,
+                duration: Duration::from_secs(self.warm_up_secs as u64),
+            });
+            self.bpm = self.warm_up_from as f64;
+        }
+        self.started = Some(now);
+    }
+
+    /// Stops the take, landing on the tempo it was heading for.
+    pub fn stop(&mut self) {
+        self.state = MetroState::Stopped;
+        self.started = None;
+        self.glide = None;
+        self.ramp_active = false;
+        self.drill_seed = None;
+        if let Some(ramp) = self.warm_up_ramp.take() {
+            self.bpm = ramp.to;
+        }
+        self.apply_pending_bpm();
+    }
+
+    /// Pauses on the beat last played. Does nothing unless playing.
+    pub fn pause(&mut self) {
+        if self.state.is_running() {
+            self.state = MetroState::Paused(self.state.index().unwrap_or(0));
+            self.poly_beat = None;
+        }
+    }
+
+    /// Carries on from a pause at `now`, with the next tick due straight away.
+    pub fn resume(&mut self, now: Instant) {
+        if matches!(self.state, MetroState::Paused(_)) {
+            self.state = self.state.resumed();
+            self.sub_beat = self.subdivision.saturating_sub(1);
+            self.started = Some(now);
+        }
+    }
+
+    fn apply_pending_bpm(&mut self) {
+        if let Some(bpm) = self.pending_bpm.take() {
+            self.bpm = bpm;
+        }
+    }
+
+    /// The tick interval and swing the clock should keep, `None` while it shouldn't tick.
+    fn clock(&self) -> Option<(Duration, f32)> {
+        // Swing pairs up eighths or sixteenths; other subdivisions stay straight
+        let swing = if self.subdivision.is_multiple_of(2) {
+            self.swing
+        } else {
+            0.5
+        };
+        self.state.is_running().then(|| {
+            (
+                tick_interval(self.bpm, self.beat_unit, self.subdivision),
+                swing,
+            )
+        })
+    }
+
+    fn timing(&self) -> Timing {
+        (self.started, self.clock())
+    }
+
+    /// Moves on by the tick of the clock due `at`, returning what it plays: the tick
+    /// itself, then the second voice's first beat if the tick starts a bar of the
+    /// polyrhythm. Nothing while stopped or paused, for a tick already in flight then.
+    pub fn tick(&mut self, at: Instant) -> Vec<BeatEvent> {
+        if !self.state.is_running() {
+            Vec::new()
+        } else if self.sub_beat + 1 < self.subdivision {
+            self.off_beat(at).into_iter().collect()
+        } else {
+            self.beat(at)
+        }
+    }
+
+    fn beat(&mut self, at: Instant) -> Vec<BeatEvent> {
+        let Some((_, state)) = self.state.advance(self.bar) else {
+            return Vec::new();
+        };
+        let index = state.index().unwrap_or(0);
+        let downbeat = index == 0;
+        let counting_in = matches!(state, MetroState::CountIn { .. });
+        if state == MetroState::FirstBeat {
+            self.bars_played += 1;
+            // The bar just begun takes its length before its downbeat plays
+            if let Some(bar) = sequence_bar(&self.meter_sequence, self.bars_played - 1)
+                .filter(|&bar| bar != self.bar)
+            {
+                self.set_bar(bar);
+            }
+            if self.ramp_active {
+                self.bpm = ramp_bpm(
+                    self.ramp_start,
+                    self.ramp_end,
+                    self.ramp_step,
+                    (self.bars_played - 1) / self.ramp_every_bars,
+                    self.ramp_loop,
+                ) as f64;
+            }
+            if let Some(seed) = self.drill_seed {
+                let bar = self.bars_played - 1;
+                if bar.is_multiple_of(self.drill_every_bars) {
+                    self.bpm = drill_bpm(
+                        seed,
+                        bar / self.drill_every_bars,
+                        self.drill_min,
+                        self.drill_max,
+                    ) as f64;
+                }
+            }
+        }
+        let silent = self.gap_trainer
+            && !counting_in
+            && gap_is_silent(
+                self.bars_played.saturating_sub(1),
+                self.gap_audible_bars,
+                self.gap_silent_bars,
+            );
+        let sound = self
+            .beat_sounds
+            .get(index as usize)
+            .copied()
+            .unwrap_or(BeatSound::Normal);
+        let beat = grouped_beat(sound.beat(counting_in), &self.groups, index)
+            .filter(|_| !silent && (!self.downbeat_only || downbeat || counting_in));
+        if self.tempo_change == TempoChange::NextBeat || downbeat {
+            self.apply_pending_bpm();
+        }
+        if matches!(self.state, MetroState::CountIn { .. }) && !counting_in {
+            // The warm-up is part of the take, so it starts with it
+            if let Some(ramp) = &mut self.warm_up_ramp {
+                ramp.started = at;
+            }
+        }
+        // Nothing about the take changes until it has started
+        if let Some(glide) = self.glide.as_mut().filter(|_| !counting_in) {
+            // The clock is retimed from this beat, so the next beat comes one interval of
+            // the glided tempo after it
+            match glide.step() {
+                Some(bpm) => self.bpm = bpm,
+                None => self.glide = None,
+            }
+        }
+        if let Some(ramp) = self.warm_up_ramp.as_ref().filter(|_| !counting_in) {
+            match ramp.bpm_at(at) {
+                Some(bpm) => self.bpm = bpm,
+                None => self.bpm = self.warm_up_ramp.take().map_or(self.bpm, |ramp| ramp.to),
+            }
+        }
+        self.state = state;
+        self.silent_bar = silent;
+        self.sub_beat = 0;
+        let mut events = vec![BeatEvent {
+            tick: Tick::Beat(index),
+            beat,
+            counting_in,
+            at,
+        }];
+        if self.polyrhythm && self.state == MetroState::FirstBeat {
+            self.poly_beat = Some(0);
+            events.push(BeatEvent {
+                tick: Tick::Poly(0),
+                beat: (!silent).then_some(Beat::PolyBeat),
+                counting_in,
+                at,
+            });
+        }
+        events
+    }
+
+    fn off_beat(&mut self, at: Instant) -> Option<BeatEvent> {
+        let index = self.state.index()?;
+        // Beats without an off-beat still take the tick, so the timing is the same
+        let flagged = self.off_beat_mask.get(index as usize) != Some(&false);
+        let last = self.sub_beat + 2 >= self.subdivision;
+        let position = self.sub_beat + 1;
+        let beat = self
+            .state
+            .off_beat(self.bar, self.pickup_off_beat, last)
+            .map(|beat| subdivision_beat(beat, position, self.subdivision))
+            .filter(|_| flagged && !self.downbeat_only && !self.silent_bar);
+        self.sub_beat = position;
+        Some(BeatEvent {
+            tick: Tick::OffBeat(index, position),
+            beat,
+            counting_in: matches!(self.state, MetroState::CountIn { .. }),
+            at,
+        })
+    }
+
+    /// Where the second voice's clock starts for the bar the downbeat `at` began: one beat
+    /// of it after the downbeat, and how many beats of it are left. A tempo change during
+    /// the bar only reaches it from the next one.
+    fn poly_bar(&self, at: Instant) -> Option<(Schedule, u32)> {
+        let divisions = self.poly_voice.divisions.max(1);
+        let interval = self.interval() * self.bar / divisions;
+        (divisions > 1).then(|| (Schedule::new(at, interval), divisions - 1))
+    }
+
+    /// Moves the second voice on by its tick due `at`, returning what it plays, or `None`
+    /// once it has stopped for the bar.
+    pub fn poly_tick(&mut self, at: Instant) -> Option<BeatEvent> {
+        let index = self.poly_beat.as_mut()?;
+        *index += 1;
+        Some(BeatEvent {
+            tick: Tick::Poly(*index),
+            beat: (!self.silent_bar).then_some(Beat::PolyBeat),
+            counting_in: false,
+            at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What the next `count` ticks play, as their tick and beat.
+    fn ticks(sequencer: &mut Sequencer, count: usize) -> Vec<(Tick, Option<Beat>)> {
+        (0..count)
+            .flat_map(|_| sequencer.tick(Instant::now()))
+            .map(|event| (event.tick, event.beat))
+            .collect()
+    }
+
+    /// What the next `count` ticks play, leaving out which ticks they are.
+    fn beats(sequencer: &mut Sequencer, count: usize) -> Vec<Option<Beat>> {
+        ticks(sequencer, count)
+            .into_iter()
+            .map(|(_, beat)| beat)
+            .collect()
+    }
+
+    /// The tempo of each of the next `count` main beats, after the beat's own changes.
+    fn tempos(sequencer: &mut Sequencer, count: usize) -> Vec<f64> {
+        (0..count)
+            .map(|_| {
+                sequencer.tick(Instant::now());
+                sequencer.bpm()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sequencer_plays_each_beat_its_sound() {
+        let mut sequencer = Sequencer::new(3);
+        sequencer.beat_sounds = vec![BeatSound::Accent, BeatSound::Silent];
+        assert_eq!(sequencer.tick(Instant::now()), []);
+        sequencer.start(Instant::now());
+        assert_eq!(
+            ticks(&mut sequencer, 4),
+            [
+                (Tick::Beat(0), Some(Beat::FirstBeat)),
+                (Tick::Beat(1), None),
+                (Tick::Beat(2), Some(Beat::Beat)),
+                (Tick::Beat(0), Some(Beat::FirstBeat)),
+            ]
+        );
+    }
+
+    #[test]
+    fn sequencer_count_in_is_heard_in_full() {
+        let mut sequencer = Sequencer::new(2);
+        sequencer.beat_sounds = vec![BeatSound::Accent, BeatSound::Silent];
+        sequencer.count_in_bars = 1;
+        sequencer.start(Instant::now());
+        let events = (0..4)
+            .flat_map(|_| sequencer.tick(Instant::now()))
+            .map(|event| (event.beat, event.counting_in))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            [
+                (Some(Beat::FirstBeat), true),
+                (Some(Beat::Beat), true),
+                (Some(Beat::FirstBeat), false),
+                (None, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn sequencer_meter_changes_keep_the_place() {
+        let mut sequencer = Sequencer::new(4);
+        sequencer.start(Instant::now());
+        ticks(&mut sequencer, 3);
+        sequencer.beat_unit = 8;
+        sequencer.set_bar(6);
+        assert_eq!(sequencer.interval(), Duration::from_millis(250));
+        let indices = ticks(&mut sequencer, 8)
+            .into_iter()
+            .map(|(tick, _)| tick)
+            .collect::<Vec<_>>();
+        assert_eq!(indices, [3, 4, 5, 0, 1, 2, 3, 4].map(Tick::Beat));
+        // Past the end of a shorter bar, the next beat is the downbeat
+        sequencer.set_bar(2);
+        assert_eq!(
+            ticks(&mut sequencer, 1),
+            [(Tick::Beat(0), Some(Beat::FirstBeat))]
+        );
+    }
+
+    #[test]
+    fn sequencer_keeps_its_tempo_for_nan() {
+        let mut sequencer = Sequencer::new(4);
+        sequencer.set_bpm(f64::NAN);
+        assert_eq!(sequencer.bpm(), 120.);
+        sequencer.set_bpm(f64::INFINITY);
+        assert_eq!(sequencer.interval(), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn subdivisions_follow_the_off_beat_mask_and_pickup() {
+        let mut sequencer = Sequencer::new(2);
+        sequencer.subdivision = 4;
+        sequencer.off_beat_mask = vec![false];
+        sequencer.pickup_off_beat = PickupOffBeat::Soften;
+        sequencer.start(Instant::now());
+        assert_eq!(
+            ticks(&mut sequencer, 9),
+            [
+                (Tick::Beat(0), Some(Beat::FirstBeat)),
+                (Tick::OffBeat(0, 1), None),
+                (Tick::OffBeat(0, 2), None),
+                (Tick::OffBeat(0, 3), None),
+                (Tick::Beat(1), Some(Beat::Beat)),
+                (Tick::OffBeat(1, 1), Some(Beat::Subdivision)),
+                (Tick::OffBeat(1, 2), Some(Beat::OffBeat)),
+                (Tick::OffBeat(1, 3), Some(Beat::SoftOffBeat)),
+                (Tick::Beat(0), Some(Beat::FirstBeat)),
+            ]
+        );
+    }
+
+    #[test]
+    fn swing_only_pairs_even_subdivisions() {
+        let mut sequencer = Sequencer::new(4);
+        sequencer.swing = 0.6;
+        assert_eq!(sequencer.clock(), None);
+        sequencer.start(Instant::now());
+        sequencer.subdivision = 2;
+        assert_eq!(sequencer.clock(), Some((Duration::from_millis(250), 0.6)));
+        sequencer.subdivision = 3;
+        assert_eq!(sequencer.clock().map(|(_, swing)| swing), Some(0.5));
+        sequencer.pause();
+        assert_eq!(sequencer.clock(), None);
+    }
+
+    #[test]
+    fn groups_and_downbeat_only_shape_the_bar() {
+        let mut sequencer = Sequencer::new(5);
+        sequencer.groups = vec![3, 2];
+        sequencer.beat_sounds[3] = BeatSound::Accent;
+        sequencer.start(Instant::now());
+        assert_eq!(
+            beats(&mut sequencer, 5),
+            [
+                Some(Beat::FirstBeat),
+                Some(Beat::Beat),
+                Some(Beat::Beat),
+                Some(Beat::MediumAccent),
+                Some(Beat::Beat),
+            ]
+        );
+        sequencer.downbeat_only = true;
+        assert_eq!(
+            beats(&mut sequencer, 5),
+            [Some(Beat::FirstBeat), None, None, None, None]
+        );
+    }
+
+    #[test]
+    fn start_beat_and_pause_keep_the_place() {
+        let mut sequencer = Sequencer::new(4);
+        sequencer.start_beat = 3;
+        sequencer.start(Instant::now());
+        assert_eq!(beats(&mut sequencer, 1), [Some(Beat::Beat)]);
+        sequencer.pause();
+        assert_eq!(sequencer.tick(Instant::now()), []);
+        sequencer.resume(Instant::now());
+        assert_eq!(
+            ticks(&mut sequencer, 2),
+            [
+                (Tick::Beat(3), Some(Beat::Beat)),
+                (Tick::Beat(0), Some(Beat::FirstBeat))
+            ]
+        );
+    }
+
+    #[test]
+    fn gap_trainer_silences_whole_bars() {
+        let mut sequencer = Sequencer::new(2);
+        sequencer.subdivision = 2;
+        sequencer.gap_trainer = true;
+        sequencer.set_gap_audible_bars(1);
+        sequencer.set_gap_silent_bars(1);
+        sequencer.start(Instant::now());
+        let heard = beats(&mut sequencer, 8)
+            .into_iter()
+            .map(|beat| beat.is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(heard, [true, true, true, true, false, false, false, false]);
+    }
+
+    #[test]
+    fn meter_sequence_sets_each_bar_before_its_downbeat() {
+        let mut sequencer = Sequencer::new(4);
+        sequencer.meter_sequence = vec![2, 3];
+        sequencer.start(Instant::now());
+        assert_eq!(sequencer.bar(), 2);
+        let bars = (0..7)
+            .map(|_| {
+                sequencer.tick(Instant::now());
+                sequencer.bar()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(bars, [2, 2, 3, 3, 3, 2, 2]);
+    }
+
+    #[test]
+    fn one_beat_bars_of_a_meter_sequence_play_once() {
+        let mut sequencer = Sequencer::new(4);
+        sequencer.meter_sequence = vec![4, 1];
+        sequencer.start(Instant::now());
+        let ticks = ticks(&mut sequencer, 10);
+        let bars = ticks
+            .split(|&(tick, _)| tick == Tick::Beat(0))
+            .skip(1)
+            .map(|bar| bar.len() + 1)
+            .collect::<Vec<_>>();
+        assert_eq!(bars, [4, 1, 4, 1]);
+        assert_eq!(ticks[4], (Tick::Beat(0), Some(Beat::FirstBeat)));
+    }
+
+    #[test]
+    fn zero_bars_and_steps_count_as_one() {
+        let mut sequencer = Sequencer::new(0);
+        assert_eq!((sequencer.bar(), sequencer.beat_sounds.len()), (1, 1));
+        sequencer.set_bar(0);
+        sequencer.meter_sequence = vec![0, 2];
+        sequencer.gap_trainer = true;
+        sequencer.set_gap_audible_bars(0);
+        sequencer.set_gap_silent_bars(0);
+        sequencer.set_ramp_trainer(true);
+        (sequencer.ramp_start, sequencer.ramp_end) = (100, 110);
+        sequencer.ramp_loop = true;
+        sequencer.set_ramp_step(0);
+        sequencer.set_ramp_every_bars(0);
+        assert_eq!(
+            (
+                sequencer.gap_audible_bars(),
+                sequencer.gap_silent_bars(),
+                sequencer.ramp_step(),
+                sequencer.ramp_every_bars(),
+            ),
+            (1, 1, 1, 1)
+        );
+        sequencer.start(Instant::now());
+        assert_eq!(sequencer.bar(), 1);
+        assert_eq!(tempos(&mut sequencer, 3), [100., 101., 101.]);
+        // The drill takes over from a ramp stopped and set going again
+        sequencer.stop();
+        sequencer.set_ramp_trainer(false);
+        sequencer.set_drill(true);
+        sequencer.set_drill_every_bars(0);
+        assert_eq!(sequencer.drill_every_bars(), 1);
+        sequencer.start(Instant::now());
+        assert_eq!(beats(&mut sequencer, 3).len(), 3);
+    }
+
+    #[test]
+    fn picked_tempos_wait_for_the_next_bar() {
+        let mut sequencer = Sequencer::new(2);
+        sequencer.set_tempo_change(TempoChange::NextBar);
+        sequencer.start(Instant::now());
+        sequencer.tick(Instant::now());
+        sequencer.set_bpm(90.);
+        assert_eq!((sequencer.bpm(), sequencer.chosen_bpm()), (120., 90.));
+        assert_eq!(tempos(&mut sequencer, 2), [120., 90.]);
+        // Stopping lands on a tempo still waiting
+        sequencer.set_bpm(100.);
+        sequencer.stop();
+        assert_eq!(sequencer.bpm(), 100.);
+    }
+
+    #[test]
+    fn ramp_trainer_steps_each_few_bars() {
+        let mut sequencer = Sequencer::new(2);
+        sequencer.set_ramp_trainer(true);
+        (sequencer.ramp_start, sequencer.ramp_end) = (100, 110);
+        sequencer.set_ramp_step(5);
+        sequencer.set_ramp_every_bars(1);
+        sequencer.start(Instant::now());
+        assert_eq!(
+            tempos(&mut sequencer, 6),
+            [100., 100., 105., 105., 110., 110.]
+        );
+        assert_eq!(sequencer.ramp_next_step(), None);
+        // Picking a tempo takes it back from the trainer
+        sequencer.set_bpm(90.);
+        assert!(!sequencer.ramp_active());
+        assert_eq!(tempos(&mut sequencer, 2), [90., 90.]);
+    }
+
+    #[test]
+    fn drill_changes_tempo_on_its_bars() {
+        let mut sequencer = Sequencer::new(2);
+        sequencer.set_drill(true);
+        (sequencer.drill_min, sequencer.drill_max) = (60, 200);
+        sequencer.set_drill_every_bars(2);
+        sequencer.start(Instant::now());
+        let tempos = tempos(&mut sequencer, 8);
+        assert!(tempos.iter().all(|bpm| (60. ..=200.).contains(bpm)));
+        assert_eq!(tempos[..4], [tempos[0]; 4]);
+        assert_eq!(tempos[4..], [tempos[4]; 4]);
+    }
+
+    #[test]
+    fn glide_steps_every_beat_of_the_take() {
+        let mut sequencer = Sequencer::new(2);
+        sequencer.count_in_bars = 1;
+        (sequencer.glide_target, sequencer.glide_bars) = (124, 2);
+        sequencer.start(Instant::now());
+        sequencer.toggle_glide();
+        assert_eq!(
+            tempos(&mut sequencer, 7),
+            [120., 120., 121., 122., 123., 124., 124.]
+        );
+        assert!(!sequencer.gliding());
+    }
+
+    #[test]
+    fn warm_up_starts_with_the_take_and_ends_on_stopping() {
+        let mut sequencer = Sequencer::new(2);
+        sequencer.warm_up = true;
+        (sequencer.warm_up_from, sequencer.warm_up_secs) = (60, 60);
+        sequencer.count_in_bars = 1;
+        let start = Instant::now();
+        sequencer.start(start);
+        assert_eq!(sequencer.warm_up_target(), Some(120.));
+        sequencer.tick(start);
+        sequencer.tick(start + Duration::from_secs(10));
+        let downbeat = start + Duration::from_secs(20);
+        sequencer.tick(downbeat);
+        assert_eq!(sequencer.bpm(), 60.);
+        sequencer.tick(downbeat + Duration::from_secs(30));
+        assert_eq!(sequencer.bpm(), 90.);
+        sequencer.stop();
+        assert_eq!((sequencer.bpm(), sequencer.warm_up_target()), (120., None));
+    }
+
+    #[test]
+    fn polyrhythm_starts_with_each_bar() {
+        let mut sequencer = Sequencer::new(4);
+        sequencer.set_polyrhythm(true);
+        let now = Instant::now();
+        sequencer.start(now);
+        assert_eq!(
+            ticks(&mut sequencer, 1),
+            [
+                (Tick::Beat(0), Some(Beat::FirstBeat)),
+                (Tick::Poly(0), Some(Beat::PolyBeat)),
+            ]
+        );
+        let (schedule, left) = sequencer.poly_bar(now).unwrap();
+        assert_eq!(
+            (schedule.next() - now, left),
+            (Duration::from_secs(2) / 3, 2)
+        );
+        assert_eq!(
+            sequencer.poly_tick(now).map(|event| event.tick),
+            Some(Tick::Poly(1))
+        );
+        sequencer.set_polyrhythm(false);
+        assert_eq!(sequencer.poly_tick(now), None);
+    }
+
+    #[test]
+    fn glides_land_on_the_target() {
+        let mut straight = Glide::new(100., 140., GlideCurve::Straight, 4);
+        let tempos = std::iter::from_fn(|| straight.step()).collect::<Vec<_>>();
+        assert_eq!(tempos, [110., 120., 130., 140.]);
+
+        let mut curved = Glide::new(140., 100., GlideCurve::Curved, 4);
+        let tempos = std::iter::from_fn(|| curved.step()).collect::<Vec<_>>();
+        assert_eq!(tempos.len(), 4);
+        assert_eq!(tempos.last(), Some(&100.));
+        assert!(tempos.windows(2).all(|pair| pair[0] >= pair[1]));
+        // Eased ends move less than the middle
+        assert!(140. - tempos[0] < tempos[1] - tempos[2]);
+    }
+
+    #[test]
+    fn warm_up_climbs_linearly_then_ends() {
+        let started = Instant::now();
+        let ramp = WarmUp {
+            from: 60.,
+            to: 120.,
+            started,
+            duration: Duration::from_secs(30),
+        };
+        let at = |secs| ramp.bpm_at(started + Duration::from_secs(secs));
+        assert_eq!(at(0), Some(60.));
+        assert_eq!(at(15), Some(90.));
+        assert_eq!(at(29), Some(118.));
+        assert_eq!(at(30), None);
+    }
+
+    #[test]
+    fn groups_start_where_the_last_one_ended() {
+        let starts = (0..7)
+            .filter(|&beat| starts_group(&[3, 2, 2], beat))
+            .collect::<Vec<_>>();
+        assert_eq!(starts, [0, 3, 5]);
+        assert!(!starts_group(&[u32::MAX, 5], 4));
+        assert!(groups_fill(&[3, 2, 2], 7));
+        // Parts too big to add up don't wrap round to fit
+        assert!(!groups_fill(&[u32::MAX, 5], 4));
+    }
+
+    #[test]
+    fn only_the_halfway_tick_is_an_off_beat() {
+        let ticks = |subdivision| {
+            (1..subdivision)
+                .map(|position| subdivision_beat(Beat::OffBeat, position, subdivision))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ticks(2), [Beat::OffBeat]);
+        assert_eq!(ticks(3), [Beat::Subdivision, Beat::Subdivision]);
+        assert_eq!(
+            ticks(4),
+            [Beat::Subdivision, Beat::OffBeat, Beat::Subdivision]
+        );
+        assert_eq!(subdivision_beat(Beat::SoftOffBeat, 3, 4), Beat::SoftOffBeat);
+    }
+
+    #[test]
+    fn later_groups_start_on_the_medium_accent() {
+        let accent = || Some(Beat::FirstBeat);
+        assert_eq!(grouped_beat(accent(), &[3, 2, 2], 0), accent());
+        assert_eq!(
+            grouped_beat(accent(), &[3, 2, 2], 3),
+            Some(Beat::MediumAccent)
+        );
+        assert_eq!(
+            grouped_beat(Some(Beat::Beat), &[3, 2, 2], 5),
+            Some(Beat::Beat)
+        );
+        assert_eq!(grouped_beat(None, &[3, 2, 2], 5), None);
+        // An accent the user put on a beat without groups keeps its full weight
+        assert_eq!(grouped_beat(accent(), &[], 3), accent());
+        assert_eq!(grouped_beat(accent(), &[3, 2, 2], 4), accent());
+    }
+
+    #[test]
+    fn meter_sequences_cycle() {
+        let bars = (0..5)
+            .map(|bar| sequence_bar(&[4, 3], bar))
+            .collect::<Vec<_>>();
+        assert_eq!(bars, [Some(4), Some(3), Some(4), Some(3), Some(4)]);
+        assert_eq!(sequence_bar(&[], 2), None);
+    }
+
+    #[test]
+    fn gap_trainer_alternates_bars() {
+        let silent = (0..9)
+            .map(|bar| gap_is_silent(bar, 2, 1))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            silent,
+            [false, false, true, false, false, true, false, false, true]
+        );
+    }
+
+    #[test]
+    fn drill_tempos_stay_in_range_and_repeat_by_seed() {
+        let tempos = (0..100)
+            .map(|change| drill_bpm(7, change, 140, 90))
+            .collect::<Vec<_>>();
+        assert!(tempos.iter().all(|bpm| (90..=140).contains(bpm)));
+        assert!(tempos.iter().any(|&bpm| bpm != tempos[0]));
+        assert_eq!(drill_bpm(7, 3, 90, 140), tempos[3]);
+        assert_eq!(drill_bpm(1, 0, 120, 120), 120);
+    }
+
+    #[test]
+    fn ramp_trainer_steps_then_holds_or_loops() {
+        let held = (0..6)
+            .map(|steps| ramp_bpm(100, 110, 4, steps, false))
+            .collect::<Vec<_>>();
+        assert_eq!(held, [100, 104, 108, 110, 110, 110]);
+        let looped = (0..6)
+            .map(|steps| ramp_bpm(100, 110, 4, steps, true))
+            .collect::<Vec<_>>();
+        assert_eq!(looped, [100, 104, 108, 110, 100, 104]);
+        assert_eq!(ramp_bpm(120, 90, 10, 2, false), 100);
+    }
+
+    #[test]
+    fn ramp_preview_counts_down_to_the_next_step() {
+        let trainer = (100, 110, 4, 2);
+        let preview = (0..=8)
+            .map(|bars| ramp_next_step(trainer, false, bars))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            preview,
+            [
+                None,
+                Some((1, 104)),
+                Some((2, 104)),
+                Some((1, 108)),
+                Some((2, 108)),
+                Some((1, 110)),
+                Some((2, 110)),
+                None,
+                None,
+            ]
+        );
+        assert_eq!(ramp_next_step(trainer, true, 7), Some((1, 100)));
+    }
+
+    #[test]
+    fn engine_sends_beats_until_stopped() {
+        let (engine, beats) = MetronomeEngine::new(300., 2, 16);
+        engine.start();
+        let first = beats.recv().unwrap();
+        let second = beats.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!((first.tick, second.tick), (Tick::Beat(0), Tick::Beat(1)));
+        assert_eq!(second.at - first.at, Duration::from_millis(50));
+        engine.stop();
+        while beats.recv_timeout(Duration::from_millis(100)).is_ok() {}
+        assert!(beats.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn engine_follows_changes_to_its_sequencer() {
+        let (engine, beats) = MetronomeEngine::new(300., 4, 4);
+        engine.start();
+        beats.recv().unwrap();
+        engine.sequencer().subdivision = 2;
+        let ticks = (0..3)
+            .map(|_| beats.recv_timeout(Duration::from_secs(1)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            ticks.iter().map(|event| event.tick).collect::<Vec<_>>(),
+            [Tick::OffBeat(0, 1), Tick::Beat(1), Tick::OffBeat(1, 1)]
+        );
+        assert_eq!(ticks[2].at - ticks[1].at, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn engine_plays_one_beat_bars_as_downbeats() {
+        let (engine, beats) = MetronomeEngine::new(300., 4, 16);
+        engine.set_meter(1, 16);
+        engine.start();
+        let played = (0..3)
+            .map(|_| beats.recv_timeout(Duration::from_secs(1)).unwrap())
+            .map(|event| (event.tick, event.beat))
+            .collect::<Vec<_>>();
+        assert_eq!(played, [(Tick::Beat(0), Some(Beat::FirstBeat)); 3]);
+    }
+
+    #[test]
+    fn engine_sends_every_listener_each_beat() {
+        let (engine, beats) = MetronomeEngine::new(300., 4, 16);
+        let (listener, heard) = mpsc::channel::<BeatEvent>();
+        engine.add_listener(listener);
+        // One listener going away, or falling behind, doesn't stop the others hearing
+        drop(beats);
+        engine.start();
+        let ticks = (0..8)
+            .map(|_| heard.recv_timeout(Duration::from_secs(1)).unwrap().tick)
+            .collect::<Vec<_>>();
+        assert_eq!(ticks[..2], [Tick::Beat(0), Tick::Beat(1)]);
+        assert_eq!(ticks[4], Tick::Beat(0));
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Older entries are forgotten past this many.
+const LIMIT: usize = 100;
+/// Edits closer together than this, like the steps of a slider drag, undo as one.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Undo and redo stacks of settings snapshots.
+pub struct History<T> {
+    undo: VecDeque<T>,
+    redo: Vec<T>,
+    last_edit: Option<Instant>,
+}
+
+impl<T: PartialEq> History<T> {
+    pub fn new() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            last_edit: None,
+        }
+    }
+
+    /// Notes an edit from `before` to `after` made at `now`; does nothing if they are equal.
+    pub fn record(&mut self, before: T, after: &T, now: Instant) {
+        if before == *after {
+            return;
+        }
+        let continues_edit = self
+            .last_edit
+            .is_some_and(|last| now.saturating_duration_since(last) < DEBOUNCE);
+        if !continues_edit {
+            if self.undo.len() == LIMIT {
+                self.undo.pop_front();
+            }
+            self.undo.push_back(before);
+        }
+        self.redo.clear();
+        self.last_edit = Some(now);
+    }
+
+    /// Swaps `current` for the snapshot before the last edit, if there is one.
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(current);
+        self.last_edit = None;
+        Some(previous)
+    }
+
+    /// Swaps `current` for the snapshot the last undo left, if there is one.
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(current);
+        self.last_edit = None;
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_merges_quick_edits() {
+        let start = Instant::now();
+        let later = |ms| start + Duration::from_millis(ms);
+        let mut history = History::new();
+        // A slider drag, then a separate edit a second later
+        history.record(100, &101, later(0));
+        history.record(101, &102, later(100));
+        history.record(102, &103, later(200));
+        history.record(103, &90, later(1200));
+        assert_eq!(history.undo(90), Some(103));
+        assert_eq!(history.undo(103), Some(100));
+        assert_eq!(history.undo(100), None);
+        assert_eq!(history.redo(100), Some(103));
+        // A new edit drops what was left to redo
+        history.record(103, &120, later(5000));
+        assert!(!history.can_redo());
+        assert_eq!(history.undo(120), Some(103));
+    }
+}
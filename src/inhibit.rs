@@ -0,0 +1,97 @@
+use std::process::Child;
+
+/// Keeps the display and system awake for as long as it is alive.
+///
+/// On macOS and Linux this runs the platform's own inhibitor (`caffeinate`, `systemd-inhibit`)
+/// as a child process tied to our PID, so it also goes away if the app exits without dropping it.
+pub struct SleepInhibitor {
+    child: Option<Child>,
+}
+
+impl SleepInhibitor {
+    /// Returns `None` if the platform inhibitor isn't available.
+    pub fn acquire() -> Option<Self> {
+        #[cfg(target_os = "windows")]
+        {
+            windows::set_awake(true);
+            Some(Self { child: None })
+        }
+        #[cfg(not(target_os = "windows"))]
+        {
+            spawn_inhibitor().map(|child| Self { child: Some(child) })
+        }
+    }
+}
+
+impl Drop for SleepInhibitor {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        #[cfg(target_os = "windows")]
+        windows::set_awake(false);
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn spawn_inhibitor() -> Option<Child> {
+    use std::process::{Command, Stdio};
+
+    Command::new("caffeinate")
+        .args(["-d", "-i", "-w", &std::process::id().to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn spawn_inhibitor() -> Option<Child> {
+    use std::process::{Command, Stdio};
+
+    Command::new("systemd-inhibit")
+        .args([
+            "--what=idle:sleep",
+            "--who=Metronome",
+            "--why=Metronome is running",
+            "tail",
+            &format!("--pid={}", std::process::id()),
+            "-f",
+            "/dev/null",
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+fn spawn_inhibitor() -> Option<Child> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+mod windows {
+    const ES_CONTINUOUS: u32 = 0x8000_0000;
+    const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+    const ES_DISPLAY_REQUIRED: u32 = 0x0000_0002;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetThreadExecutionState(flags: u32) -> u32;
+    }
+
+    pub fn set_awake(awake: bool) {
+        let flags = if awake {
+            ES_CONTINUOUS | ES_SYSTEM_REQUIRED | ES_DISPLAY_REQUIRED
+        } else {
+            ES_CONTINUOUS
+        };
+        unsafe {
+            SetThreadExecutionState(flags);
+        }
+    }
+}
@@ -0,0 +1,361 @@
+use std::fmt::{self, Display};
+
+use iced::keyboard::{KeyCode, Modifiers};
+
+/// Everything the keyboard can do. `?` always shows the bindings and isn't listed here.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Toggle,
+    Pause,
+    Silence,
+    Tap,
+    Faster,
+    Slower,
+    MuchFaster,
+    MuchSlower,
+    RecallA,
+    RecallB,
+    SwapTempoSlots,
+    Undo,
+    Redo,
+    NextSong,
+    PrevSong,
+    Mute,
+    HalfTime,
+    DoubleTime,
+}
+
+impl Action {
+    pub const ALL: [Action; 18] = [
+        Action::Toggle,
+        Action::Pause,
+        Action::Silence,
+        Action::Tap,
+        Action::Faster,
+        Action::Slower,
+        Action::MuchFaster,
+        Action::MuchSlower,
+        Action::RecallA,
+        Action::RecallB,
+        Action::SwapTempoSlots,
+        Action::Undo,
+        Action::Redo,
+        Action::NextSong,
+        Action::PrevSong,
+        Action::Mute,
+        Action::HalfTime,
+        Action::DoubleTime,
+    ];
+}
+
+/// Every key a binding can use, to read bindings back from their names.
+const KEY_CODES: [KeyCode; 161] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Escape,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::F13,
+    KeyCode::F14,
+    KeyCode::F15,
+    KeyCode::F16,
+    KeyCode::F17,
+    KeyCode::F18,
+    KeyCode::F19,
+    KeyCode::F20,
+    KeyCode::F21,
+    KeyCode::F22,
+    KeyCode::F23,
+    KeyCode::F24,
+    KeyCode::Snapshot,
+    KeyCode::Scroll,
+    KeyCode::Pause,
+    KeyCode::Insert,
+    KeyCode::Home,
+    KeyCode::Delete,
+    KeyCode::End,
+    KeyCode::PageDown,
+    KeyCode::PageUp,
+    KeyCode::Left,
+    KeyCode::Up,
+    KeyCode::Right,
+    KeyCode::Down,
+    KeyCode::Backspace,
+    KeyCode::Enter,
+    KeyCode::Space,
+    KeyCode::Compose,
+    KeyCode::Caret,
+    KeyCode::Numlock,
+    KeyCode::Numpad0,
+    KeyCode::Numpad1,
+    KeyCode::Numpad2,
+    KeyCode::Numpad3,
+    KeyCode::Numpad4,
+    KeyCode::Numpad5,
+    KeyCode::Numpad6,
+    KeyCode::Numpad7,
+    KeyCode::Numpad8,
+    KeyCode::Numpad9,
+    KeyCode::NumpadAdd,
+    KeyCode::NumpadDivide,
+    KeyCode::NumpadDecimal,
+    KeyCode::NumpadComma,
+    KeyCode::NumpadEnter,
+    KeyCode::NumpadEquals,
+    KeyCode::NumpadMultiply,
+    KeyCode::NumpadSubtract,
+    KeyCode::AbntC1,
+    KeyCode::AbntC2,
+    KeyCode::Apostrophe,
+    KeyCode::Apps,
+    KeyCode::Asterisk,
+    KeyCode::At,
+    KeyCode::Ax,
+    KeyCode::Backslash,
+    KeyCode::Calculator,
+    KeyCode::Capital,
+    KeyCode::Colon,
+    KeyCode::Comma,
+    KeyCode::Convert,
+    KeyCode::Equals,
+    KeyCode::Grave,
+    KeyCode::Kana,
+    KeyCode::Kanji,
+    KeyCode::LAlt,
+    KeyCode::LBracket,
+    KeyCode::LControl,
+    KeyCode::LShift,
+    KeyCode::LWin,
+    KeyCode::Mail,
+    KeyCode::MediaSelect,
+    KeyCode::MediaStop,
+    KeyCode::Minus,
+    KeyCode::Mute,
+    KeyCode::MyComputer,
+    KeyCode::NextTrack,
+    KeyCode::NoConvert,
+    KeyCode::OEM102,
+    KeyCode::Period,
+    KeyCode::PlayPause,
+    KeyCode::Plus,
+    KeyCode::Power,
+    KeyCode::PrevTrack,
+    KeyCode::RAlt,
+    KeyCode::RBracket,
+    KeyCode::RControl,
+    KeyCode::RShift,
+    KeyCode::RWin,
+    KeyCode::Semicolon,
+    KeyCode::Slash,
+    KeyCode::Sleep,
+    KeyCode::Stop,
+    KeyCode::Sysrq,
+    KeyCode::Tab,
+    KeyCode::Underline,
+    KeyCode::Unlabeled,
+    KeyCode::VolumeDown,
+    KeyCode::VolumeUp,
+    KeyCode::Wake,
+    KeyCode::WebBack,
+    KeyCode::WebFavorites,
+    KeyCode::WebForward,
+    KeyCode::WebHome,
+    KeyCode::WebRefresh,
+    KeyCode::WebSearch,
+    KeyCode::WebStop,
+    KeyCode::Yen,
+    KeyCode::Copy,
+    KeyCode::Paste,
+    KeyCode::Cut,
+];
+
+/// Modifier names as bindings are written, with the macOS name for the logo key as well.
+const MODIFIERS: [(Modifiers, &str); 5] = [
+    (Modifiers::CTRL, "Ctrl"),
+    (Modifiers::ALT, "Alt"),
+    (Modifiers::LOGO, "Cmd"),
+    (Modifiers::LOGO, "Super"),
+    (Modifiers::SHIFT, "Shift"),
+];
+
+/// A key together with the exact modifiers that must be held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+    pub key_code: KeyCode,
+    pub modifiers: Modifiers,
+}
+
+impl Binding {
+    pub fn new(key_code: KeyCode, modifiers: Modifiers) -> Self {
+        Self {
+            key_code,
+            modifiers,
+        }
+    }
+
+    /// Reads a binding back from how it's displayed, like `Ctrl+Shift+Z`.
+    pub fn parse(text: &str) -> Option<Binding> {
+        let (modifiers, key) = match text.rsplit_once('+') {
+            Some((modifiers, key)) => (Some(modifiers), key),
+            None => (None, text),
+        };
+        let key_code = KEY_CODES
+            .into_iter()
+            .find(|code| format!("{:?}", code) == key)?;
+        let mut binding = Binding::new(key_code, Modifiers::empty());
+        for name in modifiers
+            .into_iter()
+            .flat_map(|modifiers| modifiers.split('+'))
+        {
+            let &(modifier, _) = MODIFIERS.iter().find(|&&(_, entry)| entry == name)?;
+            binding.modifiers |= modifier;
+        }
+        Some(binding)
+    }
+}
+
+impl Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (Modifiers::CTRL, "Ctrl"),
+            (Modifiers::ALT, "Alt"),
+            (
+                Modifiers::LOGO,
+                if cfg!(target_os = "macos") {
+                    "Cmd"
+                } else {
+                    "Super"
+                },
+            ),
+            (Modifiers::SHIFT, "Shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{:?}", self.key_code)
+    }
+}
+
+/// Which key does what. An action may have several keys, but a key only one action.
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(Binding, Action)>,
+}
+
+impl KeyMap {
+    pub fn action(&self, binding: Binding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == binding)
+            .map(|&(_, action)| action)
+    }
+
+    pub fn bindings(&self, action: Action) -> impl Iterator<Item = Binding> + '_ {
+        self.bindings
+            .iter()
+            .filter(move |(_, bound)| *bound == action)
+            .map(|&(binding, _)| binding)
+    }
+
+    /// Makes `binding` the only key for `action`, taking it away from whatever had it.
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        self.bind(action, &[binding]);
+    }
+
+    /// Makes `bindings` the keys for `action`, taking them away from whatever had them.
+    pub fn bind(&mut self, action: Action, bindings: &[Binding]) {
+        self.bindings
+            .retain(|&(bound, bound_action)| !bindings.contains(&bound) && bound_action != action);
+        self.bindings
+            .extend(bindings.iter().map(|&binding| (binding, action)));
+    }
+}
+
+/// Two maps are the same if every key does the same thing, whatever order they were bound in.
+impl PartialEq for KeyMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.bindings.len() == other.bindings.len()
+            && self
+                .bindings
+                .iter()
+                .all(|binding| other.bindings.contains(binding))
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let none = Modifiers::empty();
+        let command = Modifiers::COMMAND;
+        Self {
+            bindings: vec![
+                (Binding::new(KeyCode::Space, none), Action::Toggle),
+                (Binding::new(KeyCode::P, none), Action::Pause),
+                (Binding::new(KeyCode::Escape, none), Action::Silence),
+                (Binding::new(KeyCode::Enter, none), Action::Tap),
+                (Binding::new(KeyCode::Up, none), Action::Faster),
+                (Binding::new(KeyCode::Down, none), Action::Slower),
+                (Binding::new(KeyCode::PageUp, none), Action::MuchFaster),
+                (Binding::new(KeyCode::PageDown, none), Action::MuchSlower),
+                (Binding::new(KeyCode::A, none), Action::RecallA),
+                (Binding::new(KeyCode::B, none), Action::RecallB),
+                (Binding::new(KeyCode::T, none), Action::SwapTempoSlots),
+                (Binding::new(KeyCode::Right, none), Action::NextSong),
+                (Binding::new(KeyCode::Left, none), Action::PrevSong),
+                (Binding::new(KeyCode::M, none), Action::Mute),
+                (Binding::new(KeyCode::H, none), Action::HalfTime),
+                (Binding::new(KeyCode::D, none), Action::DoubleTime),
+                (Binding::new(KeyCode::Z, command), Action::Undo),
+                (Binding::new(KeyCode::Y, command), Action::Redo),
+                (
+                    Binding::new(KeyCode::Z, command | Modifiers::SHIFT),
+                    Action::Redo,
+                ),
+            ],
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! The metronome's timekeeping, apart from its window: where a bar is up to and what each
+//! beat plays, when ticks are due, and a click track to play them on. [`Sequencer`] works
+//! out what each tick plays, trainers and all, and [`MetronomeEngine`] keeps time for one,
+//! sending its beats over a channel.
+
+mod beat;
+pub mod click_track;
+mod engine;
+mod schedule;
+
+pub use beat::{
+    default_beat_sounds, Beat, BeatSound, Cue, MetroState, PickupOffBeat, BEAT_UNITS, MAX_BAR,
+};
+pub use engine::{
+    groups_fill, starts_group, BeatEvent, GlideCurve, MetronomeEngine, PolyVoice, Sequencer,
+    SequencerGuard, TempoChange, Tick,
+};
+pub use schedule::{round_bpm, tick_interval, Schedule};
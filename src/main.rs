@@ -1,50 +1,89 @@
-use std::io::Cursor;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{self, Receiver, Sender};
-use std::time::Duration;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::{self, Display};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::mpsc::{self, Receiver, SendError, Sender};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
+use calibrate::CalibrationError;
+use config::ConfigFileError;
 #[cfg(target_os = "macos")]
 use fruitbasket::Trampoline;
-use iced::widget::{button, checkbox, column, container, row, slider, text, vertical_space};
-use iced::{color, executor, time, Application, Command, Element, Length, Settings, Theme};
-use lazy_static::lazy_static;
+use history::History;
+use iced::futures::channel::mpsc as async_mpsc;
+use iced::futures::executor::block_on;
+use iced::futures::{SinkExt, StreamExt};
+use iced::keyboard::{self, KeyCode, Modifiers};
+use iced::widget::{
+    button, checkbox, column, container, horizontal_space, pick_list, radio, row, scrollable,
+    slider, text, text_input, vertical_space, Container,
+};
+use iced::window::UserAttention;
+use iced::{
+    event, executor, subscription, window, Application, Color, Command, Element, Event, Length,
+    Settings, Size, Theme,
+};
+use inhibit::SleepInhibitor;
+use keys::{Action, Binding, KeyMap};
+use metronome::click_track::{self, ClickTrack};
+use metronome::{
+    default_beat_sounds, groups_fill, round_bpm, starts_group, Beat, BeatEvent, BeatSound, Cue,
+    GlideCurve, MetroState, MetronomeEngine, PickupOffBeat, PolyVoice, Sequencer, TempoChange,
+    Tick, BEAT_UNITS, MAX_BAR,
+};
 use rodio::{
-    source::{Buffered, SamplesConverter},
-    Decoder, OutputStream, Source,
+    cpal::traits::DeviceTrait, source::SineWave, OutputStream, OutputStreamHandle, PlayError,
+    Source,
 };
+#[cfg(not(test))]
 use rsbmalloc::RSBMalloc;
-use widgets::circle;
+use setlist::Setlist;
+use sounds::{Click, FileClick, Role, Sample, SoundBank, SoundMap, SoundPack};
+use status::StatusServer;
+use strings::{fill, Label, Language};
+use widgets::{beat_bar, circle, conductor, histogram, pendulum, repeat, wrap};
 
+mod ambiance;
+mod calibrate;
+mod cli;
+mod config;
+mod history;
+mod inhibit;
+mod keys;
+mod output;
+mod setlist;
+mod sounds;
+mod status;
+mod strings;
+mod volume;
 mod widgets;
 
-const E_CLICK: &'static [u8] = include_bytes!("../assets/e-click.wav");
-const E_FLAT_CLICK: &'static [u8] = include_bytes!("../assets/e-flat-click.wav");
-const F_CLICK: &'static [u8] = include_bytes!("../assets/f-click.wav");
-
-static OFF_BEAT: AtomicBool = AtomicBool::new(true);
+/// Bumped on every panic-stop; clicks started under an older generation cut themselves off.
+static SILENCE_GENERATION: AtomicU32 = AtomicU32::new(0);
 
+#[cfg(not(test))]
 #[global_allocator]
 static GLOBAL_ALLOCATOR: RSBMalloc = RSBMalloc::new();
 
-lazy_static! {
-    static ref E_CLICK_SOURCE: Buffered<SamplesConverter<Decoder<Cursor<&'static [u8]>>, f32>> =
-        Decoder::new(Cursor::new(E_CLICK))
-            .unwrap()
-            .convert_samples()
-            .buffered();
-    static ref E_FLAT_CLICK_SOURCE: Buffered<SamplesConverter<Decoder<Cursor<&'static [u8]>>, f32>> =
-        Decoder::new(Cursor::new(E_FLAT_CLICK))
-            .unwrap()
-            .convert_samples()
-            .buffered();
-    static ref F_CLICK_SOURCE: Buffered<SamplesConverter<Decoder<Cursor<&'static [u8]>>, f32>> =
-        Decoder::new(Cursor::new(F_CLICK))
-            .unwrap()
-            .convert_samples()
-            .buffered();
-}
-
 fn main() {
+    let args = match cli::Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    let mut settings = config::load();
+    args.apply(&mut settings);
+    if args.no_gui {
+        run_headless(settings);
+        return;
+    }
+
     #[cfg(target_os = "macos")]
     Trampoline::new("Metronome", "Metronome", "com.brochweb.metronome")
         .icon("Metronome")
@@ -57,57 +96,838 @@ fn main() {
         })
         .unwrap();
 
-    Metronome::run(Settings::default()).unwrap();
+    Metronome::run(Settings::with_flags(settings)).unwrap();
+}
+
+/// Plays without a window until the process is interrupted. The metronome is driven through
+/// the same `update` as the window, so it sounds just the same.
+fn run_headless(settings: MetronomeSettings) {
+    let (mut metronome, _) = Metronome::new(settings);
+    if let Some(err) = &metronome.audio_error {
+        eprintln!("{}", err);
+    }
+    // Without a runtime for the commands `new` returned, custom clicks are loaded here
+    for role in Role::ALL {
+        if let Some(path) = metronome.sample_files[role.index()].clone() {
+            let click = block_on(sounds::load_file(path.clone()));
+            if let Err(err) = &click {
+                eprintln!("{}", err);
+            }
+            let _ = metronome.update(Message::SampleFileLoaded(role, path, click));
+        }
+    }
+    let (sender, mut messages) = async_mpsc::channel(16);
+    metronome.send_player(PlayerCommand::Messages(sender.clone()));
+    let _ = metronome.beat_relay.send(sender);
+    let _ = metronome.update(Message::Toggle);
+    while let Some(message) = block_on(messages.next()) {
+        if let Message::OutputDeviceFailed(err) = &message {
+            eprintln!("{}", err);
+        }
+        // Audio errors are worded for the banner, so they're printed as it would show them
+        let lost = metronome.output_lost.clone();
+        let _ = metronome.update(message);
+        if let Some(err) = metronome
+            .output_lost
+            .as_ref()
+            .filter(|&err| Some(err) != lost.as_ref())
+        {
+            eprintln!("{}", err);
+        }
+    }
 }
 
 struct Metronome {
-    bar: u32,
-    bpm: u32,
-    state: MetroState,
-    accentuate_first_beat: bool,
-    off_beats: bool,
-    player_thread: Sender<Beat>,
-    vol_tx: Sender<f32>,
+    /// Keeps time and works out what each tick plays, which the window plays and shows.
+    engine: MetronomeEngine,
+    /// The engine's sequencer as of the end of the latest update, which the window is drawn
+    /// from. Changes go to the engine's own.
+    sequencer: Sequencer,
+    /// Where to tell the thread passing the engine's main beats on as messages, for showing
+    /// them, where to send them.
+    beat_relay: Sender<async_mpsc::Sender<Message>>,
+    /// What's typed in the tempo field, which may not be a valid tempo yet.
+    bpm_input: String,
+    /// How far the − and + buttons move the tempo; the outer pair moves it ten times as far.
+    step_size: f64,
+    /// How many decimals tempos are shown with, from 0 to 2. Only the display is rounded.
+    tempo_decimals: u32,
+    /// Recent taps of the Tap button, oldest first.
+    taps: Vec<Instant>,
+    /// How unevenly those taps came, shown under the button.
+    tap_spread: Option<Duration>,
+    /// How far each tap since the steadiness chart was reset strayed from the beat the taps
+    /// before it set, in seconds.
+    tap_deviations: Vec<f64>,
+    /// Two stored tempos for quick comparison, and which was recalled last.
+    tempo_slots: [Option<f64>; 2],
+    active_slot: Option<usize>,
+    /// Tempos recently settled on, newest first, each only once.
+    recent_tempos: VecDeque<f64>,
+    /// When the tempo was last picked, until it has stayed put long enough to count as
+    /// recent.
+    tempo_changed: Option<Instant>,
+    /// The tempo before the ½× and 2× buttons changed it, and how far they've multiplied it
+    /// since, so going back lands on it exactly. Any other tempo change forgets it.
+    tempo_multiplied: Option<(f64, f64)>,
+    /// Named snapshots of the settings, of which loading one brings back its tempo, bar,
+    /// accent and subdivision.
+    presets: Vec<(String, MetronomeSettings)>,
+    preset_name: String,
+    presets_tx: Sender<Vec<(String, MetronomeSettings)>>,
+    /// Songs for a gig, each with its own tempo and meter, stepped through in order.
+    setlist: Setlist<MetronomeSettings>,
+    song_name: String,
+    setlist_tx: Sender<Vec<(String, MetronomeSettings)>>,
+    /// What's typed in the field for the file to export the settings to or import them
+    /// from, and how the last export or import went.
+    config_file: String,
+    config_file_result: Option<Result<Label, ConfigFileError>>,
+    /// The slowest and fastest tempos played this session, until reset.
+    tempo_range: Option<(f64, f64)>,
+    /// The slowest and fastest tempos ever played, kept between runs.
+    tempo_record: Option<(f64, f64)>,
+    record_tx: Sender<(f64, f64)>,
+    /// Time spent playing this session, not counting the stretch since `playing_since`.
+    practice_time: Duration,
+    playing_since: Option<Instant>,
+    /// Bars and beats played this session across every take, until reset, not counting
+    /// count-ins.
+    bars_elapsed: u64,
+    beats_elapsed: u64,
+    tempo_average: TempoAverage,
+    /// What's typed in the grouping field, which may not be a valid grouping yet.
+    groups_input: String,
+    meter_sequence_input: String,
+    /// Nothing is played at all, while counting and visuals carry on as normal.
+    visual_only: bool,
+    show_beat_bar: bool,
+    /// Show the beat as a big "3 / 4" while playing, counting down during a count-in.
+    beat_counter: bool,
+    /// Show only the tempo, the beats and the transport, in a smaller window.
+    compact: bool,
+    visual_style: VisualStyle,
+    /// Pulse the lit beat indicator outwards on each beat.
+    pulse: bool,
+    /// Draw the first beat's circle bigger than the rest of the bar.
+    big_downbeat: bool,
+    /// Flash the whole window on each beat, brightest on the downbeat, to be seen from
+    /// across a stage.
+    stage_flash: bool,
+    /// Tint the window background through the color wheel once per bar.
+    background_gradient: bool,
+    gradient_intensity: f32,
+    /// When the latest beat is shown, which is `latency_offset_ms` after its click was sent
+    /// so that the flash lands when the click is heard.
+    last_beat: Option<Instant>,
+    /// The beat shown until `last_beat`.
+    previous_beat: Option<u32>,
+    /// The main beat played last, which becomes `previous_beat` with the next one.
+    played_beat: Option<u32>,
+    /// Main beats played since starting, which the pendulum changes direction on.
+    beats_played: u64,
+    /// Time of the latest frame, for animating progress through the beat.
+    now: Instant,
+    player_thread: Sender<PlayerCommand>,
+    /// How many times the player thread was started again after dying, which also restarts
+    /// the message subscription so the new thread has somewhere to send its messages.
+    player_restarts: u32,
+    /// The file clicks and message channel the player was given, for handing to a new one.
+    file_clicks: [Option<Click>; 6],
+    messages: Option<async_mpsc::Sender<Message>>,
     volume: f32,
+    /// Silences the output without touching `volume`, which unmuting goes back to.
+    muted: bool,
+    start_stop_cues: bool,
+    truncate_clicks: bool,
+    max_click_ms: u32,
+    /// Where changes to the click length and sound map go. They're only read before a
+    /// beat, so sending to a dead player is fine; a new one starts with the latest.
+    max_click_tx: Sender<Option<Duration>>,
+    sound_map: SoundMap,
+    sound_map_tx: Sender<SoundMap>,
+    /// A file per role that plays instead of its embedded sample.
+    sample_files: [Option<String>; 6],
+    /// What's typed in each role's file field.
+    sample_inputs: [String; 6],
+    /// Why each role's file couldn't be loaded.
+    sample_errors: [Option<String>; 6],
+    /// The output device to play on, by name; `None` follows the system default.
+    output_device: Option<String>,
+    /// The output devices connected when last listed.
+    output_devices: Vec<String>,
+    /// The chosen device went away, so the player fell back to the default until it's back.
+    output_fallback: bool,
+    /// Why the player couldn't open the chosen device.
+    output_error: Option<String>,
+    /// Why nothing can be heard, if no output could be opened or the open one stopped
+    /// working. The player is asked to reconnect whenever devices are listed.
+    output_lost: Option<String>,
+    show_sounds: bool,
+    /// How long a click takes from being sent to being heard, which the display waits for.
+    latency_offset_ms: u32,
+    /// Mix every click into one continuous stream at its beat's exact sample, for steadier
+    /// timing at the cost of [`click_track::LOOKAHEAD`] more latency.
+    sample_accurate: bool,
+    calibrating: bool,
+    calibration: Option<Result<Duration, CalibrationError>>,
+    /// Light or dark; the beat circles and background follow its palette.
+    theme: Theme,
+    /// Overrides for the beat circle colors; `None` follows the theme.
+    active_color: Option<Color>,
+    idle_color: Option<Color>,
+    /// Ask for the user's attention through the dock or taskbar on every downbeat while the
+    /// window isn't focused.
+    attention_on_downbeat: bool,
+    /// Show the beat counter in the window title while playing, for a cue when the window
+    /// is out of sight but its taskbar entry isn't.
+    beat_in_title: bool,
+    focused: bool,
+    inhibit_sleep: bool,
+    sleep_inhibitor: Option<SleepInhibitor>,
+    share_status: bool,
+    status_server: Option<StatusServer>,
+    status_error: Option<String>,
+    sounds: Option<Arc<SoundBank>>,
+    /// Why there is no sound, if the embedded clicks couldn't be loaded.
+    audio_error: Option<String>,
+    language: Language,
+    show_about: bool,
+    key_map: KeyMap,
+    show_keys: bool,
+    /// The action waiting for a key press to become its new binding.
+    rebinding: Option<Action>,
+    history: History<MetronomeSettings>,
+    /// The settings as last handed to the config writer.
+    saved_settings: MetronomeSettings,
+    config_tx: Sender<MetronomeSettings>,
+    audio_info: Arc<Mutex<Option<AudioInfo>>>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
 struct MetronomeSettings {
     bar: u32,
-    bpm: u32,
-    accentuate_first_beat: bool,
-    off_beats: bool,
+    beat_unit: u32,
+    bpm: f64,
+    step_size: f64,
+    tempo_decimals: u32,
+    tempo_change: TempoChange,
+    restart_bar_on_tempo: bool,
+    glide_target: u32,
+    glide_bars: u32,
+    glide_curve: GlideCurve,
+    warm_up: bool,
+    warm_up_from: u32,
+    warm_up_secs: u32,
+    gap_trainer: bool,
+    gap_audible_bars: u32,
+    gap_silent_bars: u32,
+    ramp_trainer: bool,
+    ramp_start: u32,
+    ramp_end: u32,
+    ramp_step: u32,
+    ramp_every_bars: u32,
+    ramp_loop: bool,
+    drill: bool,
+    drill_min: u32,
+    drill_max: u32,
+    drill_every_bars: u32,
+    start_beat: u32,
+    count_in_bars: u32,
+    beat_sounds: Vec<BeatSound>,
+    meter_beat_sounds: BTreeMap<u32, Vec<BeatSound>>,
+    groups: Vec<u32>,
+    meter_sequence: Vec<u32>,
+    subdivision: u32,
+    swing: f32,
+    polyrhythm: bool,
+    poly_voice: PolyVoice,
+    off_beat_mask: Vec<bool>,
+    pickup_off_beat: PickupOffBeat,
+    downbeat_only: bool,
+    visual_only: bool,
+    show_beat_bar: bool,
+    beat_counter: bool,
+    compact: bool,
+    visual_style: VisualStyle,
+    pulse: bool,
+    big_downbeat: bool,
+    stage_flash: bool,
+    background_gradient: bool,
+    gradient_intensity: f32,
     volume: f32,
+    start_stop_cues: bool,
+    truncate_clicks: bool,
+    max_click_ms: u32,
+    sound_map: SoundMap,
+    sample_files: [Option<String>; 6],
+    output_device: Option<String>,
+    latency_offset_ms: u32,
+    sample_accurate: bool,
+    theme: Theme,
+    active_color: Option<Color>,
+    idle_color: Option<Color>,
+    attention_on_downbeat: bool,
+    beat_in_title: bool,
+    inhibit_sleep: bool,
+    share_status: bool,
+    language: Language,
+    key_map: KeyMap,
+    recent_tempos: VecDeque<f64>,
 }
 
 impl Default for MetronomeSettings {
     fn default() -> Self {
         Self {
             bar: 4,
-            bpm: 100,
-            accentuate_first_beat: true,
-            off_beats: false,
+            beat_unit: 4,
+            bpm: 100.,
+            step_size: 1.,
+            tempo_decimals: 1,
+            tempo_change: TempoChange::Immediate,
+            restart_bar_on_tempo: false,
+            glide_target: 140,
+            glide_bars: 8,
+            glide_curve: GlideCurve::Straight,
+            warm_up: false,
+            warm_up_from: 60,
+            warm_up_secs: 30,
+            gap_trainer: false,
+            gap_audible_bars: 4,
+            gap_silent_bars: 2,
+            ramp_trainer: false,
+            ramp_start: 80,
+            ramp_end: 120,
+            ramp_step: 5,
+            ramp_every_bars: 4,
+            ramp_loop: false,
+            drill: false,
+            drill_min: 80,
+            drill_max: 160,
+            drill_every_bars: 4,
+            start_beat: 1,
+            count_in_bars: 0,
+            beat_sounds: default_beat_sounds(4),
+            meter_beat_sounds: BTreeMap::new(),
+            groups: Vec::new(),
+            meter_sequence: Vec::new(),
+            subdivision: 1,
+            swing: 0.5,
+            polyrhythm: false,
+            poly_voice: PolyVoice { divisions: 3 },
+            off_beat_mask: vec![true; 4],
+            pickup_off_beat: PickupOffBeat::Play,
+            downbeat_only: false,
+            visual_only: false,
+            show_beat_bar: false,
+            beat_counter: false,
+            compact: false,
+            visual_style: VisualStyle::AllBeats,
+            pulse: true,
+            big_downbeat: false,
+            stage_flash: false,
+            background_gradient: false,
+            gradient_intensity: 0.15,
             volume: 1.0,
+            start_stop_cues: true,
+            truncate_clicks: false,
+            max_click_ms: 50,
+            sound_map: SoundMap::default(),
+            sample_files: Default::default(),
+            output_device: None,
+            latency_offset_ms: 0,
+            sample_accurate: false,
+            active_color: None,
+            idle_color: None,
+            theme: Theme::Light,
+            attention_on_downbeat: false,
+            beat_in_title: false,
+            inhibit_sleep: true,
+            share_status: false,
+            language: Language::default(),
+            key_map: KeyMap::default(),
+            recent_tempos: VecDeque::new(),
+        }
+    }
+}
+
+/// Sets `sequencer` up to play as `settings` say. Playback carries on, and the tempo only
+/// jumps if it's different or something was moving it.
+fn set_sequencing(sequencer: &mut Sequencer, settings: &MetronomeSettings) {
+    sequencer.jump_to_bpm(settings.bpm);
+    sequencer.set_bar(settings.bar);
+    sequencer.beat_unit = settings.beat_unit;
+    sequencer.set_tempo_change(settings.tempo_change);
+    sequencer.restart_bar_on_tempo = settings.restart_bar_on_tempo;
+    sequencer.glide_target = settings.glide_target;
+    sequencer.glide_bars = settings.glide_bars;
+    sequencer.glide_curve = settings.glide_curve;
+    sequencer.warm_up = settings.warm_up;
+    sequencer.warm_up_from = settings.warm_up_from;
+    sequencer.warm_up_secs = settings.warm_up_secs;
+    sequencer.gap_trainer = settings.gap_trainer;
+    sequencer.set_gap_audible_bars(settings.gap_audible_bars);
+    sequencer.set_gap_silent_bars(settings.gap_silent_bars);
+    sequencer.set_ramp_trainer(settings.ramp_trainer);
+    sequencer.ramp_start = settings.ramp_start;
+    sequencer.ramp_end = settings.ramp_end;
+    sequencer.set_ramp_step(settings.ramp_step);
+    sequencer.set_ramp_every_bars(settings.ramp_every_bars);
+    sequencer.ramp_loop = settings.ramp_loop;
+    sequencer.set_drill(settings.drill);
+    sequencer.drill_min = settings.drill_min;
+    sequencer.drill_max = settings.drill_max;
+    sequencer.set_drill_every_bars(settings.drill_every_bars);
+    sequencer.start_beat = settings.start_beat.clamp(1, settings.bar);
+    sequencer.count_in_bars = settings.count_in_bars;
+    sequencer.beat_sounds = settings.beat_sounds.clone();
+    sequencer
+        .beat_sounds
+        .resize(settings.bar as usize, BeatSound::Normal);
+    sequencer.meter_beat_sounds = settings.meter_beat_sounds.clone();
+    sequencer.groups = if groups_fill(&settings.groups, settings.bar) {
+        settings.groups.clone()
+    } else {
+        Vec::new()
+    };
+    sequencer.meter_sequence = settings.meter_sequence.clone();
+    sequencer.subdivision = settings.subdivision;
+    sequencer.swing = settings.swing;
+    sequencer.set_polyrhythm(settings.polyrhythm);
+    sequencer.poly_voice = settings.poly_voice;
+    sequencer.off_beat_mask = settings.off_beat_mask.clone();
+    sequencer.pickup_off_beat = settings.pickup_off_beat;
+    sequencer.downbeat_only = settings.downbeat_only;
+}
+
+/// What the beat indicator shows. Counting and sound are the same for every style.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VisualStyle {
+    /// One circle per beat, lit on its beat.
+    AllBeats,
+    /// One circle per beat, but only the first ever lights up.
+    DownbeatOnly,
+    /// A single circle that flashes briefly on every beat.
+    Single,
+    /// A conductor's beat pattern, for bars of two to four beats; other bars show every beat.
+    Conductor,
+    /// A metronome arm swinging across on every beat.
+    Pendulum,
+}
+
+impl VisualStyle {
+    const ALL: [VisualStyle; 5] = [
+        VisualStyle::AllBeats,
+        VisualStyle::DownbeatOnly,
+        VisualStyle::Single,
+        VisualStyle::Conductor,
+        VisualStyle::Pendulum,
+    ];
+
+    fn label(self) -> Label {
+        match self {
+            VisualStyle::AllBeats => Label::StyleAllBeats,
+            VisualStyle::DownbeatOnly => Label::StyleDownbeatOnly,
+            VisualStyle::Single => Label::StyleSingle,
+            VisualStyle::Conductor => Label::StyleConductor,
+            VisualStyle::Pendulum => Label::StylePendulum,
         }
     }
 }
 
-#[derive(PartialEq, Debug, Clone)]
-enum MetroState {
-    Stopped,
-    FirstBeat,
-    Beat(u32),
+/// How loud a softened off-beat is next to a normal one.
+const SOFT_OFF_BEAT: f32 = 0.4;
+
+/// A cue is its sound twice, a fifth apart: rising to start and falling to stop.
+const CUE_INTERVAL: f32 = 1.5;
+/// How long after the first sound of a cue the second comes.
+const CUE_GAP: Duration = Duration::from_millis(40);
+
+/// The mean tempo over a session, each tempo counting for as long as it was played.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct TempoAverage {
+    /// Tempos multiplied by the seconds played at them, summed.
+    weighted: f64,
+    seconds: f64,
+}
+
+impl TempoAverage {
+    fn add(&mut self, bpm: f64, played: Duration) {
+        self.weighted += bpm * played.as_secs_f64();
+        self.seconds += played.as_secs_f64();
+    }
+
+    /// The average so far, or `None` before anything was played.
+    fn bpm(&self) -> Option<f64> {
+        (self.seconds > 0.).then(|| self.weighted / self.seconds)
+    }
+}
+
+/// How long the stage flash takes to fade after each beat.
+const FLASH_DECAY: Duration = Duration::from_millis(180);
+
+/// The single indicator stays lit for this fraction of each beat.
+const FLASH_PHASE: f32 = 0.25;
+/// Beat indicators are drawn at this opacity while counting in.
+const COUNT_IN_ALPHA: f32 = 0.4;
+/// The beat circle colors on offer besides the theme's own, starting from the original blue.
+const BEAT_COLORS: [u32; 7] = [
+    0x6080df, 0xe04f4f, 0xf09a2e, 0xf2d03b, 0x4cb05a, 0xa060d0, 0x808080,
+];
+/// Silent beats are drawn at this opacity.
+const SILENT_BEAT_ALPHA: f32 = 0.3;
+
+/// The longest a click may ring for, if truncation is enabled.
+fn max_click(truncate_clicks: bool, max_click_ms: u32) -> Option<Duration> {
+    truncate_clicks.then(|| Duration::from_millis(max_click_ms as u64))
+}
+
+/// A tap more than this long after the previous one starts a new tempo.
+const TAP_TIMEOUT: Duration = Duration::from_secs(2);
+/// Only the latest taps count towards the tempo.
+const TAP_WINDOW: usize = 8;
+/// Tapping that varies less than this is steady enough to trust, and more than the second
+/// too uneven to use.
+const TAP_STEADY: Duration = Duration::from_millis(10);
+const TAP_UNEVEN: Duration = Duration::from_millis(30);
+/// The steadiness chart sorts taps into this many buckets `TAP_STEADY` wide, from early on
+/// the left to late on the right, the one in the middle being on the beat. The outer two
+/// take in anything further out.
+const STEADINESS_BUCKETS: usize = 9;
+/// A tempo left alone this long is added to the recent tempos, so dragging the slider
+/// doesn't add every tempo on the way.
+const TEMPO_SETTLE: Duration = Duration::from_secs(2);
+/// How many recent tempos are kept.
+const RECENT_TEMPOS: usize = 6;
+/// How often the output devices are listed, to follow them being plugged in and out.
+const DEVICE_POLL: Duration = Duration::from_secs(2);
+
+/// The window size for the compact layout, or the full one, which is iced's default.
+fn window_size(compact: bool) -> Size<u32> {
+    if compact {
+        Size::new(640, 420)
+    } else {
+        Size::new(1024, 768)
+    }
+}
+
+/// The tempo implied by the mean interval between `taps`, once there are at least two.
+fn tap_bpm(taps: &[Instant]) -> Option<u32> {
+    let (first, last) = (taps.first()?, taps.last()?);
+    if taps.len() < 2 {
+        return None;
+    }
+    let mean = last.duration_since(*first).as_secs_f64() / (taps.len() - 1) as f64;
+    Some(((60. / mean).round() as u32).clamp(30, 300))
+}
+
+/// The standard deviation of the intervals between `taps`, once there are at least two
+/// intervals to compare.
+fn tap_spread(taps: &[Instant]) -> Option<Duration> {
+    if taps.len() < 3 {
+        return None;
+    }
+    let intervals = taps
+        .windows(2)
+        .map(|pair| pair[1].duration_since(pair[0]).as_secs_f64())
+        .collect::<Vec<_>>();
+    let mean = intervals.iter().sum::<f64>() / intervals.len() as f64;
+    let variance = intervals
+        .iter()
+        .map(|interval| (interval - mean).powi(2))
+        .sum::<f64>()
+        / intervals.len() as f64;
+    Some(Duration::from_secs_f64(variance.sqrt()))
+}
+
+/// How far the latest of `taps` strayed from the mean interval between them all, in
+/// seconds, once there are at least two intervals to compare. Early taps come out negative.
+fn tap_deviation(taps: &[Instant]) -> Option<f64> {
+    let [.., before, last] = taps else {
+        return None;
+    };
+    if taps.len() < 3 {
+        return None;
+    }
+    let mean = last.duration_since(taps[0]).as_secs_f64() / (taps.len() - 1) as f64;
+    Some(last.duration_since(*before).as_secs_f64() - mean)
+}
+
+/// How many of `deviations` fall in each of the steadiness chart's buckets.
+fn steadiness_buckets(deviations: &[f64]) -> Vec<u32> {
+    let mut buckets = vec![0; STEADINESS_BUCKETS];
+    let middle = (STEADINESS_BUCKETS / 2) as f64;
+    for deviation in deviations {
+        let bucket = (deviation / TAP_STEADY.as_secs_f64() + middle)
+            .round()
+            .clamp(0., (STEADINESS_BUCKETS - 1) as f64);
+        buckets[bucket as usize] += 1;
+    }
+    buckets
+}
+
+/// `range` stretched to take in `bpm`.
+fn widen(range: Option<(f64, f64)>, bpm: f64) -> (f64, f64) {
+    match range {
+        Some((min, max)) => (min.min(bpm), max.max(bpm)),
+        None => (bpm, bpm),
+    }
+}
+
+/// Puts `bpm` at the front of `recent`, moving it there if it was already in.
+fn remember_tempo(recent: &mut VecDeque<f64>, bpm: f64) {
+    recent.retain(|&recent| recent != bpm);
+    recent.push_front(bpm);
+    recent.truncate(RECENT_TEMPOS);
+}
+
+/// A button that steps the tempo by `steps` steps, and keeps stepping while held.
+fn step_button<'a>(label: String, steps: i32) -> Element<'a, Message> {
+    repeat(
+        button(
+            text(label)
+                .size(24)
+                .horizontal_alignment(iced::alignment::Horizontal::Center),
+        )
+        .width(60.0)
+        .style(iced::theme::Button::Secondary)
+        .on_press(Message::BPMStep(steps)),
+        Message::BPMStep(steps),
+    )
+    .into()
+}
+
+/// The classical tempo terms, each with the tempo it starts at. A boundary belongs to the
+/// faster term, so 120 BPM is Allegro rather than Moderato. Sources disagree by a few BPM at
+/// most edges; these follow the common metronome markings.
+const TEMPO_MARKINGS: [(f64, &str); 9] = [
+    (0., "Grave"),
+    (40., "Largo"),
+    (60., "Larghetto"),
+    (66., "Adagio"),
+    (76., "Andante"),
+    (108., "Moderato"),
+    (120., "Allegro"),
+    (168., "Presto"),
+    (200., "Prestissimo"),
+];
+
+/// The tempo term `bpm` falls under, from [`TEMPO_MARKINGS`].
+fn tempo_marking(bpm: f64) -> &'static str {
+    TEMPO_MARKINGS
+        .iter()
+        .rev()
+        .find(|&&(from, _)| bpm >= from)
+        .map_or(TEMPO_MARKINGS[0].1, |&(_, marking)| marking)
+}
+
+/// A tempo step, without a decimal when it's a whole number.
+fn step_text(step: f64) -> String {
+    if step.fract() == 0.0 {
+        format!("{}", step)
+    } else {
+        format!("{:.1}", step)
+    }
+}
+
+/// `duration` as minutes and seconds, with hours in front once there are any.
+fn clock_time(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs / 3600 {
+        0 => format!("{}:{:02}", secs / 60, secs % 60),
+        hours => format!("{}:{:02}:{:02}", hours, secs / 60 % 60, secs % 60),
+    }
+}
+
+/// A grouping like `"3+2+2"`, if its parts add up to `bar`. An empty one means no grouping.
+fn parse_groups(input: &str, bar: u32) -> Option<Vec<u32>> {
+    if input.trim().is_empty() {
+        return Some(Vec::new());
+    }
+    let groups = input
+        .split('+')
+        .map(|group| group.trim().parse().ok().filter(|&group| group > 0))
+        .collect::<Option<Vec<u32>>>()?;
+    groups_fill(&groups, bar).then_some(groups)
+}
+
+fn groups_text(groups: &[u32]) -> String {
+    groups
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// A meter sequence like `"4, 3"`, with every bar between 1 and [`MAX_BAR`] beats. An empty
+/// one means no sequence.
+fn parse_meter_sequence(input: &str) -> Option<Vec<u32>> {
+    if input.trim().is_empty() {
+        return Some(Vec::new());
+    }
+    input
+        .split(',')
+        .map(|bar| {
+            bar.trim()
+                .parse()
+                .ok()
+                .filter(|bar| (1..=MAX_BAR).contains(bar))
+        })
+        .collect()
+}
+
+fn meter_sequence_text(sequence: &[u32]) -> String {
+    sequence
+        .iter()
+        .map(u32::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A tempo as shown, to `decimals` places.
+fn tempo_text(bpm: f64, decimals: u32) -> String {
+    format!("{:.*}", decimals as usize, bpm)
+}
+
+/// A tempo as typed, to the tenth it can be set to whatever the labels show.
+fn tempo_input_text(bpm: f64) -> String {
+    tempo_text(bpm, 1)
 }
 
 #[derive(Debug, Clone)]
 enum Message {
     Toggle,
-    Beat,
-    OffBeat,
-    BPMUpdate(u32),
+    Pause,
+    /// The engine played a main beat, which is for the window to show; the player hears
+    /// of it on its own.
+    Beat(BeatEvent),
+    BPMUpdate(f64),
+    /// Move the tempo by this many steps of `step_size`.
+    BPMStep(i32),
+    /// Multiply the tempo, as for half or double time.
+    TempoMultiply(f64),
+    /// Checks at this time whether the tempo has stayed put long enough to remember.
+    TempoSettled(Instant),
+    StepSizeUpdate(f64),
+    TempoDecimals(u32),
+    BPMTextChanged(String),
+    BPMTextSubmitted,
+    Tap,
+    TempoChangeUpdate(TempoChange),
+    RestartBarOnTempo(bool),
+    GlideTargetUpdate(u32),
+    GlideBarsUpdate(u32),
+    GlideCurveUpdate(GlideCurve),
+    ToggleGlide,
+    WarmUp(bool),
+    WarmUpFromUpdate(u32),
+    WarmUpSecsUpdate(u32),
+    Polyrhythm(bool),
+    PolyDivisionsUpdate(u32),
+    GapTrainer(bool),
+    GapAudibleUpdate(u32),
+    GapSilentUpdate(u32),
+    RampToggle(bool),
+    RampStartUpdate(u32),
+    RampEndUpdate(u32),
+    RampStepUpdate(u32),
+    RampEveryUpdate(u32),
+    RampLoop(bool),
+    DrillToggle(bool),
+    DrillMinUpdate(u32),
+    DrillMaxUpdate(u32),
+    DrillEveryUpdate(u32),
+    ResetTempoRange,
+    ResetSession,
+    ResetSteadiness,
+    StoreTempoSlot(usize),
+    RecallTempoSlot(usize),
+    SwapTempoSlots,
     BarUpdate(u32),
-    FirstBeats(bool),
-    OffBeats(bool),
+    GroupsInput(String),
+    MeterSequenceInput(String),
+    BeatUnitUpdate(u32),
+    StartBeatUpdate(u32),
+    CountInUpdate(u32),
+    CycleBeatSound(u32),
+    AccentDownbeat(bool),
+    SubdivisionUpdate(u32),
+    OffBeatAfter(u32, bool),
+    SwingUpdate(f32),
+    PickupOffBeatUpdate(PickupOffBeat),
+    DownbeatOnly(bool),
+    VisualOnly(bool),
+    ShowBeatBar(bool),
+    BeatCounter(bool),
+    Compact(bool),
+    VisualStyleChanged(VisualStyle),
+    /// Pick the lit beat circle color, or `None` to follow the theme.
+    ActiveColorChanged(Option<Color>),
+    IdleColorChanged(Option<Color>),
+    Pulse(bool),
+    BigDownbeat(bool),
+    StageFlash(bool),
+    BackgroundGradient(bool),
+    GradientIntensity(f32),
+    Frame(Instant),
     SetVolume(f32),
+    ToggleMute,
+    StartStopCues(bool),
+    TruncateClicks(bool),
+    MaxClickUpdate(u32),
+    InhibitSleep(bool),
+    AttentionOnDownbeat(bool),
+    BeatInTitle(bool),
+    WindowFocused(bool),
+    ShareStatus(bool),
+    LanguageChanged(Language),
+    DarkTheme(bool),
+    PanicStop,
+    Undo,
+    Redo,
+    KeyPressed(KeyCode, Modifiers),
+    ToggleKeys,
+    Rebind(Action),
+    ToggleAbout,
+    ToggleSounds,
+    SampleChanged(Role, Sample),
+    PreviewSound(Role),
+    RoleVolume(Role, f32),
+    RolePitch(Role, f32),
+    SoundPackChanged(SoundPack),
+    ListOutputDevices,
+    OutputDevicesListed(Vec<String>),
+    OutputDeviceSelected(DeviceChoice),
+    OutputDeviceFailed(String),
+    AudioError(String),
+    OutputLost(String),
+    ClickTrackFailed(String),
+    ClickTrackStopped,
+    AudioConnected,
+    SampleFileInput(Role, String),
+    LoadSampleFile(Role),
+    SampleFileLoaded(Role, String, Result<FileClick, String>),
+    PresetNameInput(String),
+    SavePreset,
+    LoadPreset(usize),
+    DeletePreset(usize),
+    SongNameInput(String),
+    AddSong,
+    SelectSong(usize),
+    NextSong,
+    PrevSong,
+    RemoveSong(usize),
+    MoveSongUp(usize),
+    ConfigFileInput(String),
+    ExportConfig,
+    ImportConfig,
+    ConfigExported(Result<(), ConfigFileError>),
+    ConfigImported(Result<Box<MetronomeSettings>, ConfigFileError>),
+    CalibrateLatency,
+    CalibrationDone(Result<Duration, CalibrationError>),
+    LatencyOffsetUpdate(u32),
+    SampleAccurate(bool),
+    AcceptLatency,
 }
 
 impl Application for Metronome {
@@ -117,185 +937,1534 @@ impl Application for Metronome {
     type Theme = Theme;
 
     fn new(flags: MetronomeSettings) -> (Metronome, Command<Self::Message>) {
-        let (tx, rx) = mpsc::channel();
-        let (vol_tx, vol_rx) = mpsc::channel();
-        std::thread::spawn(move || player_thread(rx, flags.volume, vol_rx));
+        let saved_settings = flags.clone();
+        volume::set(flags.volume);
+        let audio_info = Arc::new(Mutex::new(None));
+        let (sounds, audio_error) = match SoundBank::load() {
+            Ok(sounds) => (Some(Arc::new(sounds)), None),
+            Err(err) => (None, Some(err)),
+        };
+        let (tx, max_click_tx, sound_map_tx) = spawn_player(
+            sounds.clone(),
+            max_click(flags.truncate_clicks, flags.max_click_ms),
+            flags.sound_map,
+            Arc::clone(&audio_info),
+        );
+        let mut sequencer = Sequencer::new(flags.bar);
+        set_sequencing(&mut sequencer, &flags);
+        let (engine, beats) = MetronomeEngine::with_sequencer(sequencer.clone());
+        engine.add_listener(tx.clone());
+        let mut metronome = Metronome {
+            engine,
+            sequencer,
+            beat_relay: spawn_beat_relay(beats),
+            bpm_input: tempo_input_text(flags.bpm),
+            step_size: flags.step_size,
+            tempo_decimals: flags.tempo_decimals,
+            taps: Vec::new(),
+            tap_spread: None,
+            tap_deviations: Vec::new(),
+            tempo_slots: [None; 2],
+            active_slot: None,
+            recent_tempos: flags.recent_tempos.clone(),
+            tempo_changed: None,
+            tempo_multiplied: None,
+            presets: config::load_presets(),
+            preset_name: String::new(),
+            presets_tx: config::spawn_presets_writer(),
+            setlist: Setlist::new(config::load_setlist()),
+            song_name: String::new(),
+            setlist_tx: config::spawn_setlist_writer(),
+            config_file: String::new(),
+            config_file_result: None,
+            tempo_range: None,
+            tempo_record: config::load_record(),
+            record_tx: config::spawn_record_writer(),
+            practice_time: Duration::ZERO,
+            playing_since: None,
+            bars_elapsed: 0,
+            beats_elapsed: 0,
+            tempo_average: TempoAverage::default(),
+            groups_input: groups_text(&flags.groups),
+            meter_sequence_input: meter_sequence_text(&flags.meter_sequence),
+            visual_only: flags.visual_only,
+            show_beat_bar: flags.show_beat_bar,
+            beat_counter: flags.beat_counter,
+            compact: flags.compact,
+            visual_style: flags.visual_style,
+            pulse: flags.pulse,
+            big_downbeat: flags.big_downbeat,
+            stage_flash: flags.stage_flash,
+            background_gradient: flags.background_gradient,
+            gradient_intensity: flags.gradient_intensity,
+            last_beat: None,
+            previous_beat: None,
+            played_beat: None,
+            beats_played: 0,
+            now: Instant::now(),
+            player_thread: tx,
+            player_restarts: 0,
+            file_clicks: Default::default(),
+            messages: None,
+            volume: flags.volume,
+            muted: false,
+            start_stop_cues: flags.start_stop_cues,
+            truncate_clicks: flags.truncate_clicks,
+            max_click_ms: flags.max_click_ms,
+            max_click_tx,
+            sound_map: flags.sound_map,
+            sound_map_tx,
+            sample_files: flags.sample_files,
+            output_device: flags.output_device.clone(),
+            output_devices: Vec::new(),
+            output_fallback: false,
+            output_error: None,
+            output_lost: None,
+            sample_inputs: Default::default(),
+            sample_errors: Default::default(),
+            show_sounds: false,
+            latency_offset_ms: flags.latency_offset_ms,
+            sample_accurate: flags.sample_accurate,
+            calibrating: false,
+            calibration: None,
+            active_color: flags.active_color,
+            idle_color: flags.idle_color,
+            theme: flags.theme,
+            attention_on_downbeat: flags.attention_on_downbeat,
+            beat_in_title: flags.beat_in_title,
+            focused: true,
+            inhibit_sleep: flags.inhibit_sleep,
+            sleep_inhibitor: None,
+            share_status: false,
+            status_server: None,
+            status_error: None,
+            sounds,
+            audio_error,
+            language: flags.language,
+            show_about: false,
+            key_map: flags.key_map,
+            show_keys: false,
+            rebinding: None,
+            history: History::new(),
+            saved_settings,
+            config_tx: config::spawn_writer(),
+            audio_info,
+        };
+        metronome.send_player(PlayerCommand::Device(flags.output_device.clone()));
+        if flags.sample_accurate {
+            metronome.send_player(PlayerCommand::SampleAccurate(true));
+        }
+        if flags.visual_only {
+            metronome.send_player(PlayerCommand::VisualOnly(true));
+        }
+        metronome.set_share_status(flags.share_status);
+        let load_files = metronome.load_sample_files();
+        let layout = if flags.compact {
+            window::resize(window_size(true))
+        } else {
+            Command::none()
+        };
         (
-            Metronome {
-                state: MetroState::Stopped,
-                bar: flags.bar,
-                bpm: flags.bpm,
-                accentuate_first_beat: flags.accentuate_first_beat,
-                off_beats: flags.off_beats,
-                player_thread: tx,
-                volume: flags.volume,
-                vol_tx,
-            },
-            Command::none(),
+            metronome,
+            Command::batch([
+                load_files,
+                layout,
+                Command::perform(output::device_names(), Message::OutputDevicesListed),
+            ]),
         )
     }
 
     fn subscription(&self) -> iced::Subscription<Self::Message> {
-        match self.state {
-            MetroState::Beat(_) | MetroState::FirstBeat => {
-                if self.off_beats {
-                    time::every(Duration::from_secs_f64(60. / self.bpm as f64 / 2.)).map(|_| {
-                        if OFF_BEAT
-                            .compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed)
-                            .is_ok()
-                        {
-                            Message::OffBeat
-                        } else {
-                            OFF_BEAT.store(true, Ordering::Relaxed);
-                            Message::Beat
-                        }
-                    })
-                } else {
-                    time::every(Duration::from_secs_f64(60. / self.bpm as f64))
-                        .map(|_| Message::Beat)
-                }
+        let keys = subscription::events_with(|event, status| match (event, status) {
+            // Typing `?` works the same on every layout, unlike the key it's on
+            (Event::Window(window::Event::Focused), _) => Some(Message::WindowFocused(true)),
+            (Event::Window(window::Event::Unfocused), _) => Some(Message::WindowFocused(false)),
+            (Event::Keyboard(keyboard::Event::CharacterReceived('?')), event::Status::Ignored) => {
+                Some(Message::ToggleKeys)
             }
-            MetroState::Stopped => iced::Subscription::none(),
-        }
+            (
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }),
+                event::Status::Ignored,
+            ) => Some(Message::KeyPressed(key_code, modifiers)),
+            _ => None,
+        });
+        // The engine and the player thread run on their own; this only hands them somewhere
+        // to send their messages
+        let player = self.player_thread.clone();
+        let beat_relay = self.beat_relay.clone();
+        let messages = subscription::channel(
+            ("messages", self.player_restarts),
+            16,
+            move |messages| async move {
+                let _ = player.send(PlayerCommand::Messages(messages.clone()));
+                let _ = beat_relay.send(messages);
+                iced::futures::future::pending().await
+            },
+        );
+        // Holding the flash back takes a frame to show it on time
+        let animated = self.show_beat_bar
+            || self.latency_offset_ms > 0
+            || self.pulse
+            || self.stage_flash
+            || self.background_gradient
+            || matches!(
+                self.visual_style,
+                VisualStyle::Single | VisualStyle::Conductor | VisualStyle::Pendulum
+            );
+        let frames = if animated && self.sequencer.state().is_running() {
+            window::frames().map(Message::Frame)
+        } else {
+            iced::Subscription::none()
+        };
+        // Slow tempos would leave the session time standing still between beats
+        let session = if self.sequencer.state().is_running() {
+            iced::time::every(Duration::from_secs(1)).map(Message::Frame)
+        } else {
+            iced::Subscription::none()
+        };
+        // Listed now and then to notice the chosen device being unplugged or plugged back in
+        let devices = iced::time::every(DEVICE_POLL).map(|_| Message::ListOutputDevices);
+        let settling = if self.tempo_changed.is_some() {
+            iced::time::every(TEMPO_SETTLE / 4).map(Message::TempoSettled)
+        } else {
+            iced::Subscription::none()
+        };
+        iced::Subscription::batch([keys, messages, frames, session, devices, settling])
     }
 
+    // The runtime only touches the window when this changes, so it's cheap to call after
+    // every message
     fn title(&self) -> String {
-        String::from("Metronome")
+        match self.beat_in_title.then(|| self.beat_count()).flatten() {
+            Some(count) => format!("{} ● {}", self.t(Label::Title), count),
+            None => String::from(self.t(Label::Title)),
+        }
+    }
+
+    fn theme(&self) -> Theme {
+        self.theme.clone()
     }
 
     fn update(&mut self, message: Self::Message) -> Command<Self::Message> {
-        match message {
+        // Playback and undo itself change settings without being edits
+        let edit = !matches!(
+            message,
+            Message::KeyPressed(..)
+                | Message::Beat(_)
+                | Message::Frame(_)
+                | Message::TempoSettled(_)
+                | Message::Undo
+                | Message::Redo
+        );
+        let before = edit.then(|| self.settings());
+        // Frames come too often to compare settings on, and never change them
+        let persist = !matches!(message, Message::Frame(_));
+        let command = match message {
             Message::Toggle => {
-                if self.state == MetroState::Stopped {
-                    self.state = MetroState::Beat(self.bar - 1);
-                    Command::perform(async {}, |()| Message::Beat)
-                } else {
-                    self.state = MetroState::Stopped;
+                let stopped = *self.engine.sequencer().state() == MetroState::Stopped;
+                if stopped {
+                    if self.start_stop_cues {
+                        self.play(Beat::Cue(Cue::Start));
+                    }
+                    self.beats_played = 0;
+                    // Playing the first beat straight away means the state started from is
+                    // never drawn
+                    self.engine.start();
+                    self.update_sleep_inhibitor();
                     Command::none()
+                } else {
+                    if self.start_stop_cues {
+                        self.play(Beat::Cue(Cue::Stop));
+                    }
+                    self.engine.stop();
+                    self.update_sleep_inhibitor();
+                    self.dismiss_attention()
+                }
+            }
+            Message::Pause => {
+                let state = self.engine.sequencer().state().clone();
+                match state {
+                    MetroState::Stopped => {}
+                    MetroState::Paused(_) => self.engine.resume(),
+                    _ => self.engine.pause(),
                 }
+                self.update_sleep_inhibitor();
+                Command::none()
             }
             Message::BPMUpdate(bpm) => {
-                self.bpm = bpm;
+                self.active_slot = None;
+                self.tempo_multiplied = None;
+                self.tempo_changed = Some(Instant::now());
+                self.engine.sequencer().set_bpm(bpm);
                 Command::none()
             }
-            Message::BarUpdate(bar) => {
-                self.bar = bar;
+            Message::BPMStep(steps) => {
+                let bpm = self.engine.sequencer().chosen_bpm();
+                let stepped = round_bpm((bpm + steps as f64 * self.step_size).clamp(30., 300.));
+                // Holding a button at either end of the range leaves the tempo alone
+                if stepped == bpm {
+                    Command::none()
+                } else {
+                    self.update(Message::BPMUpdate(stepped))
+                }
+            }
+            Message::TempoMultiply(factor) => {
+                let bpm = self.engine.sequencer().chosen_bpm();
+                let (original, total) = self.tempo_multiplied.unwrap_or((bpm, 1.));
+                let total = total * factor;
+                let bpm = if total == 1. {
+                    original
+                } else {
+                    round_bpm((original * total).clamp(30., 300.))
+                };
+                let command = self.update(Message::BPMUpdate(bpm));
+                self.tempo_multiplied = (total != 1.).then_some((original, total));
+                command
+            }
+            Message::TempoSettled(now) => {
+                if let Some(changed) = self.tempo_changed {
+                    if now.saturating_duration_since(changed) >= TEMPO_SETTLE {
+                        self.tempo_changed = None;
+                        let bpm = self.engine.sequencer().chosen_bpm();
+                        remember_tempo(&mut self.recent_tempos, bpm);
+                    }
+                }
                 Command::none()
             }
-            Message::FirstBeats(val) => {
-                self.accentuate_first_beat = val;
+            Message::StepSizeUpdate(step) => {
+                self.step_size = step;
                 Command::none()
             }
-            Message::OffBeats(val) => {
-                self.off_beats = val;
+            Message::TempoDecimals(decimals) => {
+                self.tempo_decimals = decimals;
                 Command::none()
             }
-            Message::SetVolume(vol) => {
-                self.volume = vol;
-                self.vol_tx.send(vol).unwrap();
+            Message::StoreTempoSlot(slot) => {
+                self.tempo_slots[slot] = Some(self.engine.sequencer().chosen_bpm());
+                self.active_slot = Some(slot);
                 Command::none()
             }
-            Message::Beat => {
-                match self.state {
-                    MetroState::FirstBeat => {
-                        self.player_thread.send(Beat::Beat).unwrap();
-                        self.state = MetroState::Beat(1);
-                    }
-                    MetroState::Beat(beat) => {
-                        self.player_thread
-                            .send(if self.accentuate_first_beat && beat >= self.bar - 1 {
-                                Beat::FirstBeat
-                            } else {
-                                Beat::Beat
-                            })
-                            .unwrap();
-                        if beat >= self.bar - 1 {
-                            self.state = MetroState::FirstBeat;
-                        } else {
-                            self.state = MetroState::Beat(beat + 1);
+            Message::RecallTempoSlot(slot) => match self.tempo_slots[slot] {
+                Some(bpm) => {
+                    let command = self.update(Message::BPMUpdate(bpm));
+                    self.active_slot = Some(slot);
+                    command
+                }
+                None => Command::none(),
+            },
+            Message::SwapTempoSlots => {
+                let other = match self.active_slot {
+                    Some(0) => 1,
+                    _ => 0,
+                };
+                self.update(Message::RecallTempoSlot(other))
+            }
+            Message::PresetNameInput(name) => {
+                self.preset_name = name;
+                Command::none()
+            }
+            Message::SavePreset => {
+                let name = self.preset_name.trim().to_string();
+                if name.is_empty() {
+                    return Command::none();
+                }
+                let settings = self.settings();
+                // Saving under an existing name updates that preset in place
+                match self
+                    .presets
+                    .iter_mut()
+                    .find(|(existing, _)| *existing == name)
+                {
+                    Some(preset) => preset.1 = settings,
+                    None => self.presets.push((name, settings)),
+                }
+                self.preset_name.clear();
+                let _ = self.presets_tx.send(self.presets.clone());
+                Command::none()
+            }
+            Message::LoadPreset(index) => match self.presets.get(index) {
+                Some((_, preset)) => self.apply_settings(self.with_meter(preset)),
+                None => Command::none(),
+            },
+            Message::SongNameInput(name) => {
+                self.song_name = name;
+                Command::none()
+            }
+            Message::AddSong => {
+                let name = self.song_name.trim().to_string();
+                if name.is_empty() {
+                    return Command::none();
+                }
+                self.setlist.push(name, self.settings());
+                self.song_name.clear();
+                self.save_setlist();
+                Command::none()
+            }
+            Message::SelectSong(index) => {
+                let song = self.setlist.select(index).cloned();
+                self.play_song(song)
+            }
+            Message::NextSong => {
+                let song = self.setlist.next().cloned();
+                self.play_song(song)
+            }
+            Message::PrevSong => {
+                let song = self.setlist.previous().cloned();
+                self.play_song(song)
+            }
+            Message::RemoveSong(index) => {
+                self.setlist.remove(index);
+                self.save_setlist();
+                Command::none()
+            }
+            Message::MoveSongUp(index) => {
+                self.setlist.move_up(index);
+                self.save_setlist();
+                Command::none()
+            }
+            Message::ConfigFileInput(path) => {
+                self.config_file = path;
+                Command::none()
+            }
+            Message::ExportConfig => Command::perform(
+                config::export(self.config_file.trim().to_string(), self.settings()),
+                Message::ConfigExported,
+            ),
+            Message::ImportConfig => Command::perform(
+                config::import(self.config_file.trim().to_string()),
+                |result| Message::ConfigImported(result.map(Box::new)),
+            ),
+            Message::ConfigExported(result) => {
+                self.config_file_result = Some(result.map(|()| Label::ConfigExported));
+                Command::none()
+            }
+            Message::ConfigImported(result) => match result {
+                Ok(settings) => {
+                    self.config_file_result = Some(Ok(Label::ConfigImported));
+                    // Output devices are particular to each machine
+                    self.apply_settings(MetronomeSettings {
+                        output_device: self.output_device.clone(),
+                        ..*settings
+                    })
+                }
+                Err(err) => {
+                    self.config_file_result = Some(Err(err));
+                    Command::none()
+                }
+            },
+            Message::DeletePreset(index) => {
+                if index < self.presets.len() {
+                    self.presets.remove(index);
+                    let _ = self.presets_tx.send(self.presets.clone());
+                }
+                Command::none()
+            }
+            Message::BPMTextChanged(input) => {
+                self.bpm_input = input;
+                Command::none()
+            }
+            Message::BPMTextSubmitted => match self.bpm_input.trim().parse::<f64>() {
+                Ok(bpm) if (30.0..=300.0).contains(&bpm) => {
+                    let bpm = round_bpm(bpm);
+                    self.bpm_input = tempo_input_text(bpm);
+                    self.update(Message::BPMUpdate(bpm))
+                }
+                _ => {
+                    self.bpm_input = tempo_input_text(self.engine.sequencer().chosen_bpm());
+                    Command::none()
+                }
+            },
+            Message::Tap => {
+                let now = Instant::now();
+                if self
+                    .taps
+                    .last()
+                    .is_some_and(|&last| now.duration_since(last) > TAP_TIMEOUT)
+                {
+                    self.taps.clear();
+                }
+                self.taps.push(now);
+                if self.taps.len() > TAP_WINDOW {
+                    self.taps.remove(0);
+                }
+                self.tap_spread = tap_spread(&self.taps);
+                self.tap_deviations.extend(tap_deviation(&self.taps));
+                match tap_bpm(&self.taps) {
+                    Some(bpm) => self.update(Message::BPMUpdate(bpm as f64)),
+                    None => Command::none(),
+                }
+            }
+            Message::RestartBarOnTempo(val) => {
+                self.engine.sequencer().restart_bar_on_tempo = val;
+                Command::none()
+            }
+            Message::GlideTargetUpdate(bpm) => {
+                self.engine.sequencer().glide_target = bpm;
+                Command::none()
+            }
+            Message::GlideBarsUpdate(bars) => {
+                self.engine.sequencer().glide_bars = bars;
+                Command::none()
+            }
+            Message::GlideCurveUpdate(curve) => {
+                self.engine.sequencer().glide_curve = curve;
+                Command::none()
+            }
+            Message::ToggleGlide => {
+                self.engine.sequencer().toggle_glide();
+                Command::none()
+            }
+            Message::WarmUp(val) => {
+                self.engine.sequencer().warm_up = val;
+                Command::none()
+            }
+            Message::WarmUpFromUpdate(bpm) => {
+                self.engine.sequencer().warm_up_from = bpm;
+                Command::none()
+            }
+            Message::WarmUpSecsUpdate(secs) => {
+                self.engine.sequencer().warm_up_secs = secs;
+                Command::none()
+            }
+            Message::Polyrhythm(val) => {
+                self.engine.sequencer().set_polyrhythm(val);
+                Command::none()
+            }
+            Message::PolyDivisionsUpdate(divisions) => {
+                // Takes over from the next bar, so the current one stays even
+                self.engine.sequencer().poly_voice.divisions = divisions;
+                Command::none()
+            }
+            Message::GapTrainer(val) => {
+                self.engine.sequencer().gap_trainer = val;
+                Command::none()
+            }
+            Message::GapAudibleUpdate(bars) => {
+                self.engine.sequencer().set_gap_audible_bars(bars);
+                Command::none()
+            }
+            Message::GapSilentUpdate(bars) => {
+                self.engine.sequencer().set_gap_silent_bars(bars);
+                Command::none()
+            }
+            Message::RampToggle(val) => {
+                self.engine.sequencer().set_ramp_trainer(val);
+                Command::none()
+            }
+            Message::RampStartUpdate(bpm) => {
+                self.engine.sequencer().ramp_start = bpm;
+                Command::none()
+            }
+            Message::RampEndUpdate(bpm) => {
+                self.engine.sequencer().ramp_end = bpm;
+                Command::none()
+            }
+            Message::RampStepUpdate(step) => {
+                self.engine.sequencer().set_ramp_step(step);
+                Command::none()
+            }
+            Message::RampEveryUpdate(bars) => {
+                self.engine.sequencer().set_ramp_every_bars(bars);
+                Command::none()
+            }
+            Message::RampLoop(val) => {
+                self.engine.sequencer().ramp_loop = val;
+                Command::none()
+            }
+            Message::DrillToggle(val) => {
+                self.engine.sequencer().set_drill(val);
+                Command::none()
+            }
+            Message::DrillMinUpdate(bpm) => {
+                self.engine.sequencer().drill_min = bpm;
+                Command::none()
+            }
+            Message::DrillMaxUpdate(bpm) => {
+                self.engine.sequencer().drill_max = bpm;
+                Command::none()
+            }
+            Message::DrillEveryUpdate(bars) => {
+                self.engine.sequencer().set_drill_every_bars(bars);
+                Command::none()
+            }
+            Message::ResetSession => {
+                self.practice_time = Duration::ZERO;
+                self.playing_since = self.playing_since.map(|_| Instant::now());
+                self.bars_elapsed = 0;
+                self.beats_elapsed = 0;
+                self.tempo_average = TempoAverage::default();
+                Command::none()
+            }
+            Message::ResetTempoRange => {
+                self.tempo_range = None;
+                Command::none()
+            }
+            Message::ResetSteadiness => {
+                self.tap_deviations.clear();
+                Command::none()
+            }
+            Message::TempoChangeUpdate(timing) => {
+                self.engine.sequencer().set_tempo_change(timing);
+                Command::none()
+            }
+            Message::BarUpdate(bar) => {
+                self.engine.sequencer().set_bar(bar);
+                Command::none()
+            }
+            Message::GroupsInput(input) => {
+                let mut sequencer = self.engine.sequencer();
+                if let Some(groups) = parse_groups(&input, sequencer.bar()) {
+                    // Accents move to the new groups; silent and off-beat sounds stay put
+                    if !groups.is_empty() {
+                        for (beat, sound) in sequencer.beat_sounds.iter_mut().enumerate() {
+                            if starts_group(&groups, beat as u32) {
+                                *sound = BeatSound::Accent;
+                            } else if *sound == BeatSound::Accent {
+                                *sound = BeatSound::Normal;
+                            }
                         }
                     }
-                    MetroState::Stopped => unreachable!(),
-                };
+                    sequencer.groups = groups;
+                }
+                drop(sequencer);
+                self.groups_input = input;
                 Command::none()
             }
-            Message::OffBeat => {
-                self.player_thread.send(Beat::OffBeat).unwrap();
+            Message::MeterSequenceInput(input) => {
+                if let Some(sequence) = parse_meter_sequence(&input) {
+                    let mut sequencer = self.engine.sequencer();
+                    // A running take picks the sequence up from its next bar
+                    if let Some(&bar) = sequence.first().filter(|_| !sequencer.state().is_running())
+                    {
+                        sequencer.set_bar(bar);
+                    }
+                    sequencer.meter_sequence = sequence;
+                }
+                self.meter_sequence_input = input;
                 Command::none()
             }
-        }
-    }
-
-    fn view(&self) -> Element<Self::Message> {
-        let mut beats = Vec::new();
-        let current_beat = match self.state {
-            MetroState::Beat(n) => Some(n),
-            MetroState::FirstBeat => Some(0),
-            _ => None,
-        };
-        for i in 0..self.bar {
-            beats.push(
-                circle(
-                    25.0,
-                    if Some(i) == current_beat {
-                        color!(0x6080df)
+            Message::BeatUnitUpdate(beat_unit) => {
+                self.engine.sequencer().beat_unit = beat_unit;
+                Command::none()
+            }
+            Message::CountInUpdate(bars) => {
+                self.engine.sequencer().count_in_bars = bars;
+                Command::none()
+            }
+            Message::StartBeatUpdate(beat) => {
+                self.engine.sequencer().start_beat = beat;
+                Command::none()
+            }
+            Message::AccentDownbeat(accent) => {
+                let mut sequencer = self.engine.sequencer();
+                if let Some(downbeat) = sequencer.beat_sounds.first_mut() {
+                    *downbeat = if accent {
+                        BeatSound::Accent
                     } else {
-                        color!(0xe0e0e0)
-                    },
-                )
-                .into(),
-            )
-        }
-        container(
+                        BeatSound::Normal
+                    };
+                }
+                Command::none()
+            }
+            Message::CycleBeatSound(beat) => {
+                let mut sequencer = self.engine.sequencer();
+                if let Some(sound) = sequencer.beat_sounds.get_mut(beat as usize) {
+                    *sound = sound.next();
+                }
+                Command::none()
+            }
+            Message::SubdivisionUpdate(subdivision) => {
+                self.engine.sequencer().subdivision = subdivision;
+                Command::none()
+            }
+            Message::SwingUpdate(swing) => {
+                self.engine.sequencer().swing = swing;
+                Command::none()
+            }
+            Message::PickupOffBeatUpdate(pickup) => {
+                self.engine.sequencer().pickup_off_beat = pickup;
+                Command::none()
+            }
+            Message::OffBeatAfter(beat, val) => {
+                let beat = beat as usize;
+                let mut sequencer = self.engine.sequencer();
+                if beat >= sequencer.off_beat_mask.len() {
+                    sequencer.off_beat_mask.resize(beat + 1, true);
+                }
+                sequencer.off_beat_mask[beat] = val;
+                Command::none()
+            }
+            Message::DownbeatOnly(val) => {
+                self.engine.sequencer().downbeat_only = val;
+                Command::none()
+            }
+            Message::VisualOnly(val) => {
+                self.visual_only = val;
+                self.send_player(PlayerCommand::VisualOnly(val));
+                Command::none()
+            }
+            Message::ShowBeatBar(val) => {
+                self.show_beat_bar = val;
+                Command::none()
+            }
+            Message::BeatCounter(val) => {
+                self.beat_counter = val;
+                Command::none()
+            }
+            Message::Compact(compact) => {
+                self.compact = compact;
+                window::resize(window_size(compact))
+            }
+            Message::VisualStyleChanged(style) => {
+                self.visual_style = style;
+                Command::none()
+            }
+            Message::ActiveColorChanged(color) => {
+                self.active_color = color;
+                Command::none()
+            }
+            Message::IdleColorChanged(color) => {
+                self.idle_color = color;
+                Command::none()
+            }
+            Message::StageFlash(val) => {
+                self.stage_flash = val;
+                Command::none()
+            }
+            Message::Pulse(val) => {
+                self.pulse = val;
+                Command::none()
+            }
+            Message::BigDownbeat(val) => {
+                self.big_downbeat = val;
+                Command::none()
+            }
+            Message::BackgroundGradient(val) => {
+                self.background_gradient = val;
+                Command::none()
+            }
+            Message::GradientIntensity(intensity) => {
+                self.gradient_intensity = intensity;
+                Command::none()
+            }
+            Message::Frame(now) => {
+                self.now = now;
+                Command::none()
+            }
+            Message::SetVolume(vol) => {
+                // Reaching for the volume means wanting to hear it
+                self.volume = vol;
+                self.muted = false;
+                volume::set(vol);
+                Command::none()
+            }
+            Message::ToggleMute => {
+                self.muted = !self.muted;
+                volume::set(if self.muted { 0.0 } else { self.volume });
+                Command::none()
+            }
+            Message::ToggleAbout => {
+                self.show_about = !self.show_about;
+                Command::none()
+            }
+            Message::ToggleSounds => {
+                self.show_sounds = !self.show_sounds;
+                Command::none()
+            }
+            Message::SampleChanged(role, sample) => {
+                self.sound_map.get_mut(role).sample = sample;
+                let _ = self.sound_map_tx.send(self.sound_map);
+                Command::none()
+            }
+            Message::PreviewSound(role) => {
+                // Played straight away, so the beats and the clock carry on as they were
+                let beat = match role {
+                    Role::Downbeat => Beat::FirstBeat,
+                    Role::Accent => Beat::MediumAccent,
+                    Role::Beat => Beat::Beat,
+                    Role::OffBeat => Beat::OffBeat,
+                    Role::Subdivision => Beat::Subdivision,
+                    Role::End => Beat::Cue(Cue::Start),
+                };
+                self.send_player(PlayerCommand::Play(beat));
+                Command::none()
+            }
+            Message::RoleVolume(role, volume) => {
+                self.sound_map.get_mut(role).volume = volume;
+                let _ = self.sound_map_tx.send(self.sound_map);
+                Command::none()
+            }
+            Message::RolePitch(role, pitch) => {
+                self.sound_map.get_mut(role).pitch = pitch;
+                let _ = self.sound_map_tx.send(self.sound_map);
+                Command::none()
+            }
+            Message::SoundPackChanged(pack) => {
+                self.sound_map.pack = pack;
+                let _ = self.sound_map_tx.send(self.sound_map);
+                Command::none()
+            }
+            Message::ListOutputDevices => {
+                Command::perform(output::device_names(), Message::OutputDevicesListed)
+            }
+            Message::OutputDevicesListed(names) => {
+                if let Some(name) = &self.output_device {
+                    let connected = names.contains(name);
+                    if !connected && !self.output_fallback {
+                        self.output_fallback = true;
+                        self.send_player(PlayerCommand::Device(None));
+                    } else if connected && self.output_fallback {
+                        self.output_fallback = false;
+                        self.output_error = None;
+                        self.send_player(PlayerCommand::Device(Some(name.clone())));
+                    }
+                }
+                // Nothing could be played last time; a device may have turned up since
+                if self.output_lost.is_some() && !names.is_empty() {
+                    let device = self.output_device.clone().filter(|_| !self.output_fallback);
+                    self.send_player(PlayerCommand::Device(device));
+                }
+                self.output_devices = names;
+                Command::none()
+            }
+            Message::OutputDeviceSelected(choice) => {
+                self.select_output_device(choice.name);
+                Command::none()
+            }
+            Message::OutputDeviceFailed(err) => {
+                self.output_error = Some(err);
+                Command::none()
+            }
+            Message::AudioError(err) => {
+                self.output_lost = Some(err);
+                Command::none()
+            }
+            Message::OutputLost(err) => {
+                self.output_lost = Some(fill(self.t(Label::OutputLost), &[&err]));
+                Command::none()
+            }
+            Message::ClickTrackFailed(err) => {
+                self.output_lost = Some(fill(self.t(Label::ClickTrackFailed), &[&err]));
+                Command::none()
+            }
+            Message::ClickTrackStopped => {
+                self.output_lost = Some(self.t(Label::ClickTrackStopped).to_string());
+                Command::none()
+            }
+            Message::AudioConnected => {
+                self.output_lost = None;
+                Command::none()
+            }
+            Message::SampleFileInput(role, path) => {
+                self.sample_inputs[role.index()] = path;
+                Command::none()
+            }
+            Message::LoadSampleFile(role) => {
+                let path = self.sample_inputs[role.index()].trim().to_string();
+                if path.is_empty() {
+                    self.sample_files[role.index()] = None;
+                    self.sample_errors[role.index()] = None;
+                    self.send_player(PlayerCommand::FileClick(role, None));
+                    Command::none()
+                } else {
+                    load_sample_file(role, path)
+                }
+            }
+            Message::SampleFileLoaded(role, path, result) => {
+                // A file that can't be played falls back to the embedded sample
+                let click = match result {
+                    Ok(FileClick(click)) => {
+                        self.sample_files[role.index()] = Some(path);
+                        self.sample_errors[role.index()] = None;
+                        Some(click)
+                    }
+                    Err(err) => {
+                        self.sample_files[role.index()] = None;
+                        self.sample_errors[role.index()] = Some(err);
+                        None
+                    }
+                };
+                self.send_player(PlayerCommand::FileClick(role, click));
+                Command::none()
+            }
+            Message::CalibrateLatency => match &self.sounds {
+                Some(sounds) => {
+                    self.calibrating = true;
+                    self.calibration = None;
+                    Command::perform(
+                        calibrate::measure_latency(sounds.get(self.sound_map.id(Role::Downbeat))),
+                        Message::CalibrationDone,
+                    )
+                }
+                None => Command::none(),
+            },
+            Message::CalibrationDone(result) => {
+                self.calibrating = false;
+                self.calibration = Some(result);
+                Command::none()
+            }
+            Message::LatencyOffsetUpdate(ms) => {
+                self.latency_offset_ms = ms;
+                Command::none()
+            }
+            Message::SampleAccurate(val) => {
+                self.sample_accurate = val;
+                self.send_player(PlayerCommand::SampleAccurate(val));
+                Command::none()
+            }
+            Message::AcceptLatency => {
+                if let Some(Ok(latency)) = self.calibration.take() {
+                    self.latency_offset_ms = latency.as_millis() as u32;
+                }
+                Command::none()
+            }
+            Message::StartStopCues(val) => {
+                self.start_stop_cues = val;
+                Command::none()
+            }
+            Message::TruncateClicks(val) => {
+                self.truncate_clicks = val;
+                let _ = self
+                    .max_click_tx
+                    .send(max_click(self.truncate_clicks, self.max_click_ms));
+                Command::none()
+            }
+            Message::MaxClickUpdate(ms) => {
+                self.max_click_ms = ms;
+                let _ = self
+                    .max_click_tx
+                    .send(max_click(self.truncate_clicks, self.max_click_ms));
+                Command::none()
+            }
+            Message::BeatInTitle(val) => {
+                self.beat_in_title = val;
+                Command::none()
+            }
+            Message::AttentionOnDownbeat(val) => {
+                self.attention_on_downbeat = val;
+                if val {
+                    Command::none()
+                } else {
+                    window::request_user_attention(None)
+                }
+            }
+            Message::WindowFocused(focused) => {
+                self.focused = focused;
+                Command::none()
+            }
+            Message::InhibitSleep(val) => {
+                self.inhibit_sleep = val;
+                self.update_sleep_inhibitor();
+                Command::none()
+            }
+            Message::LanguageChanged(language) => {
+                self.language = language;
+                Command::none()
+            }
+            Message::DarkTheme(val) => {
+                self.theme = if val { Theme::Dark } else { Theme::Light };
+                Command::none()
+            }
+            Message::ShareStatus(val) => {
+                self.set_share_status(val);
+                Command::none()
+            }
+            Message::Undo => match self.history.undo(self.settings()) {
+                Some(settings) => self.apply_settings(settings),
+                None => Command::none(),
+            },
+            Message::Redo => match self.history.redo(self.settings()) {
+                Some(settings) => self.apply_settings(settings),
+                None => Command::none(),
+            },
+            Message::KeyPressed(key_code, modifiers) => {
+                let binding = Binding::new(key_code, modifiers);
+                match self.rebinding.take() {
+                    // Escape backs out of rebinding; it can't be bound to anything else
+                    Some(_) if key_code == KeyCode::Escape => Command::none(),
+                    Some(action) => {
+                        let before = self.settings();
+                        self.key_map.rebind(action, binding);
+                        let after = self.settings();
+                        self.history.record(before, &after, Instant::now());
+                        Command::none()
+                    }
+                    None => match self.key_map.action(binding) {
+                        Some(action) => self.update(self.action_message(action)),
+                        None => Command::none(),
+                    },
+                }
+            }
+            Message::ToggleKeys => {
+                self.show_keys = !self.show_keys;
+                self.rebinding = None;
+                Command::none()
+            }
+            Message::Rebind(action) => {
+                self.rebinding = Some(action);
+                Command::none()
+            }
+            Message::PanicStop => {
+                self.engine.stop();
+                SILENCE_GENERATION.fetch_add(1, Ordering::AcqRel);
+                self.update_sleep_inhibitor();
+                self.dismiss_attention()
+            }
+            Message::Beat(event) => match event.tick {
+                Tick::Beat(index) => self.show_beat(index, event.counting_in, event.at),
+                Tick::OffBeat(..) | Tick::Poly(_) => Command::none(),
+            },
+        };
+        let sequencer = self.engine.sequencer().clone();
+        // Follow tempo changes from anywhere else, without clobbering typing in between
+        if sequencer.chosen_bpm() != self.sequencer.chosen_bpm() {
+            self.bpm_input = tempo_input_text(sequencer.chosen_bpm());
+        }
+        // A grouping only fits the bar it was made for
+        if sequencer.bar() != self.sequencer.bar() && sequencer.groups.is_empty() {
+            self.groups_input.clear();
+        }
+        self.sequencer = sequencer;
+        if let Some(before) = before {
+            let after = self.settings();
+            self.history.record(before, &after, Instant::now());
+        }
+        // Paused and stopped time doesn't count towards the session
+        match (self.sequencer.state().is_running(), self.playing_since) {
+            (true, None) => self.playing_since = Some(Instant::now()),
+            (false, Some(since)) => {
+                self.practice_time += since.elapsed();
+                self.playing_since = None;
+            }
+            _ => {}
+        }
+        if persist {
+            let settings = self.settings();
+            if settings != self.saved_settings {
+                self.saved_settings = settings.clone();
+                let _ = self.config_tx.send(settings);
+            }
+        }
+        self.publish_status();
+        command
+    }
+
+    fn view(&self) -> Element<'_, Self::Message> {
+        if self.show_about {
+            return self.about_view();
+        }
+        if self.show_keys {
+            return self.keys_view();
+        }
+        if self.show_sounds {
+            return self.sounds_view();
+        }
+        let mut beats = Vec::new();
+        let current_beat = self.shown_beat();
+        let theme = self.theme();
+        let palette = theme.extended_palette();
+        let mut active_color = self.active_color.unwrap_or(palette.primary.base.color);
+        let mut idle_color = self.idle_color.unwrap_or(palette.background.strong.color);
+        if matches!(self.sequencer.state(), MetroState::CountIn { .. }) {
+            active_color.a *= COUNT_IN_ALPHA;
+            idle_color.a *= COUNT_IN_ALPHA;
+        }
+        match (
+            self.visual_style,
+            widgets::conductor::pattern(self.sequencer.bar()),
+        ) {
+            (VisualStyle::Conductor, Some(pattern)) => beats.push(
+                conductor(
+                    200.0,
+                    pattern,
+                    current_beat,
+                    self.beat_phase(),
+                    active_color,
+                    idle_color,
+                )
+                .into(),
+            ),
+            (VisualStyle::Pendulum, _) => beats.push(
+                pendulum(
+                    200.0,
+                    (*self.sequencer.state() != MetroState::Stopped).then(|| self.swings_right()),
+                    self.beat_phase(),
+                    active_color,
+                    idle_color,
+                )
+                .into(),
+            ),
+            (VisualStyle::Single, _) => {
+                let lit = current_beat.is_some() && self.beat_phase() < FLASH_PHASE;
+                let mut dot = circle(40.0, if lit { active_color } else { idle_color });
+                if lit && self.pulse {
+                    dot = dot.with_pulse(self.beat_phase() / FLASH_PHASE);
+                }
+                beats.push(dot.into())
+            }
+            _ => {
+                for i in 0..self.sequencer.bar() {
+                    if i > 0 && starts_group(&self.sequencer.groups, i) {
+                        beats.push(horizontal_space(15.0).into());
+                    }
+                    let lit = Some(i) == current_beat
+                        && (i == 0 || self.visual_style == VisualStyle::AllBeats);
+                    // Accents are drawn bigger and off-beat sounds smaller; silent beats fade
+                    let sound = self.beat_sound(i);
+                    let mut size = match sound {
+                        BeatSound::Accent => 30.0,
+                        BeatSound::OffBeat => 18.0,
+                        BeatSound::Normal | BeatSound::Silent => 25.0,
+                    };
+                    if i == 0 && self.big_downbeat {
+                        size *= 1.5;
+                    }
+                    let mut color = if lit { active_color } else { idle_color };
+                    if sound == BeatSound::Silent {
+                        color.a *= SILENT_BEAT_ALPHA;
+                    }
+                    let mut dot = circle(size, color).on_press(Message::CycleBeatSound(i));
+                    if lit && self.pulse {
+                        dot = dot.with_pulse(self.beat_phase());
+                    }
+                    beats.push(dot.into())
+                }
+            }
+        }
+        if self.compact {
+            let page = container(
+                column![
+                    text(fill(
+                        self.t(Label::Bpm),
+                        &[&self.tempo_text(self.sequencer.bpm())]
+                    ))
+                    .size(46),
+                    wrap(beats).spacing(5.0).line_spacing(5.0),
+                    self.transport_view(),
+                    button(self.t(Label::FullLayout))
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::Compact(false)),
+                ]
+                .spacing(20.0)
+                .align_items(iced::Alignment::Center),
+            );
+            return self.with_ambiance(page);
+        }
+        let mut pulse = column![].spacing(30.0).align_items(iced::Alignment::Center);
+        // Playing from the setlist puts the song's name up top, readable from the stand
+        if let Some((name, _)) = self
+            .setlist
+            .current()
+            .map(|index| &self.setlist.songs()[index])
+        {
+            pulse = pulse.push(text(name).size(32));
+        }
+        let mut tempo = column![
+            text(fill(
+                self.t(Label::Bpm),
+                &[&self.tempo_text(self.sequencer.bpm())]
+            ))
+            .size(46),
+            text(tempo_marking(self.sequencer.bpm())).size(20),
+            text(self.t(Label::BpmCountsQuarters)).size(14),
+        ]
+        .width(250.0)
+        .align_items(iced::Alignment::Center);
+        if let Some(preview) = self.ramp_preview() {
+            tempo = tempo.push(text(preview).size(14));
+        }
+        pulse = pulse
+            .push(
+                row![
+                    step_button(format!("−{}", step_text(10. * self.step_size)), -10),
+                    step_button(String::from("−"), -1),
+                    tempo,
+                    step_button(String::from("+"), 1),
+                    step_button(format!("+{}", step_text(10. * self.step_size)), 10),
+                ]
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center),
+            )
+            .push(
+                row![
+                    slider(
+                        30.0..=300.0,
+                        self.sequencer.chosen_bpm(),
+                        Message::BPMUpdate
+                    )
+                    .step(0.5)
+                    .width(370.),
+                    text_input("", &self.bpm_input)
+                        .on_input(Message::BPMTextChanged)
+                        .on_submit(Message::BPMTextSubmitted)
+                        .width(70.),
+                    button(text("½×")).on_press(Message::TempoMultiply(0.5)),
+                    button(text("2×")).on_press(Message::TempoMultiply(2.)),
+                ]
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center),
+            )
+            .push(wrap(beats).spacing(5.0).line_spacing(5.0));
+        if let Some(count) = self.beat_counter.then(|| self.beat_count()).flatten() {
+            pulse = pulse.push(text(count).size(64));
+        }
+        if self.sequencer.polyrhythm() {
+            let poly_color = palette.success.base.color;
+            let mut poly_beats = Vec::new();
+            for i in 0..self.sequencer.poly_voice.divisions {
+                let lit = *self.sequencer.state() != MetroState::Stopped
+                    && self.sequencer.poly_beat() == Some(i);
+                poly_beats.push(circle(18.0, if lit { poly_color } else { idle_color }).into());
+            }
+            pulse = pulse.push(wrap(poly_beats).spacing(5.0).line_spacing(5.0));
+        }
+        if self.show_beat_bar {
+            pulse = pulse.push(beat_bar(
+                450.0,
+                6.0,
+                self.beat_phase(),
+                active_color,
+                idle_color,
+            ));
+        }
+        if self.beats_elapsed > 0 || self.playing_since.is_some() {
+            let mut session = row![text(fill(
+                self.t(Label::Session),
+                &[
+                    &clock_time(self.practiced()),
+                    &self.bars_elapsed,
+                    &self.beats_elapsed
+                ]
+            ))]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center);
+            if let Some(bpm) = self.tempo_average.bpm() {
+                session = session.push(text(fill(
+                    self.t(Label::AverageTempo),
+                    &[&self.tempo_text(round_bpm(bpm))],
+                )));
+            }
+            pulse = pulse.push(
+                session.push(
+                    button(self.t(Label::Reset))
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::ResetSession),
+                ),
+            );
+        }
+        if let Some((min, max)) = self.tempo_range {
+            pulse = pulse.push(
+                row![
+                    text(fill(
+                        self.t(Label::TempoRange),
+                        &[&self.tempo_text(min), &self.tempo_text(max)]
+                    )),
+                    button(self.t(Label::Reset))
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::ResetTempoRange)
+                ]
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        if let Some((min, max)) = self.tempo_record {
+            pulse = pulse.push(
+                text(fill(
+                    self.t(Label::TempoRecord),
+                    &[&self.tempo_text(min), &self.tempo_text(max)],
+                ))
+                .size(14),
+            );
+        }
+        let banner: Element<_> = match self.audio_error.as_ref().or(self.output_lost.as_ref()) {
+            Some(err) => text(err).style(theme.palette().danger).into(),
+            None => vertical_space(0.0).into(),
+        };
+        let mut share_status = column![checkbox(
+            self.t(Label::ShareStatus),
+            self.share_status,
+            Message::ShareStatus
+        )]
+        .align_items(iced::Alignment::Center);
+        if let Some(err) = &self.status_error {
+            share_status = share_status.push(text(err));
+        }
+        let page = container(
             column![
                 vertical_space(25.0),
-                text("Metronome")
+                text(self.t(Label::Title))
                     .size(72)
                     .horizontal_alignment(iced::alignment::Horizontal::Center),
+                banner,
                 container(
                     column![
+                        pulse,
                         column![
-                            text(format!("{} BPM", self.bpm)).size(46),
-                            slider(30..=300, self.bpm, |v| Message::BPMUpdate(v)).width(450.),
-                            row(beats).spacing(5.0),
-                        ]
-                        .spacing(30.0)
-                        .align_items(iced::Alignment::Center),
-                        column![
-                            text(format!("{} beats per bar", self.bar)),
-                            slider(2..=16, self.bar, |v| Message::BarUpdate(v)),
+                            self.tempo_slots_view(),
+                            self.recent_tempos_view(),
                             row![
-                                checkbox("First beat accent", self.accentuate_first_beat, |val| {
-                                    Message::FirstBeats(val)
-                                })
+                                text(fill(
+                                    self.t(Label::TempoStep),
+                                    &[&step_text(self.step_size)]
+                                )),
+                                slider(0.5..=10.0, self.step_size, Message::StepSizeUpdate)
+                                    .step(0.5)
+                                    .width(150.0),
+                            ]
+                            .spacing(10.0)
+                            .align_items(iced::Alignment::Center),
+                            self.tempo_decimals_view(),
+                            self.presets_view(),
+                            self.setlist_view(),
+                            self.config_file_view(),
+                            text(fill(self.t(Label::BeatsPerBar), &[&self.sequencer.bar()])),
+                            slider(2..=MAX_BAR, self.sequencer.bar(), Message::BarUpdate),
+                            self.beat_unit_view(),
+                            row![
+                                text(fill(
+                                    self.t(Label::StartOnBeat),
+                                    &[&self.sequencer.start_beat]
+                                )),
+                                slider(
+                                    1..=self.sequencer.bar(),
+                                    self.sequencer.start_beat,
+                                    Message::StartBeatUpdate
+                                )
+                                .width(150.0),
+                                text(fill(
+                                    self.t(Label::CountInBars),
+                                    &[&self.sequencer.count_in_bars]
+                                )),
+                                slider(0..=4, self.sequencer.count_in_bars, Message::CountInUpdate)
+                                    .width(80.0)
+                            ]
+                            .spacing(10.0)
+                            .align_items(iced::Alignment::Center),
+                            text(self.t(Label::BeatSoundsHint)).size(14),
+                            row![
+                                checkbox(
+                                    self.t(Label::AccentDownbeat),
+                                    self.beat_sound(0) == BeatSound::Accent,
+                                    Message::AccentDownbeat
+                                )
                                 .width(Length::FillPortion(1)),
-                                checkbox("Off-beats", self.off_beats, |val| Message::OffBeats(val))
-                                    .width(Length::FillPortion(1))
+                                checkbox(
+                                    self.t(Label::DownbeatOnly),
+                                    self.sequencer.downbeat_only,
+                                    |val| { Message::DownbeatOnly(val) }
+                                )
+                                .width(Length::FillPortion(1)),
+                                checkbox(
+                                    self.t(Label::VisualOnly),
+                                    self.visual_only,
+                                    Message::VisualOnly
+                                )
+                                .width(Length::FillPortion(1))
                             ]
                             .align_items(iced::Alignment::Center)
                             .width(450),
-                            "Volume:",
+                            self.groups_view(),
+                            self.meter_sequence_view(),
+                            self.subdivision_view(),
+                            self.off_beat_mask_view(),
+                            self.polyrhythm_view(),
+                            row![
+                                checkbox(
+                                    self.t(Label::TruncateClicks),
+                                    self.truncate_clicks,
+                                    |val| { Message::TruncateClicks(val) }
+                                ),
+                                slider(10..=130, self.max_click_ms, Message::MaxClickUpdate),
+                                text(fill(self.t(Label::Milliseconds), &[&self.max_click_ms]))
+                            ]
+                            .spacing(5.0)
+                            .align_items(iced::Alignment::Center),
+                            checkbox(self.t(Label::StartStopCues), self.start_stop_cues, |val| {
+                                Message::StartStopCues(val)
+                            }),
+                            checkbox(self.t(Label::BeatProgressBar), self.show_beat_bar, |val| {
+                                Message::ShowBeatBar(val)
+                            }),
+                            checkbox(
+                                self.t(Label::BeatCounter),
+                                self.beat_counter,
+                                Message::BeatCounter
+                            ),
+                            self.tempo_change_view(),
+                            checkbox(
+                                self.t(Label::RestartBarOnTempo),
+                                self.sequencer.restart_bar_on_tempo,
+                                Message::RestartBarOnTempo
+                            ),
+                            self.glide_view(),
+                            row![
+                                checkbox(
+                                    fill(
+                                        self.t(Label::WarmUpFrom),
+                                        &[&self.sequencer.warm_up_from]
+                                    ),
+                                    self.sequencer.warm_up,
+                                    Message::WarmUp
+                                ),
+                                slider(
+                                    30..=300,
+                                    self.sequencer.warm_up_from,
+                                    Message::WarmUpFromUpdate
+                                )
+                                .width(100.0),
+                                text(fill(
+                                    self.t(Label::WarmUpSecs),
+                                    &[&self.sequencer.warm_up_secs]
+                                )),
+                                slider(
+                                    5..=120,
+                                    self.sequencer.warm_up_secs,
+                                    Message::WarmUpSecsUpdate
+                                )
+                                .width(100.0),
+                            ]
+                            .spacing(10.0)
+                            .align_items(iced::Alignment::Center),
+                            row![
+                                checkbox(
+                                    fill(
+                                        self.t(Label::GapAudible),
+                                        &[&self.sequencer.gap_audible_bars()]
+                                    ),
+                                    self.sequencer.gap_trainer,
+                                    Message::GapTrainer
+                                ),
+                                slider(
+                                    1..=16,
+                                    self.sequencer.gap_audible_bars(),
+                                    Message::GapAudibleUpdate
+                                )
+                                .width(100.0),
+                                text(fill(
+                                    self.t(Label::GapSilent),
+                                    &[&self.sequencer.gap_silent_bars()]
+                                )),
+                                slider(
+                                    1..=16,
+                                    self.sequencer.gap_silent_bars(),
+                                    Message::GapSilentUpdate
+                                )
+                                .width(100.0),
+                            ]
+                            .spacing(10.0)
+                            .align_items(iced::Alignment::Center),
+                            self.ramp_view(),
+                            self.drill_view(),
+                            self.visual_style_view(),
+                            self.beat_colors_view(),
+                            row![
+                                checkbox(self.t(Label::Pulse), self.pulse, Message::Pulse),
+                                checkbox(
+                                    self.t(Label::BigDownbeat),
+                                    self.big_downbeat,
+                                    Message::BigDownbeat
+                                ),
+                                checkbox(
+                                    self.t(Label::StageFlash),
+                                    self.stage_flash,
+                                    Message::StageFlash
+                                ),
+                                checkbox(
+                                    self.t(Label::BackgroundGradient),
+                                    self.background_gradient,
+                                    Message::BackgroundGradient
+                                ),
+                                slider(
+                                    0.05..=0.5,
+                                    self.gradient_intensity,
+                                    Message::GradientIntensity
+                                )
+                                .step(0.01)
+                                .width(100.0)
+                            ]
+                            .spacing(10.0)
+                            .align_items(iced::Alignment::Center),
+                            checkbox(
+                                self.t(Label::AttentionOnDownbeat),
+                                self.attention_on_downbeat,
+                                Message::AttentionOnDownbeat
+                            ),
+                            checkbox(
+                                self.t(Label::BeatInTitle),
+                                self.beat_in_title,
+                                Message::BeatInTitle
+                            ),
+                            checkbox(
+                                self.t(Label::KeepAwake),
+                                self.inhibit_sleep,
+                                Message::InhibitSleep
+                            ),
+                            share_status,
+                            self.latency_view(),
+                            self.t(Label::Volume),
                             row![
-                                slider(0.1..=5.0, self.volume, |val| Message::SetVolume(val))
-                                    .step(0.01),
-                                text(format!("{}%", (self.volume * 100.).round()))
+                                slider(0.1..=5.0, self.volume, Message::SetVolume).step(0.01),
+                                text(format!("{}%", (self.volume * 100.).round())),
+                                button(self.t(if self.muted {
+                                    Label::Unmute
+                                } else {
+                                    Label::Mute
+                                }))
+                                .style(if self.muted {
+                                    iced::theme::Button::Primary
+                                } else {
+                                    iced::theme::Button::Secondary
+                                })
+                                .on_press(Message::ToggleMute)
                             ]
                             .spacing(5.0)
+                            .align_items(iced::Alignment::Center)
                         ]
                         .align_items(iced::Alignment::Center)
                         .spacing(10.0)
                         .max_width(450),
-                        button(
-                            text(if self.state == MetroState::Stopped {
-                                "Start"
-                            } else {
-                                "Stop"
-                            })
-                            .size(32)
-                            .horizontal_alignment(iced::alignment::Horizontal::Center)
-                        )
-                        .width(150.0)
-                        .on_press(Message::Toggle)
+                        self.transport_view(),
+                        row![
+                            checkbox(
+                                self.t(Label::DarkTheme),
+                                self.theme == Theme::Dark,
+                                Message::DarkTheme
+                            ),
+                            text(self.t(Label::Language)),
+                            pick_list(
+                                &Language::ALL[..],
+                                Some(self.language),
+                                Message::LanguageChanged
+                            ),
+                            button(self.t(Label::Undo))
+                                .style(iced::theme::Button::Text)
+                                .on_press_maybe(self.history.can_undo().then_some(Message::Undo)),
+                            button(self.t(Label::Redo))
+                                .style(iced::theme::Button::Text)
+                                .on_press_maybe(self.history.can_redo().then_some(Message::Redo)),
+                            button(self.t(Label::Sounds))
+                                .style(iced::theme::Button::Text)
+                                .on_press(Message::ToggleSounds),
+                            button(self.t(Label::Compact))
+                                .style(iced::theme::Button::Text)
+                                .on_press(Message::Compact(true)),
+                            button(self.t(Label::About))
+                                .style(iced::theme::Button::Text)
+                                .on_press(Message::ToggleAbout)
+                        ]
+                        .spacing(10.0)
+                        .align_items(iced::Alignment::Center),
+                        row![
+                            text(self.t(Label::KeysHint)).size(14),
+                            button(text(self.t(Label::KeyboardShortcuts)).size(14))
+                                .style(iced::theme::Button::Text)
+                                .on_press(Message::ToggleKeys)
+                        ]
+                        .spacing(10.0)
+                        .align_items(iced::Alignment::Center)
                     ]
                     .spacing(30.0)
                     .align_items(iced::Alignment::Center)
@@ -305,37 +2474,1851 @@ impl Application for Metronome {
                 .center_x(),
             ]
             .align_items(iced::Alignment::Center),
+        );
+        self.with_ambiance(page)
+    }
+}
+
+impl Metronome {
+    /// `label` in the selected language.
+    fn t(&self, label: Label) -> &'static str {
+        self.language.get(label)
+    }
+
+    fn tempo_text(&self, bpm: f64) -> String {
+        tempo_text(bpm, self.tempo_decimals)
+    }
+
+    /// The ramp trainer's next step, as the bar of this one and the change it brings.
+    fn ramp_preview(&self) -> Option<String> {
+        let (bar, next) = self.sequencer.ramp_next_step()?;
+        let change = format!("{:+}", next as i64 - self.sequencer.bpm().round() as i64);
+        Some(fill(
+            self.t(Label::RampNextStep),
+            &[&bar, &self.sequencer.ramp_every_bars(), &change],
+        ))
+    }
+
+    /// Start/stop, pause, panic stop and tap, shown in both layouts.
+    fn transport_view(&self) -> Element<'_, Message> {
+        let mut tap = column![button(
+            text(self.t(Label::Tap))
+                .size(32)
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
         )
-        .width(Length::Fill)
-        .height(Length::Fill)
-        .center_x()
-        .center_y()
+        .width(100.0)
+        .style(iced::theme::Button::Secondary)
+        .on_press(Message::Tap)]
+        .spacing(4.0)
+        .align_items(iced::Alignment::Center);
+        let theme = self.theme();
+        let palette = theme.extended_palette();
+        if let Some(spread) = self.tap_spread {
+            let color = if spread <= TAP_STEADY {
+                palette.success.base.color
+            } else if spread <= TAP_UNEVEN {
+                palette.background.base.text
+            } else {
+                palette.danger.base.color
+            };
+            tap = tap.push(
+                text(format!("±{} ms", spread.as_millis()))
+                    .size(14)
+                    .style(color),
+            );
+        }
+        if !self.tap_deviations.is_empty() {
+            tap = tap
+                .push(
+                    histogram(
+                        100.0,
+                        24.0,
+                        steadiness_buckets(&self.tap_deviations),
+                        palette.background.base.text,
+                        palette.background.weak.color,
+                    )
+                    .with_highlight(STEADINESS_BUCKETS / 2, palette.success.base.color),
+                )
+                .push(
+                    button(text(self.t(Label::Reset)).size(14))
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::ResetSteadiness),
+                );
+        }
+        row![
+            button(
+                text(if *self.sequencer.state() == MetroState::Stopped {
+                    self.t(Label::Start)
+                } else {
+                    self.t(Label::Stop)
+                })
+                .size(32)
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
+            )
+            .width(150.0)
+            .on_press(Message::Toggle),
+            button(
+                text(if matches!(self.sequencer.state(), MetroState::Paused(_)) {
+                    self.t(Label::Resume)
+                } else {
+                    self.t(Label::Pause)
+                })
+                .size(32)
+                .horizontal_alignment(iced::alignment::Horizontal::Center)
+            )
+            .width(150.0)
+            .on_press_maybe(
+                (*self.sequencer.state() != MetroState::Stopped).then_some(Message::Pause)
+            ),
+            button(
+                text(self.t(Label::Silence))
+                    .size(32)
+                    .horizontal_alignment(iced::alignment::Horizontal::Center)
+            )
+            .width(150.0)
+            .style(iced::theme::Button::Destructive)
+            .on_press(Message::PanicStop),
+            tap,
+        ]
+        .spacing(10.0)
         .into()
     }
-}
 
-enum Beat {
-    FirstBeat,
-    OffBeat,
-    Beat,
-}
+    /// `page` with the stage flash or background gradient behind it, if either is on.
+    fn with_ambiance<'a>(&self, page: Container<'a, Message>) -> Element<'a, Message> {
+        let page = page
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y();
+        if let Some(strength) = self.flash_strength() {
+            let downbeat = self.shown_beat() == Some(0);
+            page.style(move |theme: &Theme| ambiance::flash(theme, downbeat, strength))
+                .into()
+        } else if self.background_gradient {
+            let phase = self.bar_phase();
+            let intensity = self.gradient_intensity;
+            page.style(move |theme: &Theme| ambiance::appearance(theme, phase, intensity))
+                .into()
+        } else {
+            page.into()
+        }
+    }
+
+    /// The current settings; a tempo waiting to take over counts as already chosen.
+    fn settings(&self) -> MetronomeSettings {
+        let sequencer = self.engine.sequencer();
+        MetronomeSettings {
+            bar: sequencer.bar(),
+            step_size: self.step_size,
+            tempo_decimals: self.tempo_decimals,
+            bpm: sequencer
+                .warm_up_target()
+                .unwrap_or_else(|| sequencer.chosen_bpm()),
+            tempo_change: sequencer.tempo_change(),
+            restart_bar_on_tempo: sequencer.restart_bar_on_tempo,
+            glide_target: sequencer.glide_target,
+            glide_bars: sequencer.glide_bars,
+            glide_curve: sequencer.glide_curve,
+            warm_up: sequencer.warm_up,
+            warm_up_from: sequencer.warm_up_from,
+            warm_up_secs: sequencer.warm_up_secs,
+            gap_trainer: sequencer.gap_trainer,
+            gap_audible_bars: sequencer.gap_audible_bars(),
+            gap_silent_bars: sequencer.gap_silent_bars(),
+            ramp_trainer: sequencer.ramp_trainer(),
+            ramp_start: sequencer.ramp_start,
+            ramp_end: sequencer.ramp_end,
+            ramp_step: sequencer.ramp_step(),
+            ramp_every_bars: sequencer.ramp_every_bars(),
+            ramp_loop: sequencer.ramp_loop,
+            drill: sequencer.drill(),
+            drill_min: sequencer.drill_min,
+            drill_max: sequencer.drill_max,
+            drill_every_bars: sequencer.drill_every_bars(),
+            start_beat: sequencer.start_beat,
+            count_in_bars: sequencer.count_in_bars,
+            beat_sounds: sequencer.beat_sounds.clone(),
+            meter_beat_sounds: sequencer.meter_beat_sounds.clone(),
+            groups: sequencer.groups.clone(),
+            meter_sequence: sequencer.meter_sequence.clone(),
+            subdivision: sequencer.subdivision,
+            swing: sequencer.swing,
+            beat_unit: sequencer.beat_unit,
+            polyrhythm: sequencer.polyrhythm(),
+            poly_voice: sequencer.poly_voice,
+            off_beat_mask: sequencer.off_beat_mask.clone(),
+            pickup_off_beat: sequencer.pickup_off_beat,
+            downbeat_only: sequencer.downbeat_only,
+            visual_only: self.visual_only,
+            show_beat_bar: self.show_beat_bar,
+            beat_counter: self.beat_counter,
+            compact: self.compact,
+            visual_style: self.visual_style,
+            pulse: self.pulse,
+            big_downbeat: self.big_downbeat,
+            stage_flash: self.stage_flash,
+            background_gradient: self.background_gradient,
+            gradient_intensity: self.gradient_intensity,
+            volume: self.volume,
+            start_stop_cues: self.start_stop_cues,
+            truncate_clicks: self.truncate_clicks,
+            max_click_ms: self.max_click_ms,
+            sound_map: self.sound_map,
+            sample_files: self.sample_files.clone(),
+            output_device: self.output_device.clone(),
+            latency_offset_ms: self.latency_offset_ms,
+            sample_accurate: self.sample_accurate,
+            active_color: self.active_color,
+            idle_color: self.idle_color,
+            theme: self.theme.clone(),
+            attention_on_downbeat: self.attention_on_downbeat,
+            beat_in_title: self.beat_in_title,
+            inhibit_sleep: self.inhibit_sleep,
+            share_status: self.share_status,
+            language: self.language,
+            key_map: self.key_map.clone(),
+            recent_tempos: self.recent_tempos.clone(),
+        }
+    }
+
+    /// Switches to `settings` wholesale, as undo and redo do, reloading any sample files that
+    /// changed. Playback carries on.
+    fn apply_settings(&mut self, settings: MetronomeSettings) -> Command<Message> {
+        set_sequencing(&mut self.engine.sequencer(), &settings);
+        self.step_size = settings.step_size;
+        self.tempo_decimals = settings.tempo_decimals;
+        self.groups_input = groups_text(&self.engine.sequencer().groups);
+        self.meter_sequence_input = meter_sequence_text(&settings.meter_sequence);
+        if settings.visual_only != self.visual_only {
+            self.visual_only = settings.visual_only;
+            self.send_player(PlayerCommand::VisualOnly(self.visual_only));
+        }
+        self.show_beat_bar = settings.show_beat_bar;
+        self.beat_counter = settings.beat_counter;
+        self.compact = settings.compact;
+        self.visual_style = settings.visual_style;
+        self.pulse = settings.pulse;
+        self.big_downbeat = settings.big_downbeat;
+        self.stage_flash = settings.stage_flash;
+        self.background_gradient = settings.background_gradient;
+        self.gradient_intensity = settings.gradient_intensity;
+        self.volume = settings.volume;
+        if !self.muted {
+            volume::set(settings.volume);
+        }
+        self.start_stop_cues = settings.start_stop_cues;
+        self.truncate_clicks = settings.truncate_clicks;
+        self.max_click_ms = settings.max_click_ms;
+        let _ = self
+            .max_click_tx
+            .send(max_click(self.truncate_clicks, self.max_click_ms));
+        self.sound_map = settings.sound_map;
+        let _ = self.sound_map_tx.send(self.sound_map);
+        let reload = settings.sample_files != self.sample_files;
+        self.sample_files = settings.sample_files;
+        if settings.output_device != self.output_device {
+            self.select_output_device(settings.output_device);
+        }
+        self.latency_offset_ms = settings.latency_offset_ms;
+        if settings.sample_accurate != self.sample_accurate {
+            self.sample_accurate = settings.sample_accurate;
+            self.send_player(PlayerCommand::SampleAccurate(self.sample_accurate));
+        }
+        self.active_color = settings.active_color;
+        self.idle_color = settings.idle_color;
+        self.theme = settings.theme;
+        self.attention_on_downbeat = settings.attention_on_downbeat;
+        self.beat_in_title = settings.beat_in_title;
+        self.inhibit_sleep = settings.inhibit_sleep;
+        self.update_sleep_inhibitor();
+        self.language = settings.language;
+        self.key_map = settings.key_map;
+        if settings.share_status != self.share_status {
+            self.set_share_status(settings.share_status);
+        }
+        if reload {
+            self.load_sample_files()
+        } else {
+            Command::none()
+        }
+    }
 
-fn player_thread(rx: Receiver<Beat>, volume: f32, vol_rx: Receiver<f32>) {
-    let mut volume = volume;
-    let (_stream, stream_handle) = OutputStream::try_default().unwrap();
-    while let Ok(beat) = rx.recv() {
-        while let Ok(new_vol) = vol_rx.try_recv() {
-            volume = new_vol;
+    /// Loads every role's sample file, so the player matches `sample_files`.
+    fn load_sample_files(&mut self) -> Command<Message> {
+        let mut loads = Vec::new();
+        for role in Role::ALL {
+            let file = self.sample_files[role.index()].clone();
+            self.sample_inputs[role.index()] = file.clone().unwrap_or_default();
+            self.sample_errors[role.index()] = None;
+            match file {
+                Some(path) => loads.push(load_sample_file(role, path)),
+                None => self.send_player(PlayerCommand::FileClick(role, None)),
+            }
         }
-        stream_handle
-            .play_raw(
-                match beat {
-                    Beat::Beat => E_CLICK_SOURCE.clone(),
-                    Beat::FirstBeat => E_FLAT_CLICK_SOURCE.clone(),
-                    Beat::OffBeat => F_CLICK_SOURCE.clone(),
+        Command::batch(loads)
+    }
+
+    fn set_share_status(&mut self, share: bool) {
+        self.share_status = share;
+        self.status_error = None;
+        if !share {
+            self.status_server = None;
+        } else if self.status_server.is_none() {
+            match StatusServer::start() {
+                Ok(server) => self.status_server = Some(server),
+                Err(err) => {
+                    self.share_status = false;
+                    self.status_error = Some(fill(self.t(Label::ShareStatusFailed), &[&err]));
                 }
-                .amplify(volume),
-            )
-            .unwrap();
+            }
+        }
+        self.publish_status();
+    }
+
+    /// Sends the current state to status socket clients as a JSON line.
+    fn publish_status(&mut self) {
+        let beat = match self.sequencer.state().index() {
+            Some(n) => (n + 1).to_string(),
+            None => String::from("null"),
+        };
+        let status = format!(
+            "{{\"playing\":{},\"bpm\":{},\"bar\":{},\"beat\":{}}}",
+            self.sequencer.state().is_running(),
+            self.sequencer.bpm(),
+            self.sequencer.bar(),
+            beat
+        );
+        if let Some(server) = &mut self.status_server {
+            server.publish(status);
+        }
+    }
+
+    fn beat_unit_view(&self) -> Element<'_, Message> {
+        let mut units = row![text(fill(
+            self.t(Label::TimeSignature),
+            &[&self.sequencer.bar(), &self.sequencer.beat_unit]
+        ))]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
+        for beat_unit in BEAT_UNITS {
+            units = units.push(radio(
+                format!("/{}", beat_unit),
+                beat_unit,
+                Some(self.sequencer.beat_unit),
+                Message::BeatUnitUpdate,
+            ));
+        }
+        units.into()
+    }
+
+    fn polyrhythm_view(&self) -> Element<'_, Message> {
+        let mut poly = row![checkbox(
+            self.t(Label::Polyrhythm),
+            self.sequencer.polyrhythm(),
+            Message::Polyrhythm
+        )]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
+        if self.sequencer.polyrhythm() {
+            poly = poly
+                .push(text(fill(
+                    self.t(Label::PolyDivisions),
+                    &[&self.sequencer.poly_voice.divisions, &self.sequencer.bar()],
+                )))
+                .push(
+                    slider(
+                        2..=16,
+                        self.sequencer.poly_voice.divisions,
+                        Message::PolyDivisionsUpdate,
+                    )
+                    .width(150.0),
+                );
+        }
+        poly.into()
+    }
+
+    fn subdivision_view(&self) -> Element<'_, Message> {
+        let mut subdivisions = row![text(self.t(Label::Subdivision))]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center);
+        for (subdivision, label) in [
+            (1, Label::SubdivisionNone),
+            (2, Label::Eighths),
+            (3, Label::Triplets),
+            (4, Label::Sixteenths),
+        ] {
+            subdivisions = subdivisions.push(radio(
+                self.t(label),
+                subdivision,
+                Some(self.sequencer.subdivision),
+                Message::SubdivisionUpdate,
+            ));
+        }
+        if self.sequencer.subdivision.is_multiple_of(2) {
+            subdivisions = subdivisions
+                .push(text(fill(
+                    self.t(Label::Swing),
+                    &[&(self.sequencer.swing * 100.).round()],
+                )))
+                .push(
+                    slider(0.5..=0.75, self.sequencer.swing, Message::SwingUpdate)
+                        .step(0.01)
+                        .width(100.0),
+                );
+        }
+        subdivisions.into()
+    }
+
+    /// A field to split the bar into groups, with a hint while it doesn't add up.
+    fn groups_view(&self) -> Element<'_, Message> {
+        let mut groups = row![
+            text(self.t(Label::Grouping)),
+            text_input("3+2+2", &self.groups_input)
+                .on_input(Message::GroupsInput)
+                .width(100.0),
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
+        if parse_groups(&self.groups_input, self.sequencer.bar()).is_none() {
+            groups = groups
+                .push(text(fill(self.t(Label::GroupsMustSum), &[&self.sequencer.bar()])).size(14));
+        }
+        groups.into()
+    }
+
+    /// A field for the bar lengths to cycle through, with a hint while it can't be used.
+    fn meter_sequence_view(&self) -> Element<'_, Message> {
+        let mut sequence = row![
+            text(self.t(Label::MeterSequence)),
+            text_input("4, 3", &self.meter_sequence_input)
+                .on_input(Message::MeterSequenceInput)
+                .width(100.0),
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
+        if parse_meter_sequence(&self.meter_sequence_input).is_none() {
+            sequence =
+                sequence.push(text(fill(self.t(Label::MeterSequenceHint), &[&MAX_BAR])).size(14));
+        }
+        sequence.into()
+    }
+
+    /// One checkbox per beat choosing whether its off-beats play; empty without subdivisions.
+    fn off_beat_mask_view(&self) -> Element<'_, Message> {
+        if self.sequencer.subdivision < 2 {
+            return vertical_space(0.0).into();
+        }
+        let mut beats = vec![text(self.t(Label::OffBeatsAfter)).into()];
+        for beat in 0..self.sequencer.bar() {
+            let flag = self.sequencer.off_beat_mask.get(beat as usize) != Some(&false);
+            beats.push(
+                checkbox((beat + 1).to_string(), flag, move |val| {
+                    Message::OffBeatAfter(beat, val)
+                })
+                .into(),
+            );
+        }
+        let mut pickups = row![text(self.t(Label::PickupOffBeat))]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center);
+        for (pickup, label) in [
+            (PickupOffBeat::Play, Label::PickupPlay),
+            (PickupOffBeat::Soften, Label::PickupSoften),
+            (PickupOffBeat::Skip, Label::PickupSkip),
+        ] {
+            pickups = pickups.push(radio(
+                self.t(label),
+                pickup,
+                Some(self.sequencer.pickup_off_beat),
+                Message::PickupOffBeatUpdate,
+            ));
+        }
+        column![wrap(beats).spacing(10.0).line_spacing(5.0), pickups]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center)
+            .into()
+    }
+
+    /// A file to share the whole setup through, with buttons to write it or read it back.
+    fn config_file_view(&self) -> Element<'_, Message> {
+        let has_path = !self.config_file.trim().is_empty();
+        let mut file = row![
+            text_input(self.t(Label::ConfigFile), &self.config_file)
+                .on_input(Message::ConfigFileInput)
+                .width(300.0),
+            button(self.t(Label::ExportConfig))
+                .on_press_maybe(has_path.then_some(Message::ExportConfig)),
+            button(self.t(Label::ImportConfig))
+                .on_press_maybe(has_path.then_some(Message::ImportConfig)),
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
+        match &self.config_file_result {
+            Some(Ok(label)) => file = file.push(text(self.t(*label)).size(14)),
+            Some(Err(err)) => file = file.push(text(err.text(self.language)).size(14)),
+            None => {}
+        }
+        file.into()
+    }
+
+    fn tempo_slots_view(&self) -> Element<'_, Message> {
+        let mut slots = row![].spacing(10.0).align_items(iced::Alignment::Center);
+        for (slot, name) in ["A", "B"].into_iter().enumerate() {
+            let recall = button(text(match self.tempo_slots[slot] {
+                Some(bpm) => fill(self.t(Label::TempoSlot), &[&name, &self.tempo_text(bpm)]),
+                None => fill(self.t(Label::EmptyTempoSlot), &[&name]),
+            }))
+            .style(if self.active_slot == Some(slot) {
+                iced::theme::Button::Primary
+            } else {
+                iced::theme::Button::Secondary
+            })
+            .on_press_maybe(self.tempo_slots[slot].map(|_| Message::RecallTempoSlot(slot)));
+            let store = button(text(fill(self.t(Label::StoreTempoSlot), &[&name])))
+                .style(iced::theme::Button::Text)
+                .on_press(Message::StoreTempoSlot(slot));
+            slots = slots.push(recall).push(store);
+        }
+        slots
+            .push(
+                button(self.t(Label::SwapTempoSlots)).on_press_maybe(
+                    self.tempo_slots
+                        .iter()
+                        .all(Option::is_some)
+                        .then_some(Message::SwapTempoSlots),
+                ),
+            )
+            .into()
+    }
+
+    /// The recent tempos, each a button back to it; empty before there are any.
+    fn recent_tempos_view(&self) -> Element<'_, Message> {
+        if self.recent_tempos.is_empty() {
+            return vertical_space(0.0).into();
+        }
+        let mut recent = row![text(self.t(Label::RecentTempos))]
+            .spacing(5.0)
+            .align_items(iced::Alignment::Center);
+        for &bpm in &self.recent_tempos {
+            recent = recent.push(
+                button(text(self.tempo_text(bpm)))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::BPMUpdate(bpm)),
+            );
+        }
+        recent.into()
+    }
+
+    fn presets_view(&self) -> Element<'_, Message> {
+        let name = self.preset_name.trim();
+        let save = row![
+            text_input(self.t(Label::PresetName), &self.preset_name)
+                .on_input(Message::PresetNameInput)
+                .on_submit(Message::SavePreset)
+                .width(200.0),
+            button(self.t(Label::SavePreset))
+                .on_press_maybe((!name.is_empty()).then_some(Message::SavePreset)),
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
+        if self.presets.is_empty() {
+            return save.into();
+        }
+        let mut list = column![].spacing(5.0);
+        for (index, (name, preset)) in self.presets.iter().enumerate() {
+            list = list.push(
+                row![
+                    button(text(fill(
+                        self.t(Label::Preset),
+                        &[name, &self.tempo_text(preset.bpm), &preset.bar]
+                    )))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::LoadPreset(index)),
+                    button(self.t(Label::DeletePreset))
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::DeletePreset(index)),
+                ]
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        column![
+            save,
+            scrollable(list).height((self.presets.len() as f32 * 35.0).min(140.0))
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    /// The songs of the setlist in order, with controls to step through and edit them.
+    fn setlist_view(&self) -> Element<'_, Message> {
+        let name = self.song_name.trim();
+        let add = row![
+            text_input(self.t(Label::SongName), &self.song_name)
+                .on_input(Message::SongNameInput)
+                .on_submit(Message::AddSong)
+                .width(200.0),
+            button(self.t(Label::AddSong))
+                .on_press_maybe((!name.is_empty()).then_some(Message::AddSong)),
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center);
+        let songs = self.setlist.songs();
+        if songs.is_empty() {
+            return add.into();
+        }
+        let mut list = column![].spacing(5.0);
+        for (index, (name, song)) in songs.iter().enumerate() {
+            let current = self.setlist.current() == Some(index);
+            list = list.push(
+                row![
+                    button(text(fill(
+                        self.t(Label::Song),
+                        &[&(index + 1), name, &self.tempo_text(song.bpm), &song.bar]
+                    )))
+                    .style(if current {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Secondary
+                    })
+                    .on_press(Message::SelectSong(index)),
+                    button(self.t(Label::MoveUp))
+                        .style(iced::theme::Button::Text)
+                        .on_press_maybe((index > 0).then_some(Message::MoveSongUp(index))),
+                    button(self.t(Label::DeletePreset))
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::RemoveSong(index)),
+                ]
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        let steps = row![
+            button(self.t(Label::PrevSong)).on_press(Message::PrevSong),
+            button(self.t(Label::NextSong)).on_press(Message::NextSong),
+        ]
+        .spacing(10.0);
+        column![
+            add,
+            scrollable(list).height((songs.len() as f32 * 35.0).min(175.0)),
+            steps
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    /// The current settings with the tempo and meter of `preset`, which presets and songs
+    /// bring with them.
+    fn with_meter(&self, preset: &MetronomeSettings) -> MetronomeSettings {
+        MetronomeSettings {
+            bpm: preset.bpm,
+            bar: preset.bar,
+            beat_unit: preset.beat_unit,
+            beat_sounds: preset.beat_sounds.clone(),
+            groups: preset.groups.clone(),
+            subdivision: preset.subdivision,
+            ..self.settings()
+        }
+    }
+
+    /// Switches to `song`. Its tempo comes in like any other tempo change, so a song picked
+    /// while playing follows the tempo change setting instead of jumping mid-beat.
+    fn play_song(&mut self, song: Option<MetronomeSettings>) -> Command<Message> {
+        let Some(song) = song else {
+            return Command::none();
+        };
+        let settings = MetronomeSettings {
+            bpm: self.settings().bpm,
+            ..self.with_meter(&song)
+        };
+        let command = self.apply_settings(settings);
+        Command::batch([command, self.update(Message::BPMUpdate(song.bpm))])
+    }
+
+    fn save_setlist(&self) {
+        let _ = self.setlist_tx.send(self.setlist.songs().to_vec());
+    }
+
+    fn tempo_change_view(&self) -> Element<'_, Message> {
+        let mut timings = row![text(self.t(Label::TempoChange))]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center);
+        for (timing, label) in [
+            (TempoChange::Immediate, Label::TempoImmediate),
+            (TempoChange::NextBeat, Label::TempoNextBeat),
+            (TempoChange::NextBar, Label::TempoNextBar),
+        ] {
+            timings = timings.push(radio(
+                self.t(label),
+                timing,
+                Some(self.sequencer.tempo_change()),
+                Message::TempoChangeUpdate,
+            ));
+        }
+        timings.into()
+    }
+
+    fn glide_view(&self) -> Element<'_, Message> {
+        let mut curves = row![].spacing(10.0);
+        for (curve, label) in [
+            (GlideCurve::Straight, Label::GlideStraight),
+            (GlideCurve::Curved, Label::GlideCurved),
+        ] {
+            curves = curves.push(radio(
+                self.t(label),
+                curve,
+                Some(self.sequencer.glide_curve),
+                Message::GlideCurveUpdate,
+            ));
+        }
+        let toggle = button(if self.sequencer.gliding() {
+            self.t(Label::CancelGlide)
+        } else {
+            self.t(Label::Glide)
+        })
+        .on_press_maybe(
+            self.sequencer
+                .state()
+                .is_running()
+                .then_some(Message::ToggleGlide),
+        );
+        column![
+            row![
+                text(fill(
+                    self.t(Label::GlideTo),
+                    &[&self.sequencer.glide_target]
+                )),
+                slider(
+                    30..=300,
+                    self.sequencer.glide_target,
+                    Message::GlideTargetUpdate
+                )
+                .width(150.0),
+            ]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center),
+            row![
+                text(fill(
+                    self.t(Label::GlideBars),
+                    &[&self.sequencer.glide_bars]
+                )),
+                slider(1..=32, self.sequencer.glide_bars, Message::GlideBarsUpdate).width(100.0),
+                curves,
+                toggle,
+            ]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center),
+        ]
+        .spacing(5.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    /// Switches the player to `device`, or to the default output for `None`.
+    fn select_output_device(&mut self, device: Option<String>) {
+        self.output_fallback = false;
+        self.output_error = None;
+        self.send_player(PlayerCommand::Device(device.clone()));
+        self.output_device = device;
+    }
+
+    fn beat_sound(&self, index: u32) -> BeatSound {
+        self.sequencer
+            .beat_sounds
+            .get(index as usize)
+            .copied()
+            .unwrap_or(BeatSound::Normal)
+    }
+
+    /// Has the player play `beat` straight away, unless only the visuals are wanted.
+    fn play(&mut self, beat: Beat) {
+        if !self.visual_only {
+            self.send_player(PlayerCommand::Play(beat));
+        }
+    }
+
+    /// Moves the display on to main beat `index` of the bar, which was due `at`, and counts
+    /// it towards the session unless it's part of the count-in.
+    fn show_beat(&mut self, index: u32, counting_in: bool, at: Instant) -> Command<Message> {
+        if !counting_in {
+            let (bpm, interval) = {
+                let sequencer = self.engine.sequencer();
+                (sequencer.bpm(), sequencer.interval())
+            };
+            if index == 0 {
+                self.bars_elapsed += 1;
+            }
+            self.beats_elapsed += 1;
+            self.tempo_average.add(bpm, interval);
+            self.tempo_range = Some(widen(self.tempo_range, bpm));
+            let record = widen(self.tempo_record, bpm);
+            if self.tempo_record != Some(record) {
+                self.tempo_record = Some(record);
+                let _ = self.record_tx.send(record);
+            }
+        }
+        // Before the first beat of a start there's no beat to show, whatever state playback
+        // was set going from
+        self.previous_beat = self.played_beat.filter(|_| self.beats_played > 0);
+        self.played_beat = Some(index);
+        self.beats_played += 1;
+        self.last_beat = Some(at + self.latency_offset());
+        self.now = Instant::now();
+        if index == 0 && self.attention_on_downbeat && !self.focused {
+            window::request_user_attention(Some(UserAttention::Informational))
+        } else {
+            Command::none()
+        }
+    }
+
+    /// Hands `command` to the player thread, starting a new one first if it has died so a
+    /// failure there costs a few beats rather than the window.
+    fn send_player(&mut self, command: PlayerCommand) {
+        match &command {
+            PlayerCommand::FileClick(role, click) => self.file_clicks[role.index()] = click.clone(),
+            PlayerCommand::Messages(messages) => self.messages = Some(messages.clone()),
+            _ => {}
+        }
+        if let Err(SendError(command)) = self.player_thread.send(command) {
+            self.restart_player();
+            if self.player_thread.send(command).is_err() {
+                self.output_lost = Some(self.t(Label::PlayerStopped).to_string());
+            }
+        }
+    }
+
+    /// Starts a new player thread in the state the old one was left in.
+    fn restart_player(&mut self) {
+        // The old thread may have died holding the lock
+        self.audio_info = Arc::new(Mutex::new(None));
+        (self.player_thread, self.max_click_tx, self.sound_map_tx) = spawn_player(
+            self.sounds.clone(),
+            max_click(self.truncate_clicks, self.max_click_ms),
+            self.sound_map,
+            Arc::clone(&self.audio_info),
+        );
+        self.player_restarts += 1;
+        let device = self.output_device.clone().filter(|_| !self.output_fallback);
+        // The engine forgets the old thread at the first beat it can't hand it
+        self.engine.add_listener(self.player_thread.clone());
+        let mut commands = vec![
+            PlayerCommand::Device(device),
+            PlayerCommand::SampleAccurate(self.sample_accurate),
+            PlayerCommand::VisualOnly(self.visual_only),
+        ];
+        for role in Role::ALL {
+            let click = self.file_clicks[role.index()].clone();
+            commands.push(PlayerCommand::FileClick(role, click));
+        }
+        commands.extend(self.messages.clone().map(PlayerCommand::Messages));
+        for command in commands {
+            let _ = self.player_thread.send(command);
+        }
+    }
+
+    fn ramp_view(&self) -> Element<'_, Message> {
+        column![
+            row![
+                checkbox(
+                    fill(self.t(Label::RampFrom), &[&self.sequencer.ramp_start]),
+                    self.sequencer.ramp_trainer(),
+                    Message::RampToggle
+                ),
+                slider(
+                    30..=300,
+                    self.sequencer.ramp_start,
+                    Message::RampStartUpdate
+                )
+                .width(100.0),
+                text(fill(self.t(Label::RampTo), &[&self.sequencer.ramp_end])),
+                slider(30..=300, self.sequencer.ramp_end, Message::RampEndUpdate).width(100.0),
+            ]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center),
+            row![
+                text(fill(
+                    self.t(Label::RampStep),
+                    &[&self.sequencer.ramp_step()]
+                )),
+                slider(1..=20, self.sequencer.ramp_step(), Message::RampStepUpdate).width(100.0),
+                text(fill(
+                    self.t(Label::RampEvery),
+                    &[&self.sequencer.ramp_every_bars()]
+                )),
+                slider(
+                    1..=16,
+                    self.sequencer.ramp_every_bars(),
+                    Message::RampEveryUpdate
+                )
+                .width(100.0),
+                checkbox(
+                    self.t(Label::RampLoop),
+                    self.sequencer.ramp_loop,
+                    Message::RampLoop
+                ),
+            ]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center),
+        ]
+        .spacing(5.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn drill_view(&self) -> Element<'_, Message> {
+        row![
+            checkbox(
+                fill(self.t(Label::DrillFrom), &[&self.sequencer.drill_min]),
+                self.sequencer.drill(),
+                Message::DrillToggle
+            ),
+            slider(30..=300, self.sequencer.drill_min, Message::DrillMinUpdate).width(80.0),
+            text(fill(self.t(Label::RampTo), &[&self.sequencer.drill_max])),
+            slider(30..=300, self.sequencer.drill_max, Message::DrillMaxUpdate).width(80.0),
+            text(fill(
+                self.t(Label::RampEvery),
+                &[&self.sequencer.drill_every_bars()]
+            )),
+            slider(
+                1..=16,
+                self.sequencer.drill_every_bars(),
+                Message::DrillEveryUpdate
+            )
+            .width(80.0),
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn tempo_decimals_view(&self) -> Element<'_, Message> {
+        let mut decimals = row![text(self.t(Label::TempoDecimals))]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center);
+        for count in 0..=2 {
+            decimals = decimals.push(radio(
+                count.to_string(),
+                count,
+                Some(self.tempo_decimals),
+                Message::TempoDecimals,
+            ));
+        }
+        decimals.into()
+    }
+
+    fn visual_style_view(&self) -> Element<'_, Message> {
+        let mut styles = row![text(self.t(Label::VisualStyle))]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center);
+        for style in VisualStyle::ALL {
+            styles = styles.push(radio(
+                self.t(style.label()),
+                style,
+                Some(self.visual_style),
+                Message::VisualStyleChanged,
+            ));
+        }
+        styles.into()
+    }
+
+    /// A row of swatches for each of the lit and idle beat colors.
+    fn beat_colors_view(&self) -> Element<'_, Message> {
+        let swatches =
+            |label: Label, picked: Option<Color>, on_pick: fn(Option<Color>) -> Message| {
+                let style = |selected: bool| {
+                    if selected {
+                        iced::theme::Button::Primary
+                    } else {
+                        iced::theme::Button::Text
+                    }
+                };
+                let mut swatches = row![
+                    text(self.t(label)),
+                    button(self.t(Label::ThemeColor))
+                        .style(style(picked.is_none()))
+                        .on_press(on_pick(None)),
+                ]
+                .spacing(5.0)
+                .align_items(iced::Alignment::Center);
+                for color in BEAT_COLORS.map(|hex| iced::color!(hex)) {
+                    swatches = swatches.push(
+                        button(circle(16.0, color))
+                            .padding(4.0)
+                            .style(style(picked == Some(color)))
+                            .on_press(on_pick(Some(color))),
+                    );
+                }
+                swatches
+            };
+        row![
+            swatches(
+                Label::BeatColor,
+                self.active_color,
+                Message::ActiveColorChanged
+            ),
+            swatches(Label::IdleColor, self.idle_color, Message::IdleColorChanged),
+        ]
+        .spacing(20.0)
+        .align_items(iced::Alignment::Center)
+        .into()
+    }
+
+    fn latency_view(&self) -> Element<'_, Message> {
+        let calibrate = button(if self.calibrating {
+            self.t(Label::Listening)
+        } else {
+            self.t(Label::Calibrate)
+        })
+        .on_press_maybe(
+            (!self.calibrating && self.sounds.is_some() && !self.sequencer.state().is_running())
+                .then_some(Message::CalibrateLatency),
+        );
+        let mut latency = column![row![
+            text(fill(
+                self.t(Label::LatencyOffset),
+                &[&self.latency_offset_ms]
+            )),
+            slider(
+                0..=1000,
+                self.latency_offset_ms,
+                Message::LatencyOffsetUpdate
+            )
+            .width(150.0),
+            calibrate,
+            checkbox(
+                self.t(Label::SampleAccurate),
+                self.sample_accurate,
+                Message::SampleAccurate
+            ),
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)]
+        .spacing(5.0)
+        .align_items(iced::Alignment::Center);
+        match &self.calibration {
+            Some(Ok(measured)) => {
+                latency = latency.push(
+                    row![
+                        text(fill(
+                            self.t(Label::MeasuredLatency),
+                            &[&measured.as_millis()]
+                        )),
+                        button(self.t(Label::Accept)).on_press(Message::AcceptLatency)
+                    ]
+                    .spacing(10.0)
+                    .align_items(iced::Alignment::Center),
+                )
+            }
+            Some(Err(err)) => latency = latency.push(text(err.text(self.language))),
+            None => {}
+        }
+        latency.into()
+    }
+
+    /// Time spent playing this session, up to now.
+    fn practiced(&self) -> Duration {
+        self.practice_time
+            + self
+                .playing_since
+                .map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    fn latency_offset(&self) -> Duration {
+        let offset = Duration::from_millis(self.latency_offset_ms as u64);
+        if self.sample_accurate {
+            offset + click_track::LOOKAHEAD
+        } else {
+            offset
+        }
+    }
+
+    /// The beat the display is on, which trails the clicks sent by the latency offset.
+    fn shown_beat(&self) -> Option<u32> {
+        match self.last_beat {
+            Some(last_beat) if self.now < last_beat && self.sequencer.state().is_running() => {
+                self.previous_beat
+            }
+            _ => self.sequencer.state().index(),
+        }
+    }
+
+    /// Whether the pendulum swings left to right through the shown beat. It turns back on
+    /// every beat, across bar lines too, so it only goes by how many have been played.
+    fn swings_right(&self) -> bool {
+        let trailing = matches!(self.last_beat, Some(last_beat) if self.now < last_beat);
+        self.beats_played.saturating_sub(trailing as u64) % 2 == 1
+    }
+
+    /// The beat counter's text: the shown beat out of the bar, or the beats left while
+    /// counting in. `None` while stopped.
+    fn beat_count(&self) -> Option<String> {
+        match self.sequencer.state().countdown(self.sequencer.bar()) {
+            Some(left) => Some(left.to_string()),
+            None if *self.sequencer.state() == MetroState::Stopped => None,
+            None => Some(format!(
+                "{} / {}",
+                self.shown_beat()? + 1,
+                self.sequencer.bar()
+            )),
+        }
+    }
+
+    /// How brightly the stage flash lights the window, fading from `1.0` on the beat to `0.0`
+    /// over `FLASH_DECAY`, or `None` when it's off.
+    fn flash_strength(&self) -> Option<f32> {
+        if !self.stage_flash || !self.sequencer.state().is_running() {
+            return None;
+        }
+        // Until the latest beat is shown the one before it has long faded
+        let elapsed = match self.last_beat {
+            Some(last_beat) => self.now.checked_duration_since(last_beat)?,
+            None => return Some(0.0),
+        };
+        Some((1.0 - elapsed.as_secs_f32() / FLASH_DECAY.as_secs_f32()).max(0.0))
+    }
+
+    /// How far through the current bar we are, from `0.0` to `1.0`.
+    fn bar_phase(&self) -> f32 {
+        let beat = self.shown_beat().unwrap_or(0);
+        (beat as f32 + self.beat_phase()) / self.sequencer.bar() as f32
+    }
+
+    /// How far through the current main beat we are, from `0.0` to `1.0`.
+    fn beat_phase(&self) -> f32 {
+        match self.last_beat {
+            Some(last_beat) if self.sequencer.state().is_running() => {
+                let interval = self.sequencer.interval().as_secs_f64();
+                // Before the latest beat is shown, the one before it is still finishing
+                let elapsed = match self.now.checked_duration_since(last_beat) {
+                    Some(elapsed) => elapsed.as_secs_f64(),
+                    None => interval - (last_beat - self.now).as_secs_f64(),
+                };
+                (elapsed / interval).clamp(0.0, 1.0) as f32
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// The message a key bound to `action` sends.
+    fn action_message(&self, action: Action) -> Message {
+        let bpm = self.sequencer.chosen_bpm();
+        match action {
+            Action::Toggle => Message::Toggle,
+            Action::Pause => Message::Pause,
+            Action::Silence => Message::PanicStop,
+            Action::Tap => Message::Tap,
+            Action::Faster => Message::BPMUpdate((bpm + 1.).min(300.)),
+            Action::Slower => Message::BPMUpdate((bpm - 1.).max(30.)),
+            Action::MuchFaster => Message::BPMUpdate((bpm + 10.).min(300.)),
+            Action::MuchSlower => Message::BPMUpdate((bpm - 10.).max(30.)),
+            Action::RecallA => Message::RecallTempoSlot(0),
+            Action::RecallB => Message::RecallTempoSlot(1),
+            Action::SwapTempoSlots => Message::SwapTempoSlots,
+            Action::Undo => Message::Undo,
+            Action::Redo => Message::Redo,
+            Action::NextSong => Message::NextSong,
+            Action::PrevSong => Message::PrevSong,
+            Action::Mute => Message::ToggleMute,
+            Action::HalfTime => Message::TempoMultiply(0.5),
+            Action::DoubleTime => Message::TempoMultiply(2.),
+        }
+    }
+
+    /// Every binding, with a button to change each.
+    fn keys_view(&self) -> Element<'_, Message> {
+        let mut actions = column![text(self.t(Label::KeyboardShortcuts)).size(48)]
+            .spacing(8.0)
+            .align_items(iced::Alignment::Center);
+        for action in Action::ALL {
+            let name = match action {
+                Action::Toggle => String::from(self.t(Label::ActionToggle)),
+                Action::Pause => String::from(self.t(Label::Pause)),
+                Action::Silence => String::from(self.t(Label::Silence)),
+                Action::Tap => String::from(self.t(Label::Tap)),
+                Action::Faster => fill(self.t(Label::ActionFaster), &[&1]),
+                Action::Slower => fill(self.t(Label::ActionSlower), &[&1]),
+                Action::MuchFaster => fill(self.t(Label::ActionFaster), &[&10]),
+                Action::MuchSlower => fill(self.t(Label::ActionSlower), &[&10]),
+                Action::RecallA => fill(self.t(Label::ActionRecall), &[&"A"]),
+                Action::RecallB => fill(self.t(Label::ActionRecall), &[&"B"]),
+                Action::SwapTempoSlots => String::from(self.t(Label::SwapTempoSlots)),
+                Action::Undo => String::from(self.t(Label::Undo)),
+                Action::Redo => String::from(self.t(Label::Redo)),
+                Action::NextSong => String::from(self.t(Label::NextSong)),
+                Action::PrevSong => String::from(self.t(Label::PrevSong)),
+                Action::Mute => String::from(self.t(Label::Mute)),
+                Action::HalfTime => String::from(self.t(Label::HalfTime)),
+                Action::DoubleTime => String::from(self.t(Label::DoubleTime)),
+            };
+            let keys = if self.rebinding == Some(action) {
+                String::from(self.t(Label::PressKey))
+            } else {
+                let keys = self
+                    .key_map
+                    .bindings(action)
+                    .map(|binding| binding.to_string())
+                    .collect::<Vec<_>>();
+                if keys.is_empty() {
+                    String::from("—")
+                } else {
+                    keys.join(", ")
+                }
+            };
+            actions = actions.push(
+                row![
+                    text(name).width(160.0),
+                    text(keys).width(180.0),
+                    button(self.t(Label::ChangeKey))
+                        .style(iced::theme::Button::Text)
+                        .on_press(Message::Rebind(action)),
+                ]
+                .spacing(10.0)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        container(
+            actions
+                .push(text(self.t(Label::KeysHint)).size(14))
+                .push(button(self.t(Label::Back)).on_press(Message::ToggleKeys)),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into()
+    }
+
+    fn sounds_view(&self) -> Element<'_, Message> {
+        let mut packs = row![text(self.t(Label::SoundPack))]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center);
+        for pack in SoundPack::ALL {
+            packs = packs.push(radio(
+                self.t(pack.label()),
+                pack,
+                Some(self.sound_map.pack),
+                Message::SoundPackChanged,
+            ));
+        }
+        let default_device = DeviceChoice {
+            name: None,
+            label: String::from(self.t(Label::DefaultOutputDevice)),
+        };
+        let choice = |name: &String| DeviceChoice {
+            name: Some(name.clone()),
+            label: name.clone(),
+        };
+        let mut choices = vec![default_device.clone()];
+        choices.extend(self.output_devices.iter().map(choice));
+        // A chosen device that's unplugged stays listed, so it's clear what was picked
+        let selected = self.output_device.as_ref().map_or(default_device, choice);
+        if !choices.contains(&selected) {
+            choices.push(selected.clone());
+        }
+        let mut device = column![row![
+            text(self.t(Label::OutputDevice)),
+            pick_list(choices, Some(selected), Message::OutputDeviceSelected),
+        ]
+        .spacing(10.0)
+        .align_items(iced::Alignment::Center)]
+        .spacing(5.0)
+        .align_items(iced::Alignment::Center);
+        if let (true, Some(name)) = (self.output_fallback, &self.output_device) {
+            device = device.push(text(fill(self.t(Label::OutputDeviceMissing), &[name])).size(14));
+        }
+        if let Some(err) = &self.output_error {
+            device = device.push(text(err).size(14));
+        }
+        let mut roles = column![text(self.t(Label::Sounds)).size(72), device, packs]
+            .spacing(20.0)
+            .align_items(iced::Alignment::Center);
+        for role in Role::ALL {
+            let sound = self.sound_map.get(role);
+            let mut file = row![
+                text_input(self.t(Label::SampleFile), &self.sample_inputs[role.index()])
+                    .on_input(move |path| Message::SampleFileInput(role, path))
+                    .on_submit(Message::LoadSampleFile(role))
+                    .width(450.0),
+                button(self.t(Label::LoadSampleFile)).on_press(Message::LoadSampleFile(role)),
+            ]
+            .spacing(10.0)
+            .align_items(iced::Alignment::Center);
+            if let Some(err) = &self.sample_errors[role.index()] {
+                file = file.push(text(err).size(14));
+            }
+            roles = roles.push(
+                column![
+                    row![
+                        text(self.t(match role {
+                            Role::Downbeat => Label::RoleDownbeat,
+                            Role::Accent => Label::RoleAccent,
+                            Role::Beat => Label::RoleBeat,
+                            Role::OffBeat => Label::RoleOffBeat,
+                            Role::Subdivision => Label::RoleSubdivision,
+                            Role::End => Label::RoleEnd,
+                        }))
+                        .width(100.0),
+                        pick_list(&Sample::ALL[..], Some(sound.sample), move |sample| {
+                            Message::SampleChanged(role, sample)
+                        }),
+                        button(self.t(Label::PreviewSound)).on_press_maybe(
+                            self.sounds.is_some().then_some(Message::PreviewSound(role))
+                        ),
+                        text(fill(
+                            self.t(Label::SoundVolume),
+                            &[&(sound.volume * 100.).round()]
+                        ))
+                        .width(110.0),
+                        slider(0.0..=2.0, sound.volume, move |volume| {
+                            Message::RoleVolume(role, volume)
+                        })
+                        .step(0.01)
+                        .width(100.0),
+                        text(fill(
+                            self.t(Label::SoundPitch),
+                            &[&(sound.pitch * 100.).round()]
+                        ))
+                        .width(110.0),
+                        slider(0.5..=2.0, sound.pitch, move |pitch| {
+                            Message::RolePitch(role, pitch)
+                        })
+                        .step(0.01)
+                        .width(100.0),
+                    ]
+                    .spacing(10.0)
+                    .align_items(iced::Alignment::Center),
+                    file,
+                ]
+                .spacing(5.0)
+                .align_items(iced::Alignment::Center),
+            );
+        }
+        container(roles.push(button(self.t(Label::Back)).on_press(Message::ToggleSounds)))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .center_x()
+            .center_y()
+            .into()
+    }
+
+    fn about_view(&self) -> Element<'_, Message> {
+        let audio = match &*self.audio_info.lock().unwrap() {
+            Some(info) => format!(
+                "{}\n{}",
+                fill(self.t(Label::AudioDevice), &[&info.device]),
+                fill(
+                    self.t(Label::AudioFormat),
+                    &[&info.sample_rate, &info.channels, &info.sample_format]
+                )
+            ),
+            None => String::from(self.t(Label::AudioUnavailable)),
+        };
+        container(
+            column![
+                text(self.t(Label::Title)).size(72),
+                text(fill(self.t(Label::Version), &[&env!("CARGO_PKG_VERSION")])).size(24),
+                text(audio),
+                text(fill(
+                    self.t(Label::BuiltWith),
+                    &[&env!("ICED_VERSION"), &env!("RODIO_VERSION")]
+                )),
+                button(self.t(Label::Back)).on_press(Message::ToggleAbout),
+            ]
+            .spacing(20.0)
+            .align_items(iced::Alignment::Center),
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .into()
+    }
+
+    /// Stops asking for attention once the metronome stops.
+    fn dismiss_attention(&self) -> Command<Message> {
+        if self.attention_on_downbeat {
+            window::request_user_attention(None)
+        } else {
+            Command::none()
+        }
+    }
+
+    /// Holds a sleep inhibitor exactly while playing with the option enabled.
+    fn update_sleep_inhibitor(&mut self) {
+        let playing = self.engine.sequencer().state().is_running();
+        if self.inhibit_sleep && playing {
+            if self.sleep_inhibitor.is_none() {
+                self.sleep_inhibitor = SleepInhibitor::acquire();
+            }
+        } else {
+            self.sleep_inhibitor = None;
+        }
+    }
+}
+
+/// A bright, short ping for the polyrhythm's second voice, set apart from every sample.
+fn poly_source() -> impl Source<Item = f32> + Send {
+    SineWave::new(1760.0)
+        .take_duration(Duration::from_millis(25))
+        .amplify(0.3)
+}
+
+/// The output device and stream format the player is using, for the About panel.
+struct AudioInfo {
+    device: String,
+    sample_rate: u32,
+    channels: u16,
+    sample_format: String,
+}
+
+impl AudioInfo {
+    /// Describes `device`, as the player opens it.
+    fn query(device: &rodio::cpal::Device) -> Option<Self> {
+        let config = device.default_output_config().ok()?;
+        Some(Self {
+            device: device.name().unwrap_or_else(|_| String::from("Unknown")),
+            sample_rate: config.sample_rate().0,
+            channels: config.channels(),
+            sample_format: format!("{:?}", config.sample_format()),
+        })
+    }
+}
+
+/// An entry of the output device list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DeviceChoice {
+    /// `None` for the system default.
+    name: Option<String>,
+    label: String,
+}
+
+impl Display for DeviceChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.label)
+    }
+}
+
+fn load_sample_file(role: Role, path: String) -> Command<Message> {
+    Command::perform(sounds::load_file(path.clone()), move |result| {
+        Message::SampleFileLoaded(role, path, result)
+    })
+}
+
+/// What the player thread is asked to do.
+enum PlayerCommand {
+    /// Play the engine's beat, in time with the tick it was due on.
+    Beat(BeatEvent),
+    /// Play this straight away, for sounds that aren't the engine's beats.
+    Play(Beat),
+    /// Play this instead of the role's embedded sample; `None` goes back to the sample.
+    FileClick(Role, Option<Click>),
+    /// Where to send what happens to the output.
+    Messages(async_mpsc::Sender<Message>),
+    /// Play on the output device with this name, or the default one for `None`.
+    Device(Option<String>),
+    /// Mix the clicks into one continuous [`ClickTrack`] instead of starting each on its own.
+    SampleAccurate(bool),
+    /// Leave the engine's beats unplayed, for when only the visuals are wanted.
+    VisualOnly(bool),
+}
+
+impl From<BeatEvent> for PlayerCommand {
+    fn from(event: BeatEvent) -> Self {
+        PlayerCommand::Beat(event)
+    }
+}
+
+/// Starts a thread passing the engine's main `beats` on to the UI as messages, for showing
+/// them, returning where to send it the UI's message channel, which is only there once the
+/// UI has subscribed. The player hears the beats from the engine itself, so a UI that falls
+/// behind only shows them late.
+fn spawn_beat_relay(beats: Receiver<BeatEvent>) -> Sender<async_mpsc::Sender<Message>> {
+    let (tx, rx) = mpsc::channel::<async_mpsc::Sender<Message>>();
+    std::thread::spawn(move || {
+        let mut messages = None;
+        for event in beats
+            .iter()
+            .filter(|event| matches!(event.tick, Tick::Beat(_)))
+        {
+            if let Some(latest) = rx.try_iter().last() {
+                messages = Some(latest);
+            }
+            if let Some(messages) = &mut messages {
+                // Waits for room rather than losing a beat, which would throw the count off
+                let _ = block_on(messages.send(Message::Beat(event)));
+            }
+        }
+    });
+    tx
+}
+
+/// Starts a player thread, returning where to send its commands, click lengths and sound
+/// map.
+fn spawn_player(
+    sounds: Option<Arc<SoundBank>>,
+    max_click: Option<Duration>,
+    sound_map: SoundMap,
+    audio_info: Arc<Mutex<Option<AudioInfo>>>,
+) -> (
+    Sender<PlayerCommand>,
+    Sender<Option<Duration>>,
+    Sender<SoundMap>,
+) {
+    let (tx, rx) = mpsc::channel();
+    let (max_click_tx, max_click_rx) = mpsc::channel();
+    let (sound_map_tx, sound_map_rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        player_thread(
+            rx,
+            sounds,
+            max_click,
+            max_click_rx,
+            sound_map,
+            sound_map_rx,
+            audio_info,
+        )
+    });
+    (tx, max_click_tx, sound_map_tx)
+}
+
+/// Plays the engine's beats as they come due, and whatever else the UI asks for.
+fn player_thread(
+    rx: Receiver<PlayerCommand>,
+    sounds: Option<Arc<SoundBank>>,
+    max_click: Option<Duration>,
+    max_click_rx: Receiver<Option<Duration>>,
+    sound_map: SoundMap,
+    sound_map_rx: Receiver<SoundMap>,
+    audio_info: Arc<Mutex<Option<AudioInfo>>>,
+) {
+    // What the UI should hear about the output, kept until it's listening
+    let mut unreported = Vec::new();
+    // Opened once the UI says which device to use. Without sounds or an output the beats
+    // are dropped, so the UI carries on silently
+    let mut output = None;
+    let mut max_click = max_click;
+    let mut sound_map = sound_map;
+    let mut file_clicks: [Option<Click>; 6] = Default::default();
+    let mut messages = None;
+    // Where to send clicks while they're mixed into a click track
+    let mut sample_accurate = false;
+    let mut track = None;
+    let mut visual_only = false;
+    loop {
+        let (beat, tick) = match rx.recv() {
+            Ok(PlayerCommand::Beat(event)) => match event.beat.filter(|_| !visual_only) {
+                Some(beat) => (beat, Some(event.at)),
+                None => continue,
+            },
+            Ok(PlayerCommand::Play(beat)) => (beat, None),
+            Ok(PlayerCommand::VisualOnly(enabled)) => {
+                visual_only = enabled;
+                continue;
+            }
+            Ok(PlayerCommand::FileClick(role, click)) => {
+                file_clicks[role.index()] = click;
+                continue;
+            }
+            Ok(PlayerCommand::Messages(sender)) => {
+                messages = Some(sender);
+                report(&mut messages, &mut unreported);
+                continue;
+            }
+            Ok(PlayerCommand::Device(device)) => {
+                // Let go of the old stream first, in case it's the device being reopened
+                track = None;
+                output = None;
+                if sounds.is_some() {
+                    output = match connect(device.as_deref(), &audio_info, &mut unreported) {
+                        Ok(opened) => {
+                            unreported.push(Message::AudioConnected);
+                            Some(opened)
+                        }
+                        Err(err) => {
+                            unreported.push(Message::AudioError(err));
+                            None
+                        }
+                    };
+                }
+                if let Some((_, stream_handle)) = output.as_ref().filter(|_| sample_accurate) {
+                    track = start_track(stream_handle, &audio_info, &mut unreported);
+                }
+                report(&mut messages, &mut unreported);
+                continue;
+            }
+            Ok(PlayerCommand::SampleAccurate(enabled)) => {
+                sample_accurate = enabled;
+                // Dropping the sender lets the track end once it has rung out
+                track = None;
+                if let Some((_, stream_handle)) = output.as_ref().filter(|_| enabled) {
+                    track = start_track(stream_handle, &audio_info, &mut unreported);
+                }
+                report(&mut messages, &mut unreported);
+                continue;
+            }
+            Err(_) => return,
+        };
+        let (Some((_, stream_handle)), Some(sounds)) = (&output, &sounds) else {
+            continue;
+        };
+        while let Ok(new_max) = max_click_rx.try_recv() {
+            max_click = new_max;
+        }
+        while let Ok(new_map) = sound_map_rx.try_recv() {
+            sound_map = new_map;
+        }
+        let click = |role: Role, gain: f32| -> Box<dyn Source<Item = f32> + Send> {
+            let sound = sound_map.get(role);
+            let click = file_clicks[role.index()]
+                .clone()
+                .unwrap_or_else(|| sounds.get(sound_map.id(role)))
+                .speed(sound.pitch)
+                .amplify(sound.volume * gain);
+            // Samples shorter than the limit are unaffected
+            match max_click {
+                Some(max) => Box::new(click.take_duration(max)),
+                None => Box::new(click),
+            }
+        };
+        let sound = match beat {
+            Beat::Beat => click(Role::Beat, 1.0),
+            Beat::FirstBeat => click(Role::Downbeat, 1.0),
+            Beat::MediumAccent => click(Role::Accent, 1.0),
+            Beat::OffBeat => click(Role::OffBeat, 1.0),
+            Beat::SoftOffBeat => click(Role::OffBeat, SOFT_OFF_BEAT),
+            Beat::Subdivision => click(Role::Subdivision, 1.0),
+            Beat::PolyBeat => Box::new(poly_source()),
+            Beat::Cue(cue) => {
+                let (first, second) = match cue {
+                    Cue::Start => (1.0, CUE_INTERVAL),
+                    Cue::Stop => (CUE_INTERVAL, 1.0),
+                };
+                let second = click(Role::End, 1.0).speed(second).delay(CUE_GAP);
+                Box::new(click(Role::End, 1.0).speed(first).mix(second))
+            }
+        };
+        let lost = match &track {
+            Some(track) => {
+                // A click for a tick belongs at that tick, however late it was handed over;
+                // any other click, like a cue, is played as soon as it can be
+                let at = tick
+                    .filter(|tick| tick.elapsed() < click_track::LOOKAHEAD)
+                    .unwrap_or_else(Instant::now);
+                track
+                    .send((at, Box::new(controlled(sound))))
+                    .err()
+                    .map(|_| Message::ClickTrackStopped)
+            }
+            None => play_now(stream_handle, sound)
+                .err()
+                .map(|err| Message::OutputLost(err.to_string())),
+        };
+        // The device went away under the stream. The beats carry on silently until the UI
+        // asks for the output again
+        if let Some(lost) = lost {
+            track = None;
+            output = None;
+            *audio_info.lock().unwrap() = None;
+            unreported.push(lost);
+            report(&mut messages, &mut unreported);
+        }
+    }
+}
+
+/// Opens the output device called `name` for the player, or the default one for `None` or
+/// if that fails, leaving why the choice couldn't be used in `unreported`. Fails if there
+/// is no output to open at all.
+fn connect(
+    name: Option<&str>,
+    audio_info: &Mutex<Option<AudioInfo>>,
+    unreported: &mut Vec<Message>,
+) -> Result<(OutputStream, OutputStreamHandle), String> {
+    let mut opened = output::open(name);
+    if let (Err(err), Some(_)) = (&opened, name) {
+        unreported.push(Message::OutputDeviceFailed(err.clone()));
+        opened = output::open(None);
+    }
+    match opened {
+        Ok((stream, stream_handle, device)) => {
+            *audio_info.lock().unwrap() = AudioInfo::query(&device);
+            Ok((stream, stream_handle))
+        }
+        Err(err) => {
+            *audio_info.lock().unwrap() = None;
+            Err(err)
+        }
+    }
+}
+
+/// Hands what happened to the output to the UI once there's somewhere to send it.
+fn report(messages: &mut Option<async_mpsc::Sender<Message>>, unreported: &mut Vec<Message>) {
+    if let Some(messages) = messages {
+        for message in unreported.drain(..) {
+            let _ = messages.try_send(message);
+        }
+    }
+}
+
+/// Plays `sound` on the output at the current volume, cutting it off if a panic-stop happens
+/// while it rings.
+fn play_now(
+    stream_handle: &OutputStreamHandle,
+    sound: impl Source<Item = f32> + Send + 'static,
+) -> Result<(), PlayError> {
+    stream_handle.play_raw(controlled(sound))
+}
+
+/// `sound` at the current volume, cut off if a panic-stop happens while it rings.
+fn controlled(
+    sound: impl Source<Item = f32> + Send + 'static,
+) -> impl Source<Item = f32> + Send + 'static {
+    let generation = SILENCE_GENERATION.load(Ordering::Acquire);
+    volume::smoothed(sound)
+        .stoppable()
+        .periodic_access(Duration::from_millis(1), move |source| {
+            if SILENCE_GENERATION.load(Ordering::Acquire) != generation {
+                source.stop();
+            }
+        })
+}
+
+/// Starts a click track on the output, in the device's own format where it's known,
+/// returning where to send it clicks. Leaves why it couldn't in `unreported`.
+fn start_track(
+    stream_handle: &OutputStreamHandle,
+    audio_info: &Mutex<Option<AudioInfo>>,
+    unreported: &mut Vec<Message>,
+) -> Option<Sender<(Instant, click_track::Sound)>> {
+    let (sample_rate, channels) = match &*audio_info.lock().unwrap() {
+        Some(info) => (info.sample_rate, info.channels),
+        None => (48_000, 2),
+    };
+    let (tx, rx) = mpsc::channel();
+    match stream_handle.play_raw(ClickTrack::new(rx, sample_rate, channels, Instant::now())) {
+        Ok(()) => Some(tx),
+        Err(err) => {
+            unreported.push(Message::ClickTrackFailed(err.to_string()));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tap_tempo_averages_the_intervals() {
+        let start = Instant::now();
+        let taps = |intervals_ms: &[u64]| {
+            let mut at = start;
+            let mut taps = vec![at];
+            for &ms in intervals_ms {
+                at += Duration::from_millis(ms);
+                taps.push(at);
+            }
+            taps
+        };
+        assert_eq!(tap_bpm(&taps(&[])), None);
+        assert_eq!(tap_bpm(&taps(&[500])), Some(120));
+        assert_eq!(tap_bpm(&taps(&[480, 520, 500])), Some(120));
+        // Out of range tapping is clamped
+        assert_eq!(tap_bpm(&taps(&[100, 100])), Some(300));
+        assert_eq!(tap_bpm(&taps(&[1900])), Some(32));
+        assert_eq!(tap_spread(&taps(&[500])), None);
+        assert_eq!(tap_spread(&taps(&[500, 500, 500])), Some(Duration::ZERO));
+        assert_eq!(
+            tap_spread(&taps(&[480, 520])),
+            Some(Duration::from_millis(20))
+        );
+    }
+
+    #[test]
+    fn tap_steadiness_buckets_each_tap_by_its_deviation() {
+        let start = Instant::now();
+        let taps = [0, 500, 1000, 1540].map(|ms| start + Duration::from_millis(ms));
+        assert_eq!(tap_deviation(&taps[..2]), None);
+        assert_eq!(tap_deviation(&taps[..3]), Some(0.));
+        let late = tap_deviation(&taps).unwrap();
+        assert!((late - (0.54 - 1.54 / 3.)).abs() < 1e-9);
+        // Early on the left, late on the right, and the far ones in the outermost buckets
+        assert_eq!(
+            steadiness_buckets(&[0., 0.004, -0.012, late, 0.5, -0.5]),
+            [1, 0, 0, 1, 2, 0, 0, 1, 1]
+        );
+    }
+
+    #[test]
+    fn average_tempo_weighs_by_time_played() {
+        let mut average = TempoAverage::default();
+        assert_eq!(average.bpm(), None);
+        average.add(60., Duration::from_secs(30));
+        average.add(120., Duration::from_secs(10));
+        assert_eq!(average.bpm(), Some(75.));
+    }
+
+    #[test]
+    fn embedded_sounds_decode() {
+        let sounds = SoundBank::load().unwrap();
+        for pack in SoundPack::ALL {
+            for sample in Sample::ALL {
+                assert!(sounds.get(sounds::SoundId { pack, sample }).count() > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn session_time_reads_like_a_clock() {
+        assert_eq!(clock_time(Duration::from_secs(754)), "12:34");
+        assert_eq!(clock_time(Duration::from_millis(59_999)), "0:59");
+        assert_eq!(clock_time(Duration::from_secs(3600 + 65)), "1:01:05");
+    }
+
+    #[test]
+    fn tempo_markings_start_at_their_boundary() {
+        assert_eq!(tempo_marking(30.), "Grave");
+        assert_eq!(tempo_marking(65.5), "Larghetto");
+        assert_eq!(tempo_marking(66.), "Adagio");
+        assert_eq!(tempo_marking(119.9), "Moderato");
+        assert_eq!(tempo_marking(120.), "Allegro");
+        assert_eq!(tempo_marking(300.), "Prestissimo");
+        assert!(TEMPO_MARKINGS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn groupings_must_fill_the_bar() {
+        assert_eq!(parse_groups("3+2+2", 7), Some(vec![3, 2, 2]));
+        assert_eq!(parse_groups(" 2 + 3 ", 5), Some(vec![2, 3]));
+        assert_eq!(parse_groups("", 7), Some(vec![]));
+        assert_eq!(parse_groups("3+2", 7), None);
+        assert_eq!(parse_groups("3+0+4", 7), None);
+        assert_eq!(parse_groups("3+x", 7), None);
+        assert_eq!(parse_groups("4294967295+5", 4), None);
+    }
+
+    #[test]
+    fn recent_tempos_stay_distinct_and_bounded() {
+        let mut recent = VecDeque::new();
+        for bpm in [100., 120., 100.] {
+            remember_tempo(&mut recent, bpm);
+        }
+        assert_eq!(recent, [100., 120.]);
+        for bpm in 0..10 {
+            remember_tempo(&mut recent, 60. + bpm as f64);
+        }
+        assert_eq!(recent.len(), RECENT_TEMPOS);
+        assert_eq!(recent.front(), Some(&69.));
+    }
+
+    #[test]
+    fn meter_sequences_take_valid_bars() {
+        assert_eq!(parse_meter_sequence("4, 3"), Some(vec![4, 3]));
+        assert_eq!(parse_meter_sequence(" "), Some(vec![]));
+        assert_eq!(parse_meter_sequence("4, 0"), None);
+        assert_eq!(parse_meter_sequence("4, 33"), None);
+        assert_eq!(parse_meter_sequence("4,,3"), None);
     }
 }
@@ -0,0 +1,45 @@
+use std::thread;
+
+use iced::futures::channel::oneshot;
+use rodio::cpal::traits::{DeviceTrait, HostTrait};
+use rodio::cpal::{self, Device};
+use rodio::{OutputStream, OutputStreamHandle};
+
+/// The names of the output devices currently connected, listed off the UI thread since some
+/// hosts take a while to answer.
+pub async fn device_names() -> Vec<String> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let names = cpal::default_host()
+            .output_devices()
+            .map(|devices| devices.filter_map(|device| device.name().ok()).collect())
+            .unwrap_or_default();
+        let _ = tx.send(names);
+    });
+    rx.await.unwrap_or_default()
+}
+
+/// Opens the output device called `name`, or the default one for `None`.
+pub fn open(name: Option<&str>) -> Result<(OutputStream, OutputStreamHandle, Device), String> {
+    let host = cpal::default_host();
+    let device = match name {
+        Some(name) => host
+            .output_devices()
+            .map_err(|err| format!("Couldn't list audio outputs: {}", err))?
+            .find(|device| device.name().ok().as_deref() == Some(name))
+            .ok_or_else(|| format!("{} isn't connected", name))?,
+        None => host
+            .default_output_device()
+            .ok_or("No audio output found")?,
+    };
+    let (stream, stream_handle) = OutputStream::try_from_device(&device).map_err(|err| {
+        format!(
+            "Couldn't open {}: {}",
+            device
+                .name()
+                .unwrap_or_else(|_| String::from("the audio output")),
+            err
+        )
+    })?;
+    Ok((stream, stream_handle, device))
+}
@@ -0,0 +1,160 @@
+use std::time::{Duration, Instant};
+
+/// Time between clock ticks, of which there are `subdivision` per beat. `bpm` always counts
+/// quarter notes, so a beat lasts `4 / beat_unit` of a quarter. A `beat_unit` or
+/// `subdivision` of 0 counts as 1.
+///
+/// # Panics
+///
+/// If `bpm` isn't a positive, finite number.
+pub fn tick_interval(bpm: f64, beat_unit: u32, subdivision: u32) -> Duration {
+    let (beat_unit, subdivision) = (beat_unit.max(1), subdivision.max(1));
+    Duration::from_secs_f64(240. / bpm / beat_unit as f64 / subdivision as f64)
+}
+
+/// `bpm` to the nearest tenth, the finest tempo there is.
+pub fn round_bpm(bpm: f64) -> f64 {
+    (bpm * 10.).round() / 10.
+}
+
+/// When clock ticks are due. Every tick is counted from one fixed start rather than from
+/// the tick before, so late wake-ups don't add up over a long session.
+///
+/// Ticks go in pairs, and with swing the second of each pair comes `swing` of the way
+/// through the pair instead of halfway.
+#[derive(Debug, Clone, Copy)]
+pub struct Schedule {
+    start: Instant,
+    interval: Duration,
+    swing: f32,
+    /// The tick at `start` was the second of its pair.
+    second: bool,
+    ticks: u32,
+}
+
+impl Schedule {
+    /// A straight schedule whose first tick is one interval after `start`.
+    pub fn new(start: Instant, interval: Duration) -> Self {
+        Self {
+            start,
+            interval,
+            swing: 0.5,
+            second: false,
+            ticks: 0,
+        }
+    }
+
+    /// The same schedule with each pair swung by `swing`, where `second` says whether the
+    /// tick at the start was the second of its pair.
+    pub fn with_swing(self, swing: f32, second: bool) -> Self {
+        Self {
+            swing,
+            second,
+            ..self
+        }
+    }
+
+    /// When the next tick is due.
+    pub fn next(&self) -> Instant {
+        self.tick(self.ticks + 1)
+    }
+
+    fn tick(&self, tick: u32) -> Instant {
+        let first = self.second as u32;
+        self.start + self.offset(first + tick) - self.offset(first)
+    }
+
+    /// How far `tick` is from the start of the first pair.
+    fn offset(&self, tick: u32) -> Duration {
+        let pairs = self.interval * 2 * (tick / 2);
+        if tick.is_multiple_of(2) {
+            pairs
+        } else if self.swing == 0.5 {
+            // Kept exact, so straight time is exactly what it was without swing
+            pairs + self.interval
+        } else {
+            pairs + self.interval.mul_f32(2. * self.swing)
+        }
+    }
+
+    /// Moves past the tick that was due. Ticks missed by more than an interval, say while
+    /// the machine was suspended, are dropped rather than played in a burst.
+    pub fn advance(&mut self, now: Instant) {
+        self.ticks += 1;
+        let behind = now.saturating_duration_since(self.next());
+        if behind >= self.interval {
+            let missed = (behind.as_secs_f64() / self.interval.as_secs_f64()) as u32;
+            // Whole pairs only when swung, so the long and short gaps stay in step
+            self.ticks += if self.swing == 0.5 {
+                missed
+            } else {
+                missed - missed % 2
+            };
+        }
+    }
+
+    /// The same schedule at a new interval, counted from the last tick so the next one
+    /// comes one new interval after it.
+    pub fn retimed(&self, interval: Duration) -> Self {
+        Self::new(self.tick(self.ticks), interval)
+            .with_swing(self.swing, self.second ^ (self.ticks % 2 == 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_interval_at_tempo_extremes() {
+        assert_eq!(tick_interval(30., 4, 1), Duration::from_secs(2));
+        assert_eq!(tick_interval(30., 4, 2), Duration::from_secs(1));
+        assert_eq!(tick_interval(300., 4, 1), Duration::from_millis(200));
+        assert_eq!(tick_interval(300., 4, 2), Duration::from_millis(100));
+        assert_eq!(tick_interval(300., 4, 4), Duration::from_millis(50));
+        // An eighth-note beat in 6/8 ticks twice as fast as a quarter in 3/4
+        assert_eq!(tick_interval(120., 8, 1), Duration::from_millis(250));
+        assert_eq!(tick_interval(120., 4, 1), Duration::from_millis(500));
+        assert_eq!(tick_interval(120., 0, 0), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn schedule_does_not_drift() {
+        let start = Instant::now();
+        let interval = tick_interval(137., 4, 3);
+        let mut schedule = Schedule::new(start, interval);
+        // Every wake-up is a little late, as sleeping always is
+        let ticks = (Duration::from_secs(600).as_secs_f64() / interval.as_secs_f64()) as u32;
+        for _ in 0..ticks {
+            schedule.advance(schedule.next() + Duration::from_millis(2));
+        }
+        let expected = start + interval * (ticks + 1);
+        let drift = schedule.next().max(expected) - schedule.next().min(expected);
+        assert!(drift < Duration::from_millis(1), "drifted {:?}", drift);
+    }
+
+    #[test]
+    fn schedule_retimes_from_the_last_tick() {
+        let start = Instant::now();
+        let mut schedule = Schedule::new(start, Duration::from_millis(500));
+        schedule.advance(start + Duration::from_millis(500));
+        let faster = schedule.retimed(Duration::from_millis(400));
+        assert_eq!(faster.next(), start + Duration::from_millis(900));
+        // Waking up long after the tick skips all but the latest of the missed ones
+        schedule.advance(start + Duration::from_millis(2600));
+        assert_eq!(schedule.next(), start + Duration::from_millis(2500));
+    }
+
+    #[test]
+    fn swing_delays_the_second_of_each_pair() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut schedule = Schedule::new(start, ms(250)).with_swing(0.75, false);
+        assert_eq!(schedule.next(), start + ms(375));
+        schedule.advance(start + ms(375));
+        // Retiming after the swung tick keeps the short gap that follows it
+        assert_eq!(schedule.retimed(ms(250)).next(), start + ms(500));
+        schedule.advance(start + ms(500));
+        assert_eq!(schedule.next(), start + ms(875));
+    }
+}
@@ -0,0 +1,103 @@
+/// Songs to play through in order, and which one is up.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setlist<T> {
+    songs: Vec<(String, T)>,
+    current: Option<usize>,
+}
+
+impl<T> Setlist<T> {
+    pub fn new(songs: Vec<(String, T)>) -> Self {
+        Self {
+            songs,
+            current: None,
+        }
+    }
+
+    pub fn songs(&self) -> &[(String, T)] {
+        &self.songs
+    }
+
+    /// The song picked last, if any.
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn push(&mut self, name: String, song: T) {
+        self.songs.push((name, song));
+    }
+
+    /// Picks song `index`, returning it unless there's no such song.
+    pub fn select(&mut self, index: usize) -> Option<&T> {
+        let (_, song) = self.songs.get(index)?;
+        self.current = Some(index);
+        Some(song)
+    }
+
+    /// Picks the song after the current one, or the first if none was picked yet. Stays at
+    /// the end of the set rather than wrapping round.
+    pub fn next(&mut self) -> Option<&T> {
+        let index = self.current.map_or(0, |current| current + 1);
+        self.select(index)
+    }
+
+    /// Picks the song before the current one. Stays at the start of the set.
+    pub fn previous(&mut self) -> Option<&T> {
+        let index = self.current?.checked_sub(1)?;
+        self.select(index)
+    }
+
+    /// Takes out song `index`; the current song stays current unless it's the one removed.
+    pub fn remove(&mut self, index: usize) {
+        if index >= self.songs.len() {
+            return;
+        }
+        self.songs.remove(index);
+        self.current = match self.current {
+            Some(current) if current == index => None,
+            Some(current) if current > index => Some(current - 1),
+            current => current,
+        };
+    }
+
+    /// Swaps song `index` with the one before it, the current song moving along.
+    pub fn move_up(&mut self, index: usize) {
+        if index == 0 || index >= self.songs.len() {
+            return;
+        }
+        self.songs.swap(index - 1, index);
+        self.current = match self.current {
+            Some(current) if current == index => Some(index - 1),
+            Some(current) if current == index - 1 => Some(index),
+            current => current,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setlist_steps_and_reorders() {
+        let mut setlist = Setlist::new(vec![
+            (String::from("Intro"), 90),
+            (String::from("Ballad"), 60),
+            (String::from("Closer"), 160),
+        ]);
+        assert_eq!(setlist.previous(), None);
+        assert_eq!(setlist.next(), Some(&90));
+        assert_eq!(setlist.next(), Some(&60));
+        assert_eq!(setlist.previous(), Some(&90));
+        assert_eq!(setlist.select(2), Some(&160));
+        assert_eq!(setlist.next(), None);
+        assert_eq!(setlist.current(), Some(2));
+        setlist.move_up(2);
+        assert_eq!(setlist.current(), Some(1));
+        assert_eq!(setlist.songs()[1].0, "Closer");
+        setlist.remove(0);
+        assert_eq!(setlist.current(), Some(0));
+        setlist.remove(0);
+        assert_eq!(setlist.current(), None);
+        assert_eq!(setlist.songs().len(), 1);
+    }
+}
@@ -0,0 +1,275 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Display};
+use std::fs;
+use std::io::Cursor;
+use std::thread;
+
+use iced::futures::channel::oneshot;
+use rodio::{
+    decoder::DecoderError,
+    source::{Buffered, SamplesConverter},
+    Decoder, Source,
+};
+
+use crate::strings::Label;
+
+macro_rules! asset {
+    ($pack:ident, $sample:ident, $file:literal) => {
+        (
+            SoundPack::$pack,
+            Sample::$sample,
+            $file,
+            include_bytes!(concat!("../assets/", $file)),
+        )
+    };
+}
+
+/// Every embedded click, with the file it came from.
+const ASSETS: [(SoundPack, Sample, &str, &[u8]); 12] = [
+    asset!(Classic, E, "e-click.wav"),
+    asset!(Classic, EFlat, "e-flat-click.wav"),
+    asset!(Classic, F, "f-click.wav"),
+    asset!(WoodBlock, E, "wood-block-e.wav"),
+    asset!(WoodBlock, EFlat, "wood-block-e-flat.wav"),
+    asset!(WoodBlock, F, "wood-block-f.wav"),
+    asset!(Cowbell, E, "cowbell-e.wav"),
+    asset!(Cowbell, EFlat, "cowbell-e-flat.wav"),
+    asset!(Cowbell, F, "cowbell-f.wav"),
+    asset!(Beep, E, "beep-e.wav"),
+    asset!(Beep, EFlat, "beep-e-flat.wav"),
+    asset!(Beep, F, "beep-f.wav"),
+];
+
+/// A fully decoded click; clones share the decoded samples.
+pub type Click = Buffered<SamplesConverter<Decoder<Cursor<Cow<'static, [u8]>>>, f32>>;
+
+/// A click decoded from a file the user picked.
+#[derive(Clone)]
+pub struct FileClick(pub Click);
+
+impl Debug for FileClick {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FileClick")
+    }
+}
+
+/// One of the embedded instruments, each with a sample at every [`Sample`] pitch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SoundPack {
+    Classic,
+    WoodBlock,
+    Cowbell,
+    Beep,
+}
+
+impl SoundPack {
+    pub const ALL: [SoundPack; 4] = [
+        SoundPack::Classic,
+        SoundPack::WoodBlock,
+        SoundPack::Cowbell,
+        SoundPack::Beep,
+    ];
+
+    pub fn label(self) -> Label {
+        match self {
+            SoundPack::Classic => Label::PackClassic,
+            SoundPack::WoodBlock => Label::PackWoodBlock,
+            SoundPack::Cowbell => Label::PackCowbell,
+            SoundPack::Beep => Label::PackBeep,
+        }
+    }
+}
+
+/// One of a pack's embedded clicks, named by its pitch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Sample {
+    E,
+    EFlat,
+    F,
+}
+
+impl Sample {
+    pub const ALL: [Sample; 3] = [Sample::EFlat, Sample::E, Sample::F];
+}
+
+impl Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Sample::E => "E",
+            Sample::EFlat => "E♭",
+            Sample::F => "F",
+        })
+    }
+}
+
+/// Names one of the embedded clicks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SoundId {
+    pub pack: SoundPack,
+    pub sample: Sample,
+}
+
+/// The embedded clicks, decoded once and shared: every click handed out plays from the
+/// same buffer.
+pub struct SoundBank {
+    clicks: HashMap<SoundId, Click>,
+}
+
+impl SoundBank {
+    /// Decodes every embedded sample up front, so a bad asset shows up at startup instead
+    /// of on the first beat.
+    pub fn load() -> Result<Self, String> {
+        let clicks = ASSETS
+            .iter()
+            .map(|&(pack, sample, file, data)| {
+                Ok((SoundId { pack, sample }, decode(file, Cow::Borrowed(data))?))
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self { clicks })
+    }
+
+    pub fn get(&self, id: SoundId) -> Click {
+        // Every pack has every sample, so there's always one to hand out
+        self.clicks[&id].clone()
+    }
+}
+
+/// A part a sound can play in the bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Downbeat,
+    /// The start of each group after the first, in a bar split into groups.
+    Accent,
+    Beat,
+    OffBeat,
+    Subdivision,
+    /// The cues played as the metronome starts and stops.
+    End,
+}
+
+impl Role {
+    pub const ALL: [Role; 6] = [
+        Role::Downbeat,
+        Role::Accent,
+        Role::Beat,
+        Role::OffBeat,
+        Role::Subdivision,
+        Role::End,
+    ];
+
+    /// Position in [`Role::ALL`], for keeping something per role in an array.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// How one role sounds. `pitch` also changes the length, like playing a tape faster.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoleSound {
+    pub sample: Sample,
+    pub volume: f32,
+    pub pitch: f32,
+}
+
+impl RoleSound {
+    fn new(sample: Sample) -> Self {
+        Self {
+            sample,
+            volume: 1.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+/// Which sound plays for each role.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoundMap {
+    /// The pack every role's sample comes from.
+    pub pack: SoundPack,
+    pub downbeat: RoleSound,
+    pub accent: RoleSound,
+    pub beat: RoleSound,
+    pub off_beat: RoleSound,
+    pub subdivision: RoleSound,
+    pub end: RoleSound,
+}
+
+impl SoundMap {
+    pub fn get(&self, role: Role) -> &RoleSound {
+        match role {
+            Role::Downbeat => &self.downbeat,
+            Role::Accent => &self.accent,
+            Role::Beat => &self.beat,
+            Role::OffBeat => &self.off_beat,
+            Role::Subdivision => &self.subdivision,
+            Role::End => &self.end,
+        }
+    }
+
+    /// Which embedded click `role` plays.
+    pub fn id(&self, role: Role) -> SoundId {
+        SoundId {
+            pack: self.pack,
+            sample: self.get(role).sample,
+        }
+    }
+
+    pub fn get_mut(&mut self, role: Role) -> &mut RoleSound {
+        match role {
+            Role::Downbeat => &mut self.downbeat,
+            Role::Accent => &mut self.accent,
+            Role::Beat => &mut self.beat,
+            Role::OffBeat => &mut self.off_beat,
+            Role::Subdivision => &mut self.subdivision,
+            Role::End => &mut self.end,
+        }
+    }
+}
+
+impl Default for SoundMap {
+    fn default() -> Self {
+        Self {
+            pack: SoundPack::Classic,
+            downbeat: RoleSound::new(Sample::EFlat),
+            // The downbeat's click, a little quieter
+            accent: RoleSound {
+                volume: 0.7,
+                ..RoleSound::new(Sample::EFlat)
+            },
+            beat: RoleSound::new(Sample::E),
+            off_beat: RoleSound::new(Sample::F),
+            subdivision: RoleSound::new(Sample::F),
+            // Set back, as the cues aren't part of the time
+            end: RoleSound {
+                volume: 0.4,
+                ..RoleSound::new(Sample::E)
+            },
+        }
+    }
+}
+
+/// Reads and decodes a WAV, MP3, FLAC or Ogg Vorbis file off the UI thread.
+pub async fn load_file(path: String) -> Result<FileClick, String> {
+    let (tx, rx) = oneshot::channel();
+    thread::spawn(move || {
+        let click = fs::read(&path)
+            .map_err(|err| format!("Couldn't read {}: {}", path, err))
+            .and_then(|data| decode(&path, Cow::Owned(data)));
+        let _ = tx.send(click.map(FileClick));
+    });
+    rx.await
+        .unwrap_or_else(|_| Err(String::from("Loading stopped unexpectedly")))
+}
+
+fn decode(name: &str, data: Cow<'static, [u8]>) -> Result<Click, String> {
+    let click = Decoder::new(Cursor::new(data))
+        .map_err(|err: DecoderError| format!("Couldn't decode {}: {}", name, err))?
+        .convert_samples()
+        .buffered();
+    // Walking one clone decodes the whole sample into the buffer shared by all clones
+    if click.clone().count() == 0 {
+        return Err(format!("{} contains no audio", name));
+    }
+    Ok(click)
+}
@@ -0,0 +1,257 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Sender};
+
+/// Streams the metronome's state as JSON lines to anything connected to a local socket, or to
+/// a named pipe on Windows.
+///
+/// Clients get the latest state as soon as they connect and a new line on every change. The
+/// socket is removed again when the server is dropped.
+pub struct StatusServer {
+    tx: Sender<String>,
+    last: String,
+}
+
+impl StatusServer {
+    pub fn start() -> io::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        platform::spawn(socket_path(), rx)?;
+        Ok(Self {
+            tx,
+            last: String::new(),
+        })
+    }
+
+    /// Sends `status` to every client, unless it's unchanged since the last call.
+    pub fn publish(&mut self, status: String) {
+        if status != self.last {
+            let _ = self.tx.send(status.clone());
+            self.last = status;
+        }
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    if cfg!(target_os = "windows") {
+        return PathBuf::from(r"\\.\pipe\metronome");
+    }
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("metronome.sock")
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::fs;
+    use std::io::{self, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::PathBuf;
+    use std::sync::mpsc::{Receiver, RecvTimeoutError};
+    use std::thread;
+    use std::time::Duration;
+
+    /// How often to look for new clients while no state changes arrive.
+    const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+    /// A client that can't take a line within this long is dropped.
+    const WRITE_TIMEOUT: Duration = Duration::from_millis(50);
+
+    pub fn spawn(path: PathBuf, rx: Receiver<String>) -> io::Result<()> {
+        // A socket left behind by a previous run would make `bind` fail, but one that's
+        // still answering belongs to another metronome sharing its status
+        match UnixStream::connect(&path) {
+            Ok(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another metronome is already sharing its status",
+                ))
+            }
+            Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => {
+                let _ = fs::remove_file(&path);
+            }
+            Err(_) => {}
+        }
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        thread::spawn(move || serve(listener, rx, path));
+        Ok(())
+    }
+
+    fn serve(listener: UnixListener, rx: Receiver<String>, path: PathBuf) {
+        let mut clients: Vec<UnixStream> = Vec::new();
+        let mut last = String::new();
+        loop {
+            while let Ok((mut client, _)) = listener.accept() {
+                let ready = client.set_nonblocking(false).is_ok()
+                    && client.set_write_timeout(Some(WRITE_TIMEOUT)).is_ok()
+                    && (last.is_empty() || writeln!(client, "{}", last).is_ok());
+                if ready {
+                    clients.push(client);
+                }
+            }
+            match rx.recv_timeout(ACCEPT_INTERVAL) {
+                Ok(status) => {
+                    clients.retain_mut(|client| writeln!(client, "{}", status).is_ok());
+                    last = status;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        let _ = fs::remove_file(path);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::sync::mpsc;
+
+        #[test]
+        fn a_live_socket_is_left_to_its_server() {
+            let path = std::env::temp_dir().join(format!("metronome-{}.sock", std::process::id()));
+            // Left behind by a run that didn't get to clean up
+            drop(UnixListener::bind(&path).unwrap());
+            let (tx, rx) = mpsc::channel();
+            spawn(path.clone(), rx).unwrap();
+            let (_, second) = mpsc::channel();
+            let err = spawn(path.clone(), second).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+            assert!(UnixStream::connect(&path).is_ok());
+            drop(tx);
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::ffi::c_void;
+    use std::fs::File;
+    use std::io::{self, Write};
+    use std::iter;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::{AsRawHandle, FromRawHandle, RawHandle};
+    use std::path::PathBuf;
+    use std::ptr;
+    use std::sync::mpsc::{Receiver, RecvTimeoutError};
+    use std::thread;
+    use std::time::Duration;
+
+    /// How often to look for new clients while no state changes arrive.
+    const ACCEPT_INTERVAL: Duration = Duration::from_millis(100);
+    /// What a client's pipe holds before it's too far behind and gets dropped.
+    const BUFFER_SIZE: u32 = 4096;
+
+    const PIPE_ACCESS_OUTBOUND: u32 = 0x0000_0002;
+    const FILE_FLAG_FIRST_PIPE_INSTANCE: u32 = 0x0008_0000;
+    const PIPE_TYPE_BYTE: u32 = 0x0000_0000;
+    const PIPE_NOWAIT: u32 = 0x0000_0001;
+    const PIPE_REJECT_REMOTE_CLIENTS: u32 = 0x0000_0008;
+    const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+    const ERROR_PIPE_CONNECTED: i32 = 535;
+    const ERROR_NO_DATA: i32 = 232;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn CreateNamedPipeW(
+            name: *const u16,
+            open_mode: u32,
+            pipe_mode: u32,
+            max_instances: u32,
+            out_buffer_size: u32,
+            in_buffer_size: u32,
+            default_timeout: u32,
+            security_attributes: *mut c_void,
+        ) -> RawHandle;
+        fn ConnectNamedPipe(pipe: RawHandle, overlapped: *mut c_void) -> i32;
+    }
+
+    pub fn spawn(path: PathBuf, rx: Receiver<String>) -> io::Result<()> {
+        let name = path
+            .as_os_str()
+            .encode_wide()
+            .chain(iter::once(0))
+            .collect::<Vec<_>>();
+        // Only the first instance may create the pipe, so this fails while another
+        // metronome is sharing its status
+        let pipe = listen(&name, FILE_FLAG_FIRST_PIPE_INSTANCE)?;
+        thread::spawn(move || serve(name, pipe, rx));
+        Ok(())
+    }
+
+    /// A new instance of the pipe `name`, for the next client to connect to.
+    fn listen(name: &[u16], flags: u32) -> io::Result<File> {
+        // Non-blocking, so a client that stops reading fails a write instead of stalling it
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                PIPE_ACCESS_OUTBOUND | flags,
+                PIPE_TYPE_BYTE | PIPE_NOWAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                BUFFER_SIZE,
+                0,
+                0,
+                ptr::null_mut(),
+            )
+        };
+        // INVALID_HANDLE_VALUE
+        if handle as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // The handle is ours alone, and is closed with the file
+        Ok(unsafe { File::from_raw_handle(handle) })
+    }
+
+    /// Whether a client has connected to `pipe`, possibly leaving again already.
+    fn connected(pipe: &File) -> bool {
+        // A non-blocking pipe only reports on the client, which it does through the error
+        unsafe { ConnectNamedPipe(pipe.as_raw_handle(), ptr::null_mut()) };
+        matches!(
+            io::Error::last_os_error().raw_os_error(),
+            Some(ERROR_PIPE_CONNECTED | ERROR_NO_DATA)
+        )
+    }
+
+    fn serve(name: Vec<u16>, pipe: File, rx: Receiver<String>) {
+        let mut clients: Vec<File> = Vec::new();
+        let mut last = String::new();
+        let mut waiting = Some(pipe);
+        loop {
+            if waiting.is_none() {
+                waiting = listen(&name, 0).ok();
+            }
+            // Each client keeps the instance it connected to, and a new one waits for the next
+            while let Some(mut client) = waiting.take() {
+                if !connected(&client) {
+                    waiting = Some(client);
+                    break;
+                }
+                if last.is_empty() || writeln!(client, "{}", last).is_ok() {
+                    clients.push(client);
+                }
+                waiting = listen(&name, 0).ok();
+            }
+            match rx.recv_timeout(ACCEPT_INTERVAL) {
+                Ok(status) => {
+                    clients.retain_mut(|client| writeln!(client, "{}", status).is_ok());
+                    last = status;
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    }
+}
+
+#[cfg(not(any(unix, target_os = "windows")))]
+mod platform {
+    use std::io;
+    use std::path::PathBuf;
+    use std::sync::mpsc::Receiver;
+
+    pub fn spawn(_path: PathBuf, _rx: Receiver<String>) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "status sharing is only available on Unix and Windows",
+        ))
+    }
+}
@@ -0,0 +1,976 @@
+use std::fmt::{self, Display};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+    French,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::English,
+        Language::German,
+        Language::French,
+        Language::Spanish,
+    ];
+
+    /// Looks up `label` in this language, falling back to English for missing entries.
+    pub fn get(self, label: Label) -> &'static str {
+        match self {
+            Language::English => None,
+            Language::German => german(label),
+            Language::French => french(label),
+            Language::Spanish => spanish(label),
+        }
+        .unwrap_or_else(|| english(label))
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+            Language::French => "Français",
+            Language::Spanish => "Español",
+        })
+    }
+}
+
+/// Every piece of user-facing text. Entries may contain `{}` placeholders for [`fill`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    Title,
+    Bpm,
+    TempoChange,
+    TempoImmediate,
+    TempoNextBeat,
+    TempoNextBar,
+    RestartBarOnTempo,
+    TempoRange,
+    TempoRecord,
+    Session,
+    AverageTempo,
+    TempoSlot,
+    TempoStep,
+    TempoDecimals,
+    EmptyTempoSlot,
+    StoreTempoSlot,
+    PresetName,
+    SavePreset,
+    Preset,
+    DeletePreset,
+    SongName,
+    AddSong,
+    Song,
+    MoveUp,
+    PrevSong,
+    NextSong,
+    SwapTempoSlots,
+    RecentTempos,
+    HalfTime,
+    DoubleTime,
+    Reset,
+    WarmUpFrom,
+    WarmUpSecs,
+    GapAudible,
+    GapSilent,
+    RampFrom,
+    RampTo,
+    RampStep,
+    RampEvery,
+    RampLoop,
+    DrillFrom,
+    GlideTo,
+    GlideBars,
+    GlideStraight,
+    GlideCurved,
+    Glide,
+    CancelGlide,
+    BeatsPerBar,
+    TimeSignature,
+    BpmCountsQuarters,
+    RampNextStep,
+    StartOnBeat,
+    CountInBars,
+    BeatSoundsHint,
+    AccentDownbeat,
+    Grouping,
+    GroupsMustSum,
+    MeterSequence,
+    MeterSequenceHint,
+    Subdivision,
+    Swing,
+    Polyrhythm,
+    PolyDivisions,
+    SubdivisionNone,
+    Eighths,
+    Triplets,
+    Sixteenths,
+    OffBeatsAfter,
+    PickupOffBeat,
+    PickupPlay,
+    PickupSoften,
+    PickupSkip,
+    DownbeatOnly,
+    VisualOnly,
+    TruncateClicks,
+    Milliseconds,
+    StartStopCues,
+    BeatProgressBar,
+    BeatCounter,
+    Compact,
+    FullLayout,
+    VisualStyle,
+    BeatColor,
+    IdleColor,
+    ThemeColor,
+    StyleAllBeats,
+    StyleDownbeatOnly,
+    StyleSingle,
+    StyleConductor,
+    StylePendulum,
+    Pulse,
+    BigDownbeat,
+    StageFlash,
+    BackgroundGradient,
+    AttentionOnDownbeat,
+    BeatInTitle,
+    KeepAwake,
+    ShareStatus,
+    ShareStatusFailed,
+    Volume,
+    Mute,
+    Unmute,
+    Language,
+    DarkTheme,
+    Start,
+    Stop,
+    Pause,
+    Resume,
+    Silence,
+    Tap,
+    About,
+    Sounds,
+    Undo,
+    Redo,
+    KeyboardShortcuts,
+    KeysHint,
+    PressKey,
+    ChangeKey,
+    ActionToggle,
+    ActionFaster,
+    ActionSlower,
+    ActionRecall,
+    RoleDownbeat,
+    RoleAccent,
+    RoleBeat,
+    RoleOffBeat,
+    RoleSubdivision,
+    RoleEnd,
+    SoundVolume,
+    SoundPitch,
+    SoundPack,
+    OutputDevice,
+    DefaultOutputDevice,
+    OutputDeviceMissing,
+    OutputLost,
+    PlayerStopped,
+    ClickTrackFailed,
+    ClickTrackStopped,
+    PackClassic,
+    PackWoodBlock,
+    PackCowbell,
+    PackBeep,
+    SampleFile,
+    LoadSampleFile,
+    PreviewSound,
+    ConfigFile,
+    ExportConfig,
+    ImportConfig,
+    ConfigExported,
+    ConfigImported,
+    ConfigWriteFailed,
+    ConfigReadFailed,
+    NotAConfig,
+    ConfigTooNew,
+    ExportStopped,
+    ImportStopped,
+    Back,
+    Calibrate,
+    NoMicrophone,
+    MicrophoneUnavailable,
+    UnsupportedMicrophoneFormat,
+    RecordingFailed,
+    CalibrationNoOutput,
+    CalibrationClickFailed,
+    NothingRecorded,
+    ClicksNotHeard,
+    CalibrationStopped,
+    Listening,
+    LatencyOffset,
+    SampleAccurate,
+    MeasuredLatency,
+    Accept,
+    Version,
+    AudioDevice,
+    AudioFormat,
+    AudioUnavailable,
+    BuiltWith,
+}
+
+/// Replaces each `{}` in `template` with the next of `args`, in order.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut parts = template.split("{}");
+    if let Some(first) = parts.next() {
+        filled.push_str(first);
+    }
+    for part in parts {
+        if let Some(arg) = args.next() {
+            filled.push_str(&arg.to_string());
+        }
+        filled.push_str(part);
+    }
+    filled
+}
+
+fn english(label: Label) -> &'static str {
+    match label {
+        Label::Title => "Metronome",
+        Label::Bpm => "{} BPM",
+        Label::TempoChange => "New tempo:",
+        Label::TempoImmediate => "Immediately",
+        Label::TempoNextBeat => "Next beat",
+        Label::TempoNextBar => "Next bar",
+        Label::RestartBarOnTempo => "Restart the bar when the tempo changes",
+        Label::TempoRange => "Played {}–{} BPM",
+        Label::TempoRecord => "All-time {}–{} BPM",
+        Label::Session => "{} • {} bars • {} beats",
+        Label::AverageTempo => "Average {} BPM",
+        Label::TempoSlot => "{}: {} BPM",
+        Label::TempoStep => "Step: {} BPM",
+        Label::TempoDecimals => "Tempo decimals:",
+        Label::EmptyTempoSlot => "{}: —",
+        Label::StoreTempoSlot => "Set {}",
+        Label::PresetName => "Preset name",
+        Label::SavePreset => "Save preset",
+        Label::Preset => "{}: {} BPM, {} beats",
+        Label::DeletePreset => "Delete",
+        Label::SongName => "Song name",
+        Label::AddSong => "Add song",
+        Label::Song => "{}. {}: {} BPM, {} beats",
+        Label::MoveUp => "Up",
+        Label::PrevSong => "Previous song",
+        Label::NextSong => "Next song",
+        Label::SwapTempoSlots => "Swap A/B",
+        Label::RecentTempos => "Recent:",
+        Label::HalfTime => "Half time",
+        Label::DoubleTime => "Double time",
+        Label::Reset => "Reset",
+        Label::WarmUpFrom => "Warm up from {} BPM",
+        Label::WarmUpSecs => "over {} s",
+        Label::GapAudible => "Play {} bars",
+        Label::GapSilent => "then rest {} bars",
+        Label::RampFrom => "Ramp from {} BPM",
+        Label::DrillFrom => "Random tempo from {} BPM",
+        Label::RampTo => "to {} BPM",
+        Label::RampStep => "by {} BPM",
+        Label::RampEvery => "every {} bars",
+        Label::RampLoop => "Start over at the end",
+        Label::GlideTo => "Glide to {} BPM",
+        Label::GlideBars => "over {} bars",
+        Label::GlideStraight => "Straight",
+        Label::GlideCurved => "Curved",
+        Label::Glide => "Glide",
+        Label::CancelGlide => "Cancel glide",
+        Label::BeatsPerBar => "{} beats per bar",
+        Label::TimeSignature => "Time signature {}/{}",
+        Label::BpmCountsQuarters => "Tempo counts quarter notes",
+        Label::RampNextStep => "Bar {} of {} until {} BPM",
+        Label::StartOnBeat => "Start on beat {}",
+        Label::CountInBars => "Count-in bars: {}",
+        Label::BeatSoundsHint => "Click a beat to change its sound",
+        Label::AccentDownbeat => "Accent the first beat",
+        Label::Grouping => "Grouping:",
+        Label::GroupsMustSum => "Groups must add up to {}",
+        Label::MeterSequence => "Meter sequence:",
+        Label::MeterSequenceHint => "Bar lengths from 1 to {}, separated by commas",
+        Label::Subdivision => "Subdivision:",
+        Label::Swing => "Swing {}%",
+        Label::Polyrhythm => "Polyrhythm",
+        Label::PolyDivisions => "{} against {}",
+        Label::SubdivisionNone => "None",
+        Label::Eighths => "Eighths",
+        Label::Triplets => "Triplets",
+        Label::Sixteenths => "Sixteenths",
+        Label::OffBeatsAfter => "Off-beats after beat:",
+        Label::PickupOffBeat => "Off-beat before the downbeat:",
+        Label::PickupPlay => "Play",
+        Label::PickupSoften => "Soften",
+        Label::PickupSkip => "Skip",
+        Label::DownbeatOnly => "Downbeat only",
+        Label::VisualOnly => "Silent",
+        Label::TruncateClicks => "Truncate clicks",
+        Label::Milliseconds => "{} ms",
+        Label::StartStopCues => "Start/stop cues",
+        Label::BeatProgressBar => "Beat progress bar",
+        Label::BeatCounter => "Beat counter",
+        Label::Compact => "Compact",
+        Label::FullLayout => "Full view",
+        Label::VisualStyle => "Show:",
+        Label::BeatColor => "Beat color:",
+        Label::IdleColor => "Idle color:",
+        Label::ThemeColor => "Theme",
+        Label::StyleAllBeats => "All beats",
+        Label::StyleDownbeatOnly => "Downbeat only",
+        Label::StyleSingle => "Single indicator",
+        Label::StyleConductor => "Conductor",
+        Label::StylePendulum => "Pendulum",
+        Label::Pulse => "Pulse on the beat",
+        Label::BigDownbeat => "Bigger downbeat",
+        Label::StageFlash => "Flash the window",
+        Label::BackgroundGradient => "Color the background with the bar",
+        Label::KeepAwake => "Keep screen awake while playing",
+        Label::AttentionOnDownbeat => "Flash the dock or taskbar icon on the downbeat",
+        Label::BeatInTitle => "Show the beat in the window title",
+        Label::ShareStatus => "Share status over a local socket",
+        Label::ShareStatusFailed => "Couldn't share status: {}",
+        Label::Volume => "Volume:",
+        Label::Mute => "Mute",
+        Label::Unmute => "Unmute",
+        Label::Language => "Language:",
+        Label::DarkTheme => "Dark theme",
+        Label::Start => "Start",
+        Label::Stop => "Stop",
+        Label::Pause => "Pause",
+        Label::Resume => "Resume",
+        Label::Silence => "Silence",
+        Label::Tap => "Tap",
+        Label::About => "About",
+        Label::Sounds => "Sounds",
+        Label::Undo => "Undo",
+        Label::KeyboardShortcuts => "Keyboard shortcuts",
+        Label::KeysHint => "Press ? to show or hide the keyboard shortcuts",
+        Label::PressKey => "Press a key…",
+        Label::ChangeKey => "Change",
+        Label::ActionToggle => "Start/stop",
+        Label::ActionFaster => "Tempo +{}",
+        Label::ActionSlower => "Tempo −{}",
+        Label::ActionRecall => "Recall {}",
+        Label::Redo => "Redo",
+        Label::RoleDownbeat => "Downbeat",
+        Label::RoleAccent => "Group accent",
+        Label::RoleBeat => "Beat",
+        Label::RoleOffBeat => "Off-beat",
+        Label::RoleSubdivision => "Subdivision",
+        Label::RoleEnd => "Start/stop cue",
+        Label::SoundVolume => "Volume {}%",
+        Label::SoundPitch => "Pitch {}%",
+        Label::SoundPack => "Sound pack:",
+        Label::OutputDevice => "Output device:",
+        Label::DefaultOutputDevice => "System default",
+        Label::OutputDeviceMissing => "{} is disconnected; playing on the system default",
+        Label::OutputLost => "Lost the audio output: {}",
+        Label::PlayerStopped => "The audio player stopped working",
+        Label::ClickTrackFailed => "Couldn't start the click track: {}",
+        Label::ClickTrackStopped => "Lost the audio output: the click track stopped",
+        Label::PackClassic => "Classic",
+        Label::PackWoodBlock => "Wood block",
+        Label::PackCowbell => "Cowbell",
+        Label::PackBeep => "Digital beep",
+        Label::SampleFile => "WAV or MP3 file to use instead (empty for the built-in click)",
+        Label::LoadSampleFile => "Load",
+        Label::PreviewSound => "Preview",
+        Label::ConfigFile => "Config file to share",
+        Label::ExportConfig => "Export",
+        Label::ImportConfig => "Import",
+        Label::ConfigExported => "Exported",
+        Label::ConfigImported => "Imported",
+        Label::ConfigWriteFailed => "Couldn't write {}: {}",
+        Label::ConfigReadFailed => "Couldn't read {}: {}",
+        Label::NotAConfig => "That isn't an exported metronome config",
+        Label::ConfigTooNew => "That config is from a newer version of the metronome",
+        Label::ExportStopped => "Exporting stopped unexpectedly",
+        Label::ImportStopped => "Importing stopped unexpectedly",
+        Label::Back => "Back",
+        Label::Calibrate => "Calibrate",
+        Label::NoMicrophone => "No microphone found",
+        Label::MicrophoneUnavailable => "Microphone unavailable: {}",
+        Label::UnsupportedMicrophoneFormat => "Unsupported microphone format {}",
+        Label::RecordingFailed => "Couldn't record from the microphone: {}",
+        Label::CalibrationNoOutput => "No audio output: {}",
+        Label::CalibrationClickFailed => "Couldn't play click: {}",
+        Label::NothingRecorded => "The microphone recorded nothing",
+        Label::ClicksNotHeard => "Couldn't hear the clicks; turn the volume up and retry",
+        Label::CalibrationStopped => "Calibration stopped unexpectedly",
+        Label::Listening => "Listening…",
+        Label::LatencyOffset => "Latency offset: {} ms",
+        Label::SampleAccurate => "Sample-accurate timing",
+        Label::MeasuredLatency => "Measured {} ms",
+        Label::Accept => "Accept",
+        Label::Version => "Version {}",
+        Label::AudioDevice => "Audio device: {}",
+        Label::AudioFormat => "Format: {} Hz, {} channel(s), {}",
+        Label::AudioUnavailable => "Audio device: unavailable",
+        Label::BuiltWith => "Built with iced {} and rodio {}",
+    }
+}
+
+fn german(label: Label) -> Option<&'static str> {
+    Some(match label {
+        Label::Title => "Metronom",
+        Label::Bpm => "{} BPM",
+        Label::TempoChange => "Neues Tempo:",
+        Label::TempoImmediate => "Sofort",
+        Label::TempoNextBeat => "Nächster Schlag",
+        Label::TempoNextBar => "Nächster Takt",
+        Label::RestartBarOnTempo => "Takt bei Tempowechsel neu beginnen",
+        Label::TempoRange => "Gespielt: {}–{} BPM",
+        Label::TempoRecord => "Insgesamt: {}–{} BPM",
+        Label::Session => "{} • {} Takte • {} Schläge",
+        Label::AverageTempo => "Durchschnitt {} BPM",
+        Label::TempoSlot => "{}: {} BPM",
+        Label::TempoStep => "Schritt: {} BPM",
+        Label::TempoDecimals => "Nachkommastellen:",
+        Label::EmptyTempoSlot => "{}: —",
+        Label::StoreTempoSlot => "{} setzen",
+        Label::PresetName => "Name der Vorlage",
+        Label::SavePreset => "Vorlage speichern",
+        Label::Preset => "{}: {} BPM, {} Schläge",
+        Label::DeletePreset => "Löschen",
+        Label::SongName => "Songname",
+        Label::AddSong => "Song hinzufügen",
+        Label::Song => "{}. {}: {} BPM, {} Schläge",
+        Label::MoveUp => "Hoch",
+        Label::PrevSong => "Vorheriger Song",
+        Label::NextSong => "Nächster Song",
+        Label::SwapTempoSlots => "A/B wechseln",
+        Label::RecentTempos => "Zuletzt:",
+        Label::HalfTime => "Halbes Tempo",
+        Label::DoubleTime => "Doppeltes Tempo",
+        Label::Reset => "Zurücksetzen",
+        Label::WarmUpFrom => "Aufwärmen ab {} BPM",
+        Label::WarmUpSecs => "über {} s",
+        Label::GapAudible => "{} Takte spielen",
+        Label::GapSilent => "dann {} Takte Pause",
+        Label::RampFrom => "Tempo steigern ab {} BPM",
+        Label::DrillFrom => "Zufallstempo ab {} BPM",
+        Label::RampTo => "bis {} BPM",
+        Label::RampStep => "um {} BPM",
+        Label::RampEvery => "alle {} Takte",
+        Label::RampLoop => "Am Ende neu beginnen",
+        Label::GlideTo => "Übergang zu {} BPM",
+        Label::GlideBars => "über {} Takte",
+        Label::GlideStraight => "Gleichmäßig",
+        Label::GlideCurved => "Weich",
+        Label::Glide => "Übergang",
+        Label::CancelGlide => "Übergang abbrechen",
+        Label::BeatsPerBar => "{} Schläge pro Takt",
+        Label::TimeSignature => "Taktart {}/{}",
+        Label::BpmCountsQuarters => "Das Tempo zählt Viertelnoten",
+        Label::RampNextStep => "Takt {} von {} bis {} BPM",
+        Label::StartOnBeat => "Auf Schlag {} beginnen",
+        Label::CountInBars => "Einzähltakte: {}",
+        Label::BeatSoundsHint => "Klick auf einen Schlag ändert seinen Klang",
+        Label::AccentDownbeat => "Ersten Schlag betonen",
+        Label::Grouping => "Gruppierung:",
+        Label::GroupsMustSum => "Die Gruppen müssen {} ergeben",
+        Label::MeterSequence => "Taktfolge:",
+        Label::MeterSequenceHint => "Taktlängen von 1 bis {}, durch Kommas getrennt",
+        Label::Subdivision => "Unterteilung:",
+        Label::Swing => "Swing {} %",
+        Label::Polyrhythm => "Polyrhythmus",
+        Label::PolyDivisions => "{} gegen {}",
+        Label::SubdivisionNone => "Keine",
+        Label::Eighths => "Achtel",
+        Label::Triplets => "Triolen",
+        Label::Sixteenths => "Sechzehntel",
+        Label::OffBeatsAfter => "Zwischenschläge nach Schlag:",
+        Label::PickupOffBeat => "Zwischenschlag vor dem ersten Schlag:",
+        Label::PickupPlay => "Spielen",
+        Label::PickupSoften => "Leiser",
+        Label::PickupSkip => "Auslassen",
+        Label::DownbeatOnly => "Nur erster Schlag",
+        Label::VisualOnly => "Lautlos",
+        Label::TruncateClicks => "Klicks kürzen",
+        Label::StartStopCues => "Start-/Stopp-Signal",
+        Label::BeatProgressBar => "Fortschrittsbalken",
+        Label::BeatCounter => "Schlagzähler",
+        Label::Compact => "Kompakt",
+        Label::FullLayout => "Volle Ansicht",
+        Label::VisualStyle => "Anzeige:",
+        Label::BeatColor => "Schlagfarbe:",
+        Label::IdleColor => "Ruhefarbe:",
+        Label::ThemeColor => "Design",
+        Label::StyleAllBeats => "Alle Schläge",
+        Label::StyleDownbeatOnly => "Nur erster Schlag",
+        Label::StyleSingle => "Einzelne Anzeige",
+        Label::StyleConductor => "Dirigat",
+        Label::StylePendulum => "Pendel",
+        Label::Pulse => "Im Takt pulsieren",
+        Label::BigDownbeat => "Größere Eins",
+        Label::StageFlash => "Fenster aufblitzen lassen",
+        Label::BackgroundGradient => "Hintergrund im Takt einfärben",
+        Label::KeepAwake => "Bildschirm beim Spielen wach halten",
+        Label::AttentionOnDownbeat => "Dock-/Taskleistensymbol beim ersten Schlag hervorheben",
+        Label::BeatInTitle => "Schlag im Fenstertitel anzeigen",
+        Label::ShareStatus => "Status über lokalen Socket teilen",
+        Label::ShareStatusFailed => "Status konnte nicht geteilt werden: {}",
+        Label::Volume => "Lautstärke:",
+        Label::Mute => "Stumm",
+        Label::Unmute => "Ton an",
+        Label::Language => "Sprache:",
+        Label::DarkTheme => "Dunkles Design",
+        Label::Start => "Start",
+        Label::Stop => "Stopp",
+        Label::Pause => "Pause",
+        Label::Resume => "Fortsetzen",
+        Label::Silence => "Stille",
+        Label::Tap => "Tippen",
+        Label::About => "Über",
+        Label::Sounds => "Klänge",
+        Label::Undo => "Rückgängig",
+        Label::KeyboardShortcuts => "Tastenkürzel",
+        Label::KeysHint => "? zeigt oder verbirgt die Tastenkürzel",
+        Label::PressKey => "Taste drücken…",
+        Label::ChangeKey => "Ändern",
+        Label::ActionToggle => "Start/Stopp",
+        Label::ActionFaster => "Tempo +{}",
+        Label::ActionSlower => "Tempo −{}",
+        Label::ActionRecall => "{} abrufen",
+        Label::Redo => "Wiederholen",
+        Label::RoleDownbeat => "Erster Schlag",
+        Label::RoleAccent => "Gruppenakzent",
+        Label::RoleBeat => "Schlag",
+        Label::RoleOffBeat => "Zwischenschlag",
+        Label::RoleSubdivision => "Unterteilung",
+        Label::RoleEnd => "Start-/Stoppsignal",
+        Label::SoundVolume => "Lautstärke {} %",
+        Label::SoundPitch => "Tonhöhe {} %",
+        Label::SoundPack => "Klangpaket:",
+        Label::OutputDevice => "Ausgabegerät:",
+        Label::DefaultOutputDevice => "Systemstandard",
+        Label::OutputDeviceMissing => "{} ist nicht verbunden; Wiedergabe über den Systemstandard",
+        Label::OutputLost => "Die Audioausgabe ist weggefallen: {}",
+        Label::PlayerStopped => "Die Audiowiedergabe funktioniert nicht mehr",
+        Label::ClickTrackFailed => "Die Klickspur konnte nicht gestartet werden: {}",
+        Label::ClickTrackStopped => {
+            "Die Audioausgabe ist weggefallen: die Klickspur hat angehalten"
+        }
+        Label::PackClassic => "Klassisch",
+        Label::PackWoodBlock => "Holzblock",
+        Label::PackCowbell => "Kuhglocke",
+        Label::PackBeep => "Digitaler Piepton",
+        Label::SampleFile => "Stattdessen WAV- oder MP3-Datei (leer für den eingebauten Klick)",
+        Label::LoadSampleFile => "Laden",
+        Label::PreviewSound => "Anhören",
+        Label::ConfigFile => "Konfigurationsdatei zum Teilen",
+        Label::ExportConfig => "Exportieren",
+        Label::ImportConfig => "Importieren",
+        Label::ConfigExported => "Exportiert",
+        Label::ConfigImported => "Importiert",
+        Label::ConfigWriteFailed => "{} konnte nicht geschrieben werden: {}",
+        Label::ConfigReadFailed => "{} konnte nicht gelesen werden: {}",
+        Label::NotAConfig => "Das ist keine exportierte Metronom-Konfiguration",
+        Label::ConfigTooNew => "Diese Konfiguration stammt aus einer neueren Version des Metronoms",
+        Label::ExportStopped => "Der Export wurde unerwartet abgebrochen",
+        Label::ImportStopped => "Der Import wurde unerwartet abgebrochen",
+        Label::Back => "Zurück",
+        Label::Calibrate => "Kalibrieren",
+        Label::NoMicrophone => "Kein Mikrofon gefunden",
+        Label::MicrophoneUnavailable => "Mikrofon nicht verfügbar: {}",
+        Label::UnsupportedMicrophoneFormat => "Nicht unterstütztes Mikrofonformat {}",
+        Label::RecordingFailed => "Aufnahme vom Mikrofon nicht möglich: {}",
+        Label::CalibrationNoOutput => "Keine Audioausgabe: {}",
+        Label::CalibrationClickFailed => "Klick konnte nicht abgespielt werden: {}",
+        Label::NothingRecorded => "Das Mikrofon hat nichts aufgenommen",
+        Label::ClicksNotHeard => {
+            "Die Klicks waren nicht zu hören; Lautstärke erhöhen und erneut versuchen"
+        }
+        Label::CalibrationStopped => "Kalibrierung unerwartet abgebrochen",
+        Label::Listening => "Höre zu…",
+        Label::LatencyOffset => "Latenzausgleich: {} ms",
+        Label::SampleAccurate => "Sample-genaues Timing",
+        Label::MeasuredLatency => "Gemessen: {} ms",
+        Label::Accept => "Übernehmen",
+        Label::Version => "Version {}",
+        Label::AudioDevice => "Audiogerät: {}",
+        Label::AudioFormat => "Format: {} Hz, {} Kanal/Kanäle, {}",
+        Label::AudioUnavailable => "Audiogerät: nicht verfügbar",
+        Label::BuiltWith => "Erstellt mit iced {} und rodio {}",
+        _ => return None,
+    })
+}
+
+fn french(label: Label) -> Option<&'static str> {
+    Some(match label {
+        Label::Title => "Métronome",
+        Label::Bpm => "{} BPM",
+        Label::TempoChange => "Nouveau tempo :",
+        Label::TempoImmediate => "Immédiatement",
+        Label::TempoNextBeat => "Temps suivant",
+        Label::TempoNextBar => "Mesure suivante",
+        Label::RestartBarOnTempo => "Recommencer la mesure au changement de tempo",
+        Label::TempoRange => "Joué : {}–{} BPM",
+        Label::TempoRecord => "Depuis toujours : {}–{} BPM",
+        Label::Session => "{} • {} mesures • {} temps",
+        Label::AverageTempo => "Moyenne {} BPM",
+        Label::TempoSlot => "{} : {} BPM",
+        Label::TempoStep => "Pas : {} BPM",
+        Label::TempoDecimals => "Décimales du tempo :",
+        Label::EmptyTempoSlot => "{} : —",
+        Label::StoreTempoSlot => "Définir {}",
+        Label::PresetName => "Nom du préréglage",
+        Label::SavePreset => "Enregistrer le préréglage",
+        Label::Preset => "{} : {} BPM, {} temps",
+        Label::DeletePreset => "Supprimer",
+        Label::SongName => "Nom du morceau",
+        Label::AddSong => "Ajouter le morceau",
+        Label::Song => "{}. {} : {} BPM, {} temps",
+        Label::MoveUp => "Monter",
+        Label::PrevSong => "Morceau précédent",
+        Label::NextSong => "Morceau suivant",
+        Label::SwapTempoSlots => "Alterner A/B",
+        Label::RecentTempos => "Récents :",
+        Label::HalfTime => "Tempo divisé par deux",
+        Label::DoubleTime => "Tempo doublé",
+        Label::Reset => "Réinitialiser",
+        Label::WarmUpFrom => "Échauffement depuis {} BPM",
+        Label::WarmUpSecs => "sur {} s",
+        Label::GapAudible => "Jouer {} mesures",
+        Label::GapSilent => "puis {} mesures de silence",
+        Label::RampFrom => "Progression depuis {} BPM",
+        Label::DrillFrom => "Tempo aléatoire depuis {} BPM",
+        Label::RampTo => "jusqu'à {} BPM",
+        Label::RampStep => "de {} BPM",
+        Label::RampEvery => "toutes les {} mesures",
+        Label::RampLoop => "Recommencer à la fin",
+        Label::GlideTo => "Glisser vers {} BPM",
+        Label::GlideBars => "sur {} mesures",
+        Label::GlideStraight => "Linéaire",
+        Label::GlideCurved => "Adouci",
+        Label::Glide => "Glisser",
+        Label::CancelGlide => "Annuler",
+        Label::BeatsPerBar => "{} temps par mesure",
+        Label::TimeSignature => "Signature rythmique {}/{}",
+        Label::BpmCountsQuarters => "Le tempo compte des noires",
+        Label::RampNextStep => "Mesure {} sur {} avant {} BPM",
+        Label::StartOnBeat => "Commencer au temps {}",
+        Label::CountInBars => "Mesures de décompte : {}",
+        Label::BeatSoundsHint => "Cliquez sur un temps pour changer son son",
+        Label::AccentDownbeat => "Accentuer le premier temps",
+        Label::Grouping => "Groupement :",
+        Label::GroupsMustSum => "La somme des groupes doit faire {}",
+        Label::MeterSequence => "Suite de mesures :",
+        Label::MeterSequenceHint => "Longueurs de mesure de 1 à {}, séparées par des virgules",
+        Label::Subdivision => "Subdivision :",
+        Label::Swing => "Swing {} %",
+        Label::Polyrhythm => "Polyrythmie",
+        Label::PolyDivisions => "{} contre {}",
+        Label::SubdivisionNone => "Aucune",
+        Label::Eighths => "Croches",
+        Label::Triplets => "Triolets",
+        Label::Sixteenths => "Doubles croches",
+        Label::OffBeatsAfter => "Contretemps après le temps :",
+        Label::PickupOffBeat => "Contretemps avant le premier temps :",
+        Label::PickupPlay => "Jouer",
+        Label::PickupSoften => "Adoucir",
+        Label::PickupSkip => "Omettre",
+        Label::DownbeatOnly => "Premier temps seulement",
+        Label::VisualOnly => "Silencieux",
+        Label::TruncateClicks => "Raccourcir les clics",
+        Label::StartStopCues => "Signal de départ/arrêt",
+        Label::BeatProgressBar => "Barre de progression",
+        Label::BeatCounter => "Compteur de temps",
+        Label::Compact => "Compact",
+        Label::FullLayout => "Vue complète",
+        Label::VisualStyle => "Affichage :",
+        Label::BeatColor => "Couleur du temps :",
+        Label::IdleColor => "Couleur au repos :",
+        Label::ThemeColor => "Thème",
+        Label::StyleAllBeats => "Tous les temps",
+        Label::StyleDownbeatOnly => "Premier temps seulement",
+        Label::StyleSingle => "Indicateur unique",
+        Label::StyleConductor => "Battue",
+        Label::StylePendulum => "Pendule",
+        Label::Pulse => "Pulsation sur le temps",
+        Label::BigDownbeat => "Premier temps plus grand",
+        Label::StageFlash => "Faire clignoter la fenêtre",
+        Label::BackgroundGradient => "Colorer le fond au rythme de la mesure",
+        Label::KeepAwake => "Garder l’écran allumé pendant la lecture",
+        Label::AttentionOnDownbeat => {
+            "Signaler le premier temps dans le Dock ou la barre des tâches"
+        }
+        Label::BeatInTitle => "Afficher le temps dans le titre de la fenêtre",
+        Label::ShareStatus => "Partager l’état via un socket local",
+        Label::ShareStatusFailed => "Impossible de partager l’état : {}",
+        Label::Volume => "Volume :",
+        Label::Mute => "Couper le son",
+        Label::Unmute => "Rétablir le son",
+        Label::Language => "Langue :",
+        Label::DarkTheme => "Thème sombre",
+        Label::Start => "Démarrer",
+        Label::Stop => "Arrêter",
+        Label::Pause => "Pause",
+        Label::Resume => "Reprendre",
+        Label::Silence => "Silence",
+        Label::Tap => "Taper",
+        Label::About => "À propos",
+        Label::Sounds => "Sons",
+        Label::Undo => "Annuler",
+        Label::KeyboardShortcuts => "Raccourcis clavier",
+        Label::KeysHint => "Appuyez sur ? pour afficher ou masquer les raccourcis",
+        Label::PressKey => "Appuyez sur une touche…",
+        Label::ChangeKey => "Modifier",
+        Label::ActionToggle => "Démarrer/arrêter",
+        Label::ActionFaster => "Tempo +{}",
+        Label::ActionSlower => "Tempo −{}",
+        Label::ActionRecall => "Rappeler {}",
+        Label::Redo => "Rétablir",
+        Label::RoleDownbeat => "Premier temps",
+        Label::RoleAccent => "Accent de groupe",
+        Label::RoleBeat => "Temps",
+        Label::RoleOffBeat => "Contretemps",
+        Label::RoleSubdivision => "Subdivision",
+        Label::RoleEnd => "Signal de début/fin",
+        Label::SoundVolume => "Volume {} %",
+        Label::SoundPitch => "Hauteur {} %",
+        Label::SoundPack => "Jeu de sons :",
+        Label::OutputDevice => "Sortie audio :",
+        Label::DefaultOutputDevice => "Sortie par défaut",
+        Label::OutputDeviceMissing => "{} est déconnecté ; lecture sur la sortie par défaut",
+        Label::OutputLost => "La sortie audio a été perdue : {}",
+        Label::PlayerStopped => "Le lecteur audio ne fonctionne plus",
+        Label::ClickTrackFailed => "Impossible de démarrer la piste de clics : {}",
+        Label::ClickTrackStopped => {
+            "La sortie audio a été perdue : la piste de clics s’est arrêtée"
+        }
+        Label::PackClassic => "Classique",
+        Label::PackWoodBlock => "Wood-block",
+        Label::PackCowbell => "Cloche",
+        Label::PackBeep => "Bip numérique",
+        Label::SampleFile => "Fichier WAV ou MP3 à utiliser (vide pour le clic intégré)",
+        Label::LoadSampleFile => "Charger",
+        Label::PreviewSound => "Écouter",
+        Label::ConfigFile => "Fichier de configuration à partager",
+        Label::ExportConfig => "Exporter",
+        Label::ImportConfig => "Importer",
+        Label::ConfigExported => "Exporté",
+        Label::ConfigImported => "Importé",
+        Label::ConfigWriteFailed => "Impossible d’écrire {} : {}",
+        Label::ConfigReadFailed => "Impossible de lire {} : {}",
+        Label::NotAConfig => "Ce n’est pas une configuration de métronome exportée",
+        Label::ConfigTooNew => "Cette configuration vient d’une version plus récente du métronome",
+        Label::ExportStopped => "L’exportation s’est arrêtée de manière inattendue",
+        Label::ImportStopped => "L’importation s’est arrêtée de manière inattendue",
+        Label::Back => "Retour",
+        Label::Calibrate => "Calibrer",
+        Label::NoMicrophone => "Aucun microphone trouvé",
+        Label::MicrophoneUnavailable => "Microphone indisponible : {}",
+        Label::UnsupportedMicrophoneFormat => "Format de microphone non pris en charge {}",
+        Label::RecordingFailed => "Impossible d’enregistrer depuis le microphone : {}",
+        Label::CalibrationNoOutput => "Aucune sortie audio : {}",
+        Label::CalibrationClickFailed => "Impossible de jouer le clic : {}",
+        Label::NothingRecorded => "Le microphone n’a rien enregistré",
+        Label::ClicksNotHeard => "Clics inaudibles ; montez le volume et réessayez",
+        Label::CalibrationStopped => "Le calibrage s’est arrêté de façon inattendue",
+        Label::Listening => "Écoute…",
+        Label::LatencyOffset => "Compensation de latence : {} ms",
+        Label::SampleAccurate => "Synchronisation à l'échantillon près",
+        Label::MeasuredLatency => "Mesuré : {} ms",
+        Label::Accept => "Accepter",
+        Label::Version => "Version {}",
+        Label::AudioDevice => "Périphérique audio : {}",
+        Label::AudioUnavailable => "Périphérique audio : indisponible",
+        Label::BuiltWith => "Conçu avec iced {} et rodio {}",
+        _ => return None,
+    })
+}
+
+fn spanish(label: Label) -> Option<&'static str> {
+    Some(match label {
+        Label::Title => "Metrónomo",
+        Label::Bpm => "{} PPM",
+        Label::TempoChange => "Nuevo tempo:",
+        Label::TempoImmediate => "Inmediatamente",
+        Label::TempoNextBeat => "Siguiente pulso",
+        Label::TempoNextBar => "Siguiente compás",
+        Label::RestartBarOnTempo => "Reiniciar el compás al cambiar el tempo",
+        Label::TempoRange => "Tocado: {}–{} PPM",
+        Label::TempoRecord => "Histórico: {}–{} PPM",
+        Label::Session => "{} • {} compases • {} pulsos",
+        Label::AverageTempo => "Promedio {} BPM",
+        Label::TempoSlot => "{}: {} PPM",
+        Label::TempoStep => "Paso: {} PPM",
+        Label::TempoDecimals => "Decimales del tempo:",
+        Label::EmptyTempoSlot => "{}: —",
+        Label::StoreTempoSlot => "Fijar {}",
+        Label::PresetName => "Nombre del preajuste",
+        Label::SavePreset => "Guardar preajuste",
+        Label::Preset => "{}: {} PPM, {} tiempos",
+        Label::DeletePreset => "Eliminar",
+        Label::SongName => "Nombre de la canción",
+        Label::AddSong => "Añadir canción",
+        Label::Song => "{}. {}: {} PPM, {} pulsos",
+        Label::MoveUp => "Subir",
+        Label::PrevSong => "Canción anterior",
+        Label::NextSong => "Canción siguiente",
+        Label::SwapTempoSlots => "Alternar A/B",
+        Label::RecentTempos => "Recientes:",
+        Label::HalfTime => "Mitad de tempo",
+        Label::DoubleTime => "Doble de tempo",
+        Label::Reset => "Restablecer",
+        Label::WarmUpFrom => "Calentar desde {} PPM",
+        Label::WarmUpSecs => "en {} s",
+        Label::GapAudible => "Tocar {} compases",
+        Label::GapSilent => "y callar {} compases",
+        Label::RampFrom => "Rampa desde {} PPM",
+        Label::DrillFrom => "Tempo aleatorio desde {} PPM",
+        Label::RampTo => "hasta {} PPM",
+        Label::RampStep => "en pasos de {} PPM",
+        Label::RampEvery => "cada {} compases",
+        Label::RampLoop => "Volver a empezar al final",
+        Label::GlideTo => "Transición a {} PPM",
+        Label::GlideBars => "en {} compases",
+        Label::GlideStraight => "Lineal",
+        Label::GlideCurved => "Suave",
+        Label::Glide => "Transición",
+        Label::CancelGlide => "Cancelar",
+        Label::BeatsPerBar => "{} pulsos por compás",
+        Label::TimeSignature => "Compás {}/{}",
+        Label::BpmCountsQuarters => "El tempo cuenta negras",
+        Label::RampNextStep => "Compás {} de {} hasta {} PPM",
+        Label::StartOnBeat => "Empezar en el pulso {}",
+        Label::CountInBars => "Compases de entrada: {}",
+        Label::BeatSoundsHint => "Haz clic en un pulso para cambiar su sonido",
+        Label::AccentDownbeat => "Acentuar el primer pulso",
+        Label::Grouping => "Agrupación:",
+        Label::GroupsMustSum => "Los grupos deben sumar {}",
+        Label::MeterSequence => "Secuencia de compases:",
+        Label::MeterSequenceHint => "Longitudes de compás de 1 a {}, separadas por comas",
+        Label::Subdivision => "Subdivisión:",
+        Label::Swing => "Swing {} %",
+        Label::Polyrhythm => "Polirritmo",
+        Label::PolyDivisions => "{} contra {}",
+        Label::SubdivisionNone => "Ninguna",
+        Label::Eighths => "Corcheas",
+        Label::Triplets => "Tresillos",
+        Label::Sixteenths => "Semicorcheas",
+        Label::OffBeatsAfter => "Contratiempos tras el pulso:",
+        Label::PickupOffBeat => "Contratiempo antes del primer pulso:",
+        Label::PickupPlay => "Tocar",
+        Label::PickupSoften => "Suavizar",
+        Label::PickupSkip => "Omitir",
+        Label::DownbeatOnly => "Solo el primer pulso",
+        Label::VisualOnly => "Silencio",
+        Label::TruncateClicks => "Acortar clics",
+        Label::StartStopCues => "Aviso de inicio/parada",
+        Label::BeatProgressBar => "Barra de progreso",
+        Label::BeatCounter => "Contador de pulsos",
+        Label::Compact => "Compacto",
+        Label::FullLayout => "Vista completa",
+        Label::VisualStyle => "Mostrar:",
+        Label::BeatColor => "Color del pulso:",
+        Label::IdleColor => "Color en reposo:",
+        Label::ThemeColor => "Tema",
+        Label::StyleAllBeats => "Todos los pulsos",
+        Label::StyleDownbeatOnly => "Solo el primer pulso",
+        Label::StyleSingle => "Indicador único",
+        Label::StyleConductor => "Director",
+        Label::StylePendulum => "Péndulo",
+        Label::Pulse => "Pulsar con el pulso",
+        Label::BigDownbeat => "Primer pulso más grande",
+        Label::StageFlash => "Destellar la ventana",
+        Label::BackgroundGradient => "Colorear el fondo con el compás",
+        Label::KeepAwake => "Mantener la pantalla encendida",
+        Label::AttentionOnDownbeat => "Avisar en el Dock o la barra de tareas en el primer pulso",
+        Label::BeatInTitle => "Mostrar el pulso en el título de la ventana",
+        Label::ShareStatus => "Compartir estado por un socket local",
+        Label::ShareStatusFailed => "No se pudo compartir el estado: {}",
+        Label::Volume => "Volumen:",
+        Label::Mute => "Silenciar",
+        Label::Unmute => "Activar sonido",
+        Label::Language => "Idioma:",
+        Label::DarkTheme => "Tema oscuro",
+        Label::Start => "Iniciar",
+        Label::Stop => "Detener",
+        Label::Pause => "Pausa",
+        Label::Resume => "Reanudar",
+        Label::Silence => "Silencio",
+        Label::Tap => "Marcar",
+        Label::About => "Acerca de",
+        Label::Sounds => "Sonidos",
+        Label::Undo => "Deshacer",
+        Label::KeyboardShortcuts => "Atajos de teclado",
+        Label::KeysHint => "Pulsa ? para mostrar u ocultar los atajos",
+        Label::PressKey => "Pulsa una tecla…",
+        Label::ChangeKey => "Cambiar",
+        Label::ActionToggle => "Iniciar/detener",
+        Label::ActionFaster => "Tempo +{}",
+        Label::ActionSlower => "Tempo −{}",
+        Label::ActionRecall => "Recuperar {}",
+        Label::Redo => "Rehacer",
+        Label::RoleDownbeat => "Primer pulso",
+        Label::RoleAccent => "Acento de grupo",
+        Label::RoleBeat => "Pulso",
+        Label::RoleOffBeat => "Contratiempo",
+        Label::RoleSubdivision => "Subdivisión",
+        Label::RoleEnd => "Aviso de inicio/fin",
+        Label::SoundVolume => "Volumen {}%",
+        Label::SoundPitch => "Tono {}%",
+        Label::SoundPack => "Paquete de sonidos:",
+        Label::OutputDevice => "Dispositivo de salida:",
+        Label::DefaultOutputDevice => "Predeterminado del sistema",
+        Label::OutputDeviceMissing => {
+            "{} está desconectado; se reproduce en el predeterminado del sistema"
+        }
+        Label::PackClassic => "Clásico",
+        Label::OutputLost => "Se perdió la salida de audio: {}",
+        Label::PlayerStopped => "El reproductor de audio dejó de funcionar",
+        Label::ClickTrackFailed => "No se pudo iniciar la pista de clics: {}",
+        Label::ClickTrackStopped => "Se perdió la salida de audio: la pista de clics se detuvo",
+        Label::PackWoodBlock => "Caja china",
+        Label::PackCowbell => "Cencerro",
+        Label::PackBeep => "Pitido digital",
+        Label::SampleFile => "Archivo WAV o MP3 en su lugar (vacío para el clic integrado)",
+        Label::LoadSampleFile => "Cargar",
+        Label::PreviewSound => "Escuchar",
+        Label::ConfigFile => "Archivo de configuración para compartir",
+        Label::ExportConfig => "Exportar",
+        Label::ImportConfig => "Importar",
+        Label::ConfigExported => "Exportado",
+        Label::ConfigImported => "Importado",
+        Label::ConfigWriteFailed => "No se pudo escribir {}: {}",
+        Label::ConfigReadFailed => "No se pudo leer {}: {}",
+        Label::NotAConfig => "Eso no es una configuración de metrónomo exportada",
+        Label::ConfigTooNew => "Esa configuración es de una versión más reciente del metrónomo",
+        Label::ExportStopped => "La exportación se detuvo inesperadamente",
+        Label::ImportStopped => "La importación se detuvo inesperadamente",
+        Label::Back => "Volver",
+        Label::Calibrate => "Calibrar",
+        Label::NoMicrophone => "No se encontró ningún micrófono",
+        Label::MicrophoneUnavailable => "Micrófono no disponible: {}",
+        Label::UnsupportedMicrophoneFormat => "Formato de micrófono no compatible {}",
+        Label::RecordingFailed => "No se pudo grabar del micrófono: {}",
+        Label::CalibrationNoOutput => "Sin salida de audio: {}",
+        Label::CalibrationClickFailed => "No se pudo reproducir el clic: {}",
+        Label::NothingRecorded => "El micrófono no grabó nada",
+        Label::ClicksNotHeard => "No se oyeron los clics; sube el volumen y vuelve a intentarlo",
+        Label::CalibrationStopped => "La calibración se detuvo inesperadamente",
+        Label::Listening => "Escuchando…",
+        Label::LatencyOffset => "Compensación de latencia: {} ms",
+        Label::SampleAccurate => "Sincronía exacta por muestra",
+        Label::MeasuredLatency => "Medido: {} ms",
+        Label::Accept => "Aceptar",
+        Label::Version => "Versión {}",
+        Label::AudioDevice => "Dispositivo de audio: {}",
+        Label::AudioUnavailable => "Dispositivo de audio: no disponible",
+        Label::BuiltWith => "Hecho con iced {} y rodio {}",
+        _ => return None,
+    })
+}
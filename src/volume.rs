@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use rodio::Source;
+
+/// The output volume as `f32` bits, read by every sound while it plays.
+static LEVEL: AtomicU32 = AtomicU32::new(0x3f80_0000); // 1.0
+
+/// The gain the latest sound was played at. The next sound starts from here, so a volume
+/// change between beats glides in across them instead of jumping at the next click.
+static APPLIED: AtomicU32 = AtomicU32::new(0x3f80_0000);
+
+/// How quickly a ringing sound follows a volume change; short enough to feel instant, long
+/// enough not to click.
+const TIME_CONSTANT: f32 = 0.005;
+
+pub fn set(volume: f32) {
+    LEVEL.store(volume.to_bits(), Ordering::Relaxed);
+}
+
+fn get() -> f32 {
+    f32::from_bits(LEVEL.load(Ordering::Relaxed))
+}
+
+/// Plays `source` at the output volume, gliding to a new volume instead of jumping to it.
+pub fn smoothed<S: Source<Item = f32>>(source: S) -> Smoothed<S> {
+    let samples_per_second = source.sample_rate() as f32 * source.channels() as f32;
+    Smoothed {
+        source,
+        gain: f32::from_bits(APPLIED.load(Ordering::Relaxed)),
+        // One-pole smoothing: each sample closes this much of the gap to the target
+        step: 1.0 - (-1.0 / (TIME_CONSTANT * samples_per_second)).exp(),
+    }
+}
+
+pub struct Smoothed<S> {
+    source: S,
+    gain: f32,
+    step: f32,
+}
+
+impl<S: Source<Item = f32>> Iterator for Smoothed<S> {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let sample = self.source.next()?;
+        self.gain += (get() - self.gain) * self.step;
+        APPLIED.store(self.gain.to_bits(), Ordering::Relaxed);
+        Some(sample * self.gain)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.source.size_hint()
+    }
+}
+
+impl<S: Source<Item = f32>> Source for Smoothed<S> {
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volume_changes_glide_in_across_sounds() {
+        let click = || rodio::buffer::SamplesBuffer::new(1, 1000, vec![1.0; 100]);
+        // Whatever ran before left its own levels behind
+        LEVEL.store(1f32.to_bits(), Ordering::Relaxed);
+        APPLIED.store(1f32.to_bits(), Ordering::Relaxed);
+        smoothed(click()).for_each(drop);
+        set(0.5);
+        let samples = smoothed(click()).collect::<Vec<_>>();
+        assert!(samples[0] > 0.9);
+        assert!(samples.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert!((samples[99] - 0.5).abs() < 0.001);
+    }
+}
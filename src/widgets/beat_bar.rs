@@ -0,0 +1,97 @@
+use iced::{
+    advanced::{
+        layout::{self, Layout},
+        renderer,
+        widget::{self, Widget},
+    },
+    mouse::Cursor,
+};
+use iced::{Color, Element, Length, Rectangle, Size};
+
+/// A thin bar that fills from left to right over the course of one beat.
+pub struct BeatBar {
+    width: f32,
+    height: f32,
+    phase: f32,
+    color: Color,
+    track_color: Color,
+}
+
+impl BeatBar {
+    /// `phase` is how far through the current beat we are, from `0.0` to `1.0`.
+    pub fn new(width: f32, height: f32, phase: f32, color: Color, track_color: Color) -> Self {
+        Self {
+            width,
+            height,
+            phase: phase.clamp(0.0, 1.0),
+            color,
+            track_color,
+        }
+    }
+}
+
+pub fn beat_bar(width: f32, height: f32, phase: f32, color: Color, track_color: Color) -> BeatBar {
+    BeatBar::new(width, height, phase, color, track_color)
+}
+
+impl<Message, Renderer> Widget<Message, Renderer> for BeatBar
+where
+    Renderer: iced::advanced::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, _renderer: &Renderer, _limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(Size::new(self.width, self.height))
+    }
+
+    fn draw(
+        &self,
+        _state: &widget::Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border_radius: (self.height / 2.0).into(),
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            },
+            self.track_color,
+        );
+        if self.phase > 0.0 {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        width: bounds.width * self.phase,
+                        ..bounds
+                    },
+                    border_radius: (self.height / 2.0).into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                self.color,
+            );
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<BeatBar> for Element<'a, Message, Renderer>
+where
+    Renderer: renderer::Renderer,
+{
+    fn from(beat_bar: BeatBar) -> Self {
+        Self::new(beat_bar)
+    }
+}
@@ -3,28 +3,54 @@ use iced::{
         layout::{self, Layout},
         renderer,
         widget::{self, Widget},
+        Clipboard, Shell,
     },
+    event, mouse,
     mouse::Cursor,
 };
-use iced::{Color, Element, Length, Rectangle, Size};
+use iced::{Color, Element, Event, Length, Rectangle, Size};
 
-pub struct Circle {
+/// How far past the circle the pulse reaches at its start, as a fraction of the radius.
+const PULSE_GROWTH: f32 = 0.2;
+
+pub struct Circle<Message> {
     radius: f32,
     color: Color,
+    pulse: Option<f32>,
+    on_press: Option<Message>,
 }
 
-impl Circle {
+impl<Message> Circle<Message> {
     pub fn new(radius: f32, color: Color) -> Self {
-        Self { radius, color }
+        Self {
+            radius,
+            color,
+            pulse: None,
+            on_press: None,
+        }
+    }
+
+    /// Draws a halo that shrinks and fades over the beat; `phase` is how far through the
+    /// beat we are, from `0.0` to `1.0`. The halo spills outside the layout bounds.
+    pub fn with_pulse(mut self, phase: f32) -> Self {
+        self.pulse = Some(phase.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Sends `message` when the circle is clicked.
+    pub fn on_press(mut self, message: Message) -> Self {
+        self.on_press = Some(message);
+        self
     }
 }
 
-pub fn circle(radius: f32, color: Color) -> Circle {
+pub fn circle<Message>(radius: f32, color: Color) -> Circle<Message> {
     Circle::new(radius, color)
 }
 
-impl<Message, Renderer> Widget<Message, Renderer> for Circle
+impl<Message, Renderer> Widget<Message, Renderer> for Circle<Message>
 where
+    Message: Clone,
     Renderer: iced::advanced::Renderer,
 {
     fn width(&self) -> Length {
@@ -39,6 +65,45 @@ where
         layout::Node::new(Size::new(self.radius * 2.0, self.radius * 2.0))
     }
 
+    fn on_event(
+        &mut self,
+        _state: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        match (&self.on_press, event) {
+            (
+                Some(message),
+                Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                | Event::Touch(iced::touch::Event::FingerPressed { .. }),
+            ) if cursor.is_over(layout.bounds()) => {
+                shell.publish(message.clone());
+                event::Status::Captured
+            }
+            _ => event::Status::Ignored,
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        if self.on_press.is_some() && cursor.is_over(layout.bounds()) {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::Idle
+        }
+    }
+
     fn draw(
         &self,
         _state: &widget::Tree,
@@ -49,6 +114,29 @@ where
         _cursor: Cursor,
         _viewport: &Rectangle,
     ) {
+        if let Some(phase) = self.pulse {
+            // Fast at first and easing out, like the click it follows
+            let strength = (1.0 - phase).powi(2);
+            let radius = self.radius * (1.0 + PULSE_GROWTH * strength);
+            let center = layout.bounds().center();
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: center.x - radius,
+                        y: center.y - radius,
+                        width: radius * 2.0,
+                        height: radius * 2.0,
+                    },
+                    border_radius: radius.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                Color {
+                    a: self.color.a * 0.5 * strength,
+                    ..self.color
+                },
+            );
+        }
         renderer.fill_quad(
             renderer::Quad {
                 bounds: layout.bounds(),
@@ -61,11 +149,12 @@ where
     }
 }
 
-impl<'a, Message, Renderer> From<Circle> for Element<'a, Message, Renderer>
+impl<'a, Message, Renderer> From<Circle<Message>> for Element<'a, Message, Renderer>
 where
+    Message: Clone + 'a,
     Renderer: renderer::Renderer,
 {
-    fn from(circle: Circle) -> Self {
+    fn from(circle: Circle<Message>) -> Self {
         Self::new(circle)
     }
 }
@@ -0,0 +1,154 @@
+use iced::{
+    advanced::{
+        layout::{self, Layout},
+        renderer,
+        widget::{self, Widget},
+    },
+    mouse::Cursor,
+};
+use iced::{Color, Element, Length, Point, Rectangle, Size};
+
+/// Where each beat lands in a conducting pattern, as fractions of the widget's width and height.
+const TWO: [(f32, f32); 2] = [(0.5, 0.9), (0.55, 0.2)];
+const THREE: [(f32, f32); 3] = [(0.5, 0.9), (0.85, 0.7), (0.55, 0.15)];
+const FOUR: [(f32, f32); 4] = [(0.5, 0.9), (0.15, 0.7), (0.85, 0.7), (0.55, 0.15)];
+
+/// How far the hand rebounds between two beats, as a fraction of the height.
+const REBOUND: f32 = 0.15;
+/// Trail dots drawn between each pair of beats.
+const TRAIL: usize = 12;
+
+/// The conducting pattern for a bar of `beats`, if there is one.
+pub fn pattern(beats: u32) -> Option<&'static [(f32, f32)]> {
+    match beats {
+        2 => Some(&TWO),
+        3 => Some(&THREE),
+        4 => Some(&FOUR),
+        _ => None,
+    }
+}
+
+/// A dot that moves through a conductor's beat pattern, landing on each beat as it is played.
+pub struct Conductor {
+    size: f32,
+    pattern: &'static [(f32, f32)],
+    beat: Option<u32>,
+    phase: f32,
+    color: Color,
+    track_color: Color,
+}
+
+impl Conductor {
+    /// `beat` is the beat last played, counting from 0, or `None` while stopped; `phase` is how
+    /// far through it we are, from `0.0` to `1.0`.
+    pub fn new(
+        size: f32,
+        pattern: &'static [(f32, f32)],
+        beat: Option<u32>,
+        phase: f32,
+        color: Color,
+        track_color: Color,
+    ) -> Self {
+        Self {
+            size,
+            pattern,
+            beat,
+            phase: phase.clamp(0.0, 1.0),
+            color,
+            track_color,
+        }
+    }
+
+    /// The position `t` of the way from beat `from` to the one after it, relative to the
+    /// widget. The hand scoops up after each beat and falls into the next.
+    fn position(&self, from: usize, t: f32) -> (f32, f32) {
+        let (x0, y0) = self.pattern[from % self.pattern.len()];
+        let (x1, y1) = self.pattern[(from + 1) % self.pattern.len()];
+        let eased = t * t * (3.0 - 2.0 * t);
+        let lift = REBOUND * (std::f32::consts::PI * t).sin();
+        (x0 + (x1 - x0) * eased, y0 + (y1 - y0) * eased - lift)
+    }
+}
+
+pub fn conductor(
+    size: f32,
+    pattern: &'static [(f32, f32)],
+    beat: Option<u32>,
+    phase: f32,
+    color: Color,
+    track_color: Color,
+) -> Conductor {
+    Conductor::new(size, pattern, beat, phase, color, track_color)
+}
+
+impl<Message, Renderer> Widget<Message, Renderer> for Conductor
+where
+    Renderer: iced::advanced::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, _renderer: &Renderer, _limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(Size::new(self.size, self.size))
+    }
+
+    fn draw(
+        &self,
+        _state: &widget::Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let mut dot = |(x, y): (f32, f32), radius: f32, color: Color| {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle::new(
+                        Point::new(
+                            bounds.x + x * bounds.width - radius,
+                            bounds.y + y * bounds.height - radius,
+                        ),
+                        Size::new(radius * 2.0, radius * 2.0),
+                    ),
+                    border_radius: radius.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                color,
+            );
+        };
+        for from in 0..self.pattern.len() {
+            for step in 1..TRAIL {
+                dot(
+                    self.position(from, step as f32 / TRAIL as f32),
+                    2.0,
+                    self.track_color,
+                );
+            }
+            dot(self.pattern[from], 6.0, self.track_color);
+        }
+        let hand = match self.beat {
+            Some(beat) => self.position(beat as usize, self.phase),
+            // Waiting at the top, ready for the downbeat
+            None => self.pattern[self.pattern.len() - 1],
+        };
+        dot(hand, 12.0, self.color);
+    }
+}
+
+impl<'a, Message, Renderer> From<Conductor> for Element<'a, Message, Renderer>
+where
+    Renderer: renderer::Renderer,
+{
+    fn from(conductor: Conductor) -> Self {
+        Self::new(conductor)
+    }
+}
@@ -0,0 +1,142 @@
+use iced::{
+    advanced::{
+        layout::{self, Layout},
+        renderer,
+        widget::{self, Widget},
+    },
+    mouse::Cursor,
+};
+use iced::{Color, Element, Length, Rectangle, Size};
+
+/// The gap between neighbouring bars.
+const GAP: f32 = 2.0;
+
+/// A small bar chart of `counts`, each bar as tall as its count against the largest, on a
+/// baseline.
+pub struct Histogram {
+    width: f32,
+    height: f32,
+    counts: Vec<u32>,
+    color: Color,
+    track_color: Color,
+    /// A bar drawn in a color of its own.
+    highlight: Option<(usize, Color)>,
+}
+
+impl Histogram {
+    pub fn new(
+        width: f32,
+        height: f32,
+        counts: Vec<u32>,
+        color: Color,
+        track_color: Color,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            counts,
+            color,
+            track_color,
+            highlight: None,
+        }
+    }
+
+    /// Draws bar `index` in `color` instead, to make it stand out.
+    pub fn with_highlight(mut self, index: usize, color: Color) -> Self {
+        self.highlight = Some((index, color));
+        self
+    }
+}
+
+pub fn histogram(
+    width: f32,
+    height: f32,
+    counts: Vec<u32>,
+    color: Color,
+    track_color: Color,
+) -> Histogram {
+    Histogram::new(width, height, counts, color, track_color)
+}
+
+impl<Message, Renderer> Widget<Message, Renderer> for Histogram
+where
+    Renderer: iced::advanced::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, _renderer: &Renderer, _limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(Size::new(self.width, self.height))
+    }
+
+    fn draw(
+        &self,
+        _state: &widget::Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let mut quad = |bounds: Rectangle, color: Color| {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds,
+                    border_radius: 1.0.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                color,
+            );
+        };
+        let baseline = 2.0;
+        quad(
+            Rectangle {
+                y: bounds.y + bounds.height - baseline,
+                height: baseline,
+                ..bounds
+            },
+            self.track_color,
+        );
+        let most = self.counts.iter().copied().max().unwrap_or(0);
+        if most == 0 {
+            return;
+        }
+        let slot = bounds.width / self.counts.len() as f32;
+        let tallest = bounds.height - baseline;
+        for (i, &count) in self.counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let height = tallest * count as f32 / most as f32;
+            quad(
+                Rectangle {
+                    x: bounds.x + slot * i as f32 + GAP / 2.0,
+                    y: bounds.y + tallest - height,
+                    width: (slot - GAP).max(1.0),
+                    height,
+                },
+                match self.highlight {
+                    Some((index, color)) if index == i => color,
+                    _ => self.color,
+                },
+            );
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<Histogram> for Element<'a, Message, Renderer>
+where
+    Renderer: renderer::Renderer,
+{
+    fn from(histogram: Histogram) -> Self {
+        Self::new(histogram)
+    }
+}
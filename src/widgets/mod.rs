@@ -1,3 +1,15 @@
+pub mod beat_bar;
 pub mod circle;
+pub mod conductor;
+pub mod histogram;
+pub mod pendulum;
+pub mod repeat;
+pub mod wrap;
 
+pub use beat_bar::*;
 pub use circle::*;
+pub use conductor::conductor;
+pub use histogram::histogram;
+pub use pendulum::pendulum;
+pub use repeat::repeat;
+pub use wrap::*;
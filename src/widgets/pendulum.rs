@@ -0,0 +1,144 @@
+use iced::{
+    advanced::{
+        layout::{self, Layout},
+        renderer,
+        widget::{self, Widget},
+    },
+    mouse::Cursor,
+};
+use iced::{Color, Element, Length, Point, Rectangle, Size};
+
+/// How far the arm swings either side of upright, in radians.
+const MAX_ANGLE: f32 = 0.5;
+/// The arm's length, as a fraction of the height.
+const ARM: f32 = 0.8;
+/// Dots the arm is drawn with, and the arc marking its swing.
+const ARM_DOTS: usize = 16;
+const ARC_DOTS: usize = 15;
+
+/// A metronome arm that swings from one side to the other over each beat, reaching the far
+/// side just as the next beat plays.
+pub struct Pendulum {
+    size: f32,
+    /// Where the arm is, from `-1.0` all the way left to `1.0` all the way right.
+    position: f32,
+    color: Color,
+    track_color: Color,
+}
+
+impl Pendulum {
+    /// `rightwards` is which way the arm swings through the current beat, or `None` while
+    /// stopped, when it stands upright; `phase` is how far through the beat we are, from
+    /// `0.0` to `1.0`.
+    pub fn new(
+        size: f32,
+        rightwards: Option<bool>,
+        phase: f32,
+        color: Color,
+        track_color: Color,
+    ) -> Self {
+        // Slowing towards each end like a real pendulum
+        let swing = -(std::f32::consts::PI * phase.clamp(0.0, 1.0)).cos();
+        let position = match rightwards {
+            Some(true) => swing,
+            Some(false) => -swing,
+            None => 0.0,
+        };
+        Self {
+            size,
+            position,
+            color,
+            track_color,
+        }
+    }
+}
+
+pub fn pendulum(
+    size: f32,
+    rightwards: Option<bool>,
+    phase: f32,
+    color: Color,
+    track_color: Color,
+) -> Pendulum {
+    Pendulum::new(size, rightwards, phase, color, track_color)
+}
+
+impl<Message, Renderer> Widget<Message, Renderer> for Pendulum
+where
+    Renderer: iced::advanced::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, _renderer: &Renderer, _limits: &layout::Limits) -> layout::Node {
+        layout::Node::new(Size::new(self.size, self.size))
+    }
+
+    fn draw(
+        &self,
+        _state: &widget::Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        _style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let pivot = Point::new(
+            bounds.x + bounds.width * 0.5,
+            bounds.y + bounds.height * 0.95,
+        );
+        // The point `length` of the height out along the arm at `angle` from upright
+        let along = |angle: f32, length: f32| {
+            let reach = length * bounds.height;
+            Point::new(pivot.x + reach * angle.sin(), pivot.y - reach * angle.cos())
+        };
+        let mut dot = |center: Point, radius: f32, color: Color| {
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle::new(
+                        Point::new(center.x - radius, center.y - radius),
+                        Size::new(radius * 2.0, radius * 2.0),
+                    ),
+                    border_radius: radius.into(),
+                    border_width: 0.0,
+                    border_color: Color::TRANSPARENT,
+                },
+                color,
+            );
+        };
+        for step in 0..ARC_DOTS {
+            let t = step as f32 / (ARC_DOTS - 1) as f32;
+            dot(
+                along(MAX_ANGLE * (2.0 * t - 1.0), ARM),
+                2.0,
+                self.track_color,
+            );
+        }
+        let angle = MAX_ANGLE * self.position;
+        for step in 0..ARM_DOTS {
+            dot(
+                along(angle, ARM * step as f32 / ARM_DOTS as f32),
+                2.5,
+                self.color,
+            );
+        }
+        dot(along(angle, ARM), 10.0, self.color);
+        dot(pivot, 5.0, self.track_color);
+    }
+}
+
+impl<'a, Message, Renderer> From<Pendulum> for Element<'a, Message, Renderer>
+where
+    Renderer: renderer::Renderer,
+{
+    fn from(pendulum: Pendulum) -> Self {
+        Self::new(pendulum)
+    }
+}
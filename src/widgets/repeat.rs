@@ -0,0 +1,206 @@
+use std::time::{Duration, Instant};
+
+use iced::{
+    advanced::{
+        layout::{self, Layout},
+        renderer,
+        widget::{self, tree, Operation, Widget},
+        Clipboard, Shell,
+    },
+    event,
+    mouse::{self, Cursor},
+    touch, window,
+};
+use iced::{Element, Event, Length, Rectangle};
+
+/// How long a press is held before it starts repeating.
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+/// Time between repeats once they've started.
+const REPEAT_INTERVAL: Duration = Duration::from_millis(80);
+
+/// Sends its message when pressed, then again and again for as long as it's held down.
+///
+/// The content is drawn and hovered as usual but never sees presses itself, so a `button`
+/// inside can keep its own `on_press` for its enabled look without firing twice.
+pub struct Repeat<'a, Message, Renderer> {
+    content: Element<'a, Message, Renderer>,
+    on_press: Message,
+}
+
+impl<'a, Message, Renderer> Repeat<'a, Message, Renderer> {
+    pub fn new(content: impl Into<Element<'a, Message, Renderer>>, on_press: Message) -> Self {
+        Self {
+            content: content.into(),
+            on_press,
+        }
+    }
+}
+
+pub fn repeat<'a, Message, Renderer>(
+    content: impl Into<Element<'a, Message, Renderer>>,
+    on_press: Message,
+) -> Repeat<'a, Message, Renderer> {
+    Repeat::new(content, on_press)
+}
+
+/// When the held press repeats next, or `None` while it isn't held.
+#[derive(Default)]
+struct State {
+    next: Option<Instant>,
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Repeat<'a, Message, Renderer>
+where
+    Message: Clone,
+    Renderer: iced::advanced::Renderer,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn children(&self) -> Vec<widget::Tree> {
+        vec![widget::Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut widget::Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content))
+    }
+
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(renderer, limits)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, renderer, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+            | Event::Touch(touch::Event::FingerPressed { .. })
+                if cursor.is_over(layout.bounds()) =>
+            {
+                shell.publish(self.on_press.clone());
+                let next = Instant::now() + REPEAT_DELAY;
+                state.next = Some(next);
+                shell.request_redraw(window::RedrawRequest::At(next));
+                return event::Status::Captured;
+            }
+            // Letting go anywhere stops it, even after straying off the content
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
+            | Event::Touch(touch::Event::FingerLifted { .. } | touch::Event::FingerLost { .. })
+                if state.next.is_some() =>
+            {
+                state.next = None;
+                return event::Status::Captured;
+            }
+            Event::Window(window::Event::RedrawRequested(now)) => {
+                if let Some(next) = state.next {
+                    let next = if now >= next {
+                        shell.publish(self.on_press.clone());
+                        now + REPEAT_INTERVAL
+                    } else {
+                        next
+                    };
+                    state.next = Some(next);
+                    shell.request_redraw(window::RedrawRequest::At(next));
+                }
+            }
+            _ => {}
+        }
+        match event {
+            // The content would act on presses too, so it only gets to see everything else
+            Event::Mouse(mouse::Event::ButtonPressed(_) | mouse::Event::ButtonReleased(_))
+            | Event::Touch(_) => event::Status::Ignored,
+            event => self.content.as_widget_mut().on_event(
+                &mut tree.children[0],
+                event,
+                layout,
+                cursor,
+                renderer,
+                clipboard,
+                shell,
+                viewport,
+            ),
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &widget::Tree,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor,
+            viewport,
+        );
+    }
+}
+
+impl<'a, Message, Renderer> From<Repeat<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Message: Clone + 'a,
+    Renderer: renderer::Renderer + 'a,
+{
+    fn from(repeat: Repeat<'a, Message, Renderer>) -> Self {
+        Self::new(repeat)
+    }
+}
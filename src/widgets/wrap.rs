@@ -0,0 +1,232 @@
+use iced::{
+    advanced::{
+        layout::{self, Layout},
+        overlay, renderer,
+        widget::{self, Operation, Widget},
+        Clipboard, Shell,
+    },
+    event,
+    mouse::{self, Cursor},
+};
+use iced::{Element, Event, Length, Point, Rectangle, Size};
+
+/// Lays children out left to right, starting a new centered line whenever the next child
+/// would overflow the available width. A child is never split, so a group of beats kept in
+/// one `row` wraps as a unit.
+pub struct Wrap<'a, Message, Renderer> {
+    children: Vec<Element<'a, Message, Renderer>>,
+    spacing: f32,
+    line_spacing: f32,
+}
+
+impl<'a, Message, Renderer> Wrap<'a, Message, Renderer> {
+    pub fn new(children: Vec<Element<'a, Message, Renderer>>) -> Self {
+        Self {
+            children,
+            spacing: 0.0,
+            line_spacing: 0.0,
+        }
+    }
+
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+}
+
+pub fn wrap<'a, Message, Renderer>(
+    children: Vec<Element<'a, Message, Renderer>>,
+) -> Wrap<'a, Message, Renderer> {
+    Wrap::new(children)
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Wrap<'a, Message, Renderer>
+where
+    Renderer: iced::advanced::Renderer,
+{
+    fn children(&self) -> Vec<widget::Tree> {
+        self.children.iter().map(widget::Tree::new).collect()
+    }
+
+    fn diff(&self, tree: &mut widget::Tree) {
+        tree.diff_children(&self.children)
+    }
+
+    fn width(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let max_width = limits.max().width;
+        let child_limits = limits.loose();
+        let mut nodes: Vec<layout::Node> = self
+            .children
+            .iter()
+            .map(|child| child.as_widget().layout(renderer, &child_limits))
+            .collect();
+
+        // Split into lines of (first index, end index, width, height)
+        let mut lines = Vec::new();
+        let mut start = 0;
+        let mut line_width = 0.0_f32;
+        let mut line_height = 0.0_f32;
+        for (i, node) in nodes.iter().enumerate() {
+            let size = node.size();
+            let next_width = if i == start {
+                size.width
+            } else {
+                line_width + self.spacing + size.width
+            };
+            if i > start && next_width > max_width {
+                lines.push((start, i, line_width, line_height));
+                start = i;
+                line_width = size.width;
+                line_height = size.height;
+            } else {
+                line_width = next_width;
+                line_height = line_height.max(size.height);
+            }
+        }
+        if start < nodes.len() {
+            lines.push((start, nodes.len(), line_width, line_height));
+        }
+
+        let width = lines.iter().fold(0.0_f32, |w, line| w.max(line.2));
+        let mut y = 0.0;
+        for (i, &(start, end, line_width, line_height)) in lines.iter().enumerate() {
+            if i > 0 {
+                y += self.line_spacing;
+            }
+            let mut x = (width - line_width) / 2.0;
+            for node in &mut nodes[start..end] {
+                let size = node.size();
+                node.move_to(Point::new(x, y + (line_height - size.height) / 2.0));
+                x += size.width + self.spacing;
+            }
+            y += line_height;
+        }
+
+        layout::Node::with_children(Size::new(width, y), nodes)
+    }
+
+    fn operate(
+        &self,
+        tree: &mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+        operation: &mut dyn Operation<Message>,
+    ) {
+        operation.container(None, layout.bounds(), &mut |operation| {
+            self.children
+                .iter()
+                .zip(&mut tree.children)
+                .zip(layout.children())
+                .for_each(|((child, state), layout)| {
+                    child
+                        .as_widget()
+                        .operate(state, layout, renderer, operation);
+                })
+        });
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut widget::Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        viewport: &Rectangle,
+    ) -> event::Status {
+        self.children
+            .iter_mut()
+            .zip(&mut tree.children)
+            .zip(layout.children())
+            .map(|((child, state), layout)| {
+                child.as_widget_mut().on_event(
+                    state,
+                    event.clone(),
+                    layout,
+                    cursor,
+                    renderer,
+                    clipboard,
+                    shell,
+                    viewport,
+                )
+            })
+            .fold(event::Status::Ignored, event::Status::merge)
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &widget::Tree,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.children
+            .iter()
+            .zip(&tree.children)
+            .zip(layout.children())
+            .map(|((child, state), layout)| {
+                child
+                    .as_widget()
+                    .mouse_interaction(state, layout, cursor, viewport, renderer)
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    fn draw(
+        &self,
+        tree: &widget::Tree,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: Cursor,
+        viewport: &Rectangle,
+    ) {
+        for ((child, state), layout) in self
+            .children
+            .iter()
+            .zip(&tree.children)
+            .zip(layout.children())
+        {
+            child
+                .as_widget()
+                .draw(state, renderer, theme, style, layout, cursor, viewport);
+        }
+    }
+
+    fn overlay<'b>(
+        &'b mut self,
+        tree: &'b mut widget::Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        overlay::from_children(&mut self.children, tree, layout, renderer)
+    }
+}
+
+impl<'a, Message, Renderer> From<Wrap<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Message: 'a,
+    Renderer: renderer::Renderer + 'a,
+{
+    fn from(wrap: Wrap<'a, Message, Renderer>) -> Self {
+        Self::new(wrap)
+    }
+}